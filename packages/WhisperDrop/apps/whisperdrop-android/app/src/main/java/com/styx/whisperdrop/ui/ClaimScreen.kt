@@ -229,11 +229,14 @@ if (method == ClaimMethod.ESCROW) {
   Spacer(Modifier.height(8.dp))
   val campaignIdB64 = remember { mutableStateOf("") }
   val recipient58 = remember { mutableStateOf("") }
+  val nonceHex = remember { mutableStateOf("") }
   OutlinedTextField(value = campaignIdB64.value, onValueChange = { campaignIdB64.value = it }, modifier = Modifier.fillMaxWidth(), label = { Text("campaignId (32-byte b64url)") })
   Spacer(Modifier.height(8.dp))
   OutlinedTextField(value = recipient58.value, onValueChange = { recipient58.value = it }, modifier = Modifier.fillMaxWidth(), label = { Text("recipient pubkey (base58)") })
   Spacer(Modifier.height(8.dp))
-  Button(onClick = { vm.checkClaimStatusEscrow(campaignIdB64.value.trim(), recipient58.value.trim()) }) { Text("Run Escrow Check") }
+  OutlinedTextField(value = nonceHex.value, onValueChange = { nonceHex.value = it }, modifier = Modifier.fillMaxWidth(), label = { Text("ticket nonce (16-byte hex)") })
+  Spacer(Modifier.height(8.dp))
+  Button(onClick = { vm.checkClaimStatusEscrow(campaignIdB64.value.trim(), recipient58.value.trim(), nonceHex.value.trim()) }) { Text("Run Escrow Check") }
 }
 
 if (statusErr.isNotBlank()) Text(statusErr, color = MaterialTheme.colorScheme.error, style = MaterialTheme.typography.bodySmall)
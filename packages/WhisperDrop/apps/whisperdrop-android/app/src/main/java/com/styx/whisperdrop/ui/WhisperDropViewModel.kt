@@ -233,7 +233,7 @@ fun checkClaimStatusLite(nullifierB64Url: String) {
   }
 }
 
-fun checkClaimStatusEscrow(campaignIdB64Url: String, recipientBase58: String) {
+fun checkClaimStatusEscrow(campaignIdB64Url: String, recipientBase58: String, nonceHex16: String) {
   claimStatusError.value = ""
   claimStatus.value = ""
   try {
@@ -246,7 +246,8 @@ fun checkClaimStatusEscrow(campaignIdB64Url: String, recipientBase58: String) {
     val (campaignPda32, _) = SolanaPda.findProgramAddress(listOf("campaign".toByteArray(), campaignId32), programId32)
 
     val recipient32 = Base58.decode(recipientBase58).also { require(it.size == 32) { "Recipient must decode to 32 bytes" } }
-    val (nullifierPda32, _) = SolanaPda.findProgramAddress(listOf("nullifier".toByteArray(), campaignPda32, recipient32), programId32)
+    val nonce16 = Hex.decode(nonceHex16).also { require(it.size == 16) { "nonce must be 16 bytes hex (32 chars)" } }
+    val (nullifierPda32, _) = SolanaPda.findProgramAddress(listOf("nullifier".toByteArray(), campaignPda32, recipient32, nonce16), programId32)
     val nullifierPda58 = Base58.encode(nullifierPda32)
 
     val info = SolanaRpc.getAccountInfo(rpc, nullifierPda58).toString()
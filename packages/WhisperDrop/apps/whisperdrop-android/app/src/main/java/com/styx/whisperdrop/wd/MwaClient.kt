@@ -130,11 +130,9 @@ suspend fun sendEscrowClaim(
     val (escrowPda32, _) = SolanaPda.findProgramAddress(listOf("escrow".toByteArray(), campaignPda32), programId32)
 
     val recipient32 = Base58.decode(recipientBase58).also { require(it.size == 32) { "recipient must be 32 bytes" } }
-    val (nullifierPda32, _) = SolanaPda.findProgramAddress(listOf("nullifier".toByteArray(), campaignPda32, recipient32), programId32)
 
     val campaignPda = SolanaPublicKey(Base58.encode(campaignPda32))
     val escrowPda = SolanaPublicKey(Base58.encode(escrowPda32))
-    val nullifierPda = SolanaPublicKey(Base58.encode(nullifierPda32))
     val mint = SolanaPublicKey(mintBase58)
     val recipient = SolanaPublicKey(recipientBase58)
 
@@ -153,6 +151,9 @@ suspend fun sendEscrowClaim(
       require(it.length == 32) { "nonceHex16 must be 16 bytes hex (32 chars)" }
       Hex.decode(it)
     }
+    // One nullifier per leaf, seeded with its nonce
+    val (nullifierPda32, _) = SolanaPda.findProgramAddress(listOf("nullifier".toByteArray(), campaignPda32, recipient32, nonce), programId32)
+    val nullifierPda = SolanaPublicKey(Base58.encode(nullifierPda32))
     val proof32 = proofB64Url.map { p ->
       Base64Url.decode(p).also { b -> require(b.size == 32) { "proof node must be 32 bytes" } }
     }
@@ -209,6 +210,7 @@ suspend fun sendInitCampaign(
   merkleRootB64Url: String,
   mintBase58: String,
   expiryUnix: Long,
+  claimDeadlineUnix: Long? = null,
   identityUri: String = "https://boobiesinteractive.com",
   iconPath: String = "favicon.ico",
   identityName: String = "WhisperDrop"
@@ -246,6 +248,7 @@ suspend fun sendInitCampaign(
       .bytes(manifestHash32)
       .bytes(merkleRoot32)
       .u64(expiryUnix) // anchor uses i64; u64 is fine for non-negative
+      .u64(claimDeadlineUnix ?: expiryUnix) // defaults to expiry (no claim grace)
       .toByteArray()
 
     val ix = TransactionInstruction(
@@ -4,7 +4,7 @@
 //! Revolutionary privacy features for Solana:
 //!
 //! 🔐 **ENCRYPTION & FORWARD SECRECY**
-//! - ChaCha20-Poly1305 AEAD encryption
+//! - ChaCha20-Poly1305 AEAD encryption (AES-256-GCM selectable per message)
 //! - Double Ratchet-inspired ephemeral keys
 //! - Per-message key derivation (forward secrecy)
 //! - Key rotation with ratchet chains
@@ -21,6 +21,9 @@
 //! - Confidential memos with transfers
 //! - Cross-program invocation support
 //!
+//! 🔑 **KEY DISCOVERY**
+//! - Per-owner keybundle PDA, rotatable by its owner
+//!
 //! 📋 **COMPLIANCE (OPTIONAL)**
 //! - Auditor key support
 //! - Selective disclosure proofs
@@ -28,19 +31,29 @@
 //! - Regulatory hooks (opt-in)
 //!
 //! Wire formats designed for maximum privacy while enabling optional compliance.
+//!
+//! Every successful instruction emits exactly one `[MEMO_EVENT_TAG, borsh(MemoEvent)]`
+//! `sol_log_data` frame. That frame is the stable machine-readable output;
+//! the `msg!` lines are for humans and may change.
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    clock::Clock,
     entrypoint,
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
+    rent::Rent,
     system_instruction,
+    sysvar::Sysvar,
 };
+use aes_gcm::Aes256Gcm;
 use chacha20poly1305::{
-    aead::{Aead, NewAead},
+    aead::{Aead, NewAead, Payload},
     ChaCha20Poly1305, Nonce, Key,
 };
 use sha2::{Sha256, Digest};
@@ -55,6 +68,11 @@ const TAG_ROUTED_MESSAGE: u8 = 4;        // Multi-hop routed message
 const TAG_PRIVATE_TRANSFER: u8 = 5;      // Private token transfer
 const TAG_RATCHET_MESSAGE: u8 = 7;       // Forward-secret message
 const TAG_COMPLIANCE_REVEAL: u8 = 8;     // Compliance disclosure
+const TAG_DECRYPT_VERIFY: u8 = 9;        // On-chain AEAD tag check
+const TAG_RATCHET_ADVANCE: u8 = 10;      // Ratchet chain step
+const TAG_REGISTER_KEYBUNDLE: u8 = 11;   // Publish/rotate an owner's keybundle
+const TAG_PRIVATE_MESSAGE_WIDE: u8 = 12; // Private message with a u32 payload_len
+const TAG_PRIVATE_TRANSFER_DRY_RUN: u8 = 13; // Private transfer, checks only
 
 // ============================================================================
 // FLAGS
@@ -62,24 +80,93 @@ const TAG_COMPLIANCE_REVEAL: u8 = 8;     // Compliance disclosure
 
 const FLAG_ENCRYPT: u8 = 0b0000_0001;
 const FLAG_STEALTH: u8 = 0b0000_0010;
+const FLAG_AEAD_METADATA: u8 = 0b0000_0100;
+const FLAG_SHARED_KEY_V3: u8 = 0b0000_1000;
 const FLAG_COMPLIANCE_ENABLED: u8 = 0b0001_0000;
+/// A `[cipher:1]` byte follows the flags; without it the cipher is ChaCha20-Poly1305
+const FLAG_CIPHER_SELECT: u8 = 0b0010_0000;
+/// A `[msg_counter:8]` follows (after any cipher byte) and keys the payload nonce
+const FLAG_MSG_COUNTER: u8 = 0b0100_0000;
+/// Private message / DecryptVerify: seal the payload with `message_aad` as
+/// associated data
+const FLAG_HEADER_AAD: u8 = 0b1000_0000;
+
+// ============================================================================
+// CIPHERS
+// ============================================================================
+
+/// ChaCha20-Poly1305, the default and the cheaper of the two on BPF
+pub const CIPHER_CHACHA20_POLY1305: u8 = 0;
+/// AES-256-GCM, for deployments that need a FIPS-approved cipher
+pub const CIPHER_AES256_GCM: u8 = 1;
 
 // ============================================================================
 // CONSTANTS
 // ============================================================================
 
-/// Maximum number of hops for routed messages
-const MAX_HOPS: usize = 5;
+/// Hard ceiling on the per-session `max_hops` carried by routed messages.
+///
+/// Each hop is a separate instruction that decrypts the whole layered payload
+/// once, and every layer adds a 16-byte Poly1305 tag, so deeper routes cost
+/// more compute per hop and leave less room for the message in a 1232-byte
+/// transaction. 3-5 hops is plenty for most deployments.
+const MAX_HOPS_CEILING: usize = 16;
 
 /// Key derivation domains
 const RATCHET_CHAIN_DOMAIN: &[u8] = b"STYX_RATCHET_CHAIN_V1";
 const RATCHET_MESSAGE_DOMAIN: &[u8] = b"STYX_RATCHET_MSG_V1";
 const TRANSFER_DOMAIN: &[u8] = b"STYX_TRANSFER_V1";
+const MSG_NONCE_DOMAIN: &[u8] = b"STYX_MSG_NONCE_V3";
+const MSG_COUNTER_NONCE_DOMAIN: &[u8] = b"STYX_MSG_NONCE_V4";
+const AMOUNT_NONCE_DOMAIN: &[u8] = b"STYX_AMOUNT_NONCE_V1";
+
+/// PDA seed for one-time private transfer nullifiers: [seed, sender, amount_nonce]
+const SEED_TRANSFER_NULLIFIER: &[u8] = b"transfer_nullifier";
+
+/// PDA seed for an owner's published keybundle: [seed, owner]
+pub const SEED_KEYBUNDLE: &[u8] = b"keybundle";
+
+/// Custom error: the transfer nullifier already exists (replayed transfer)
+const ERR_ALREADY_SPENT: u32 = 1;
+/// Custom error: a compliance reveal was submitted before its `reveal_after_unix`
+const ERR_REVEAL_LOCKED: u32 = 2;
+const SHARED_KEY_DOMAIN: &[u8] = b"STYX_SHARED_KEY_V3";
+const METADATA_AEAD_DOMAIN: &[u8] = b"STYX_METADATA_AEAD_V4";
+const ROUTE_HOP_KEY_DOMAIN: &[u8] = b"STYX_ROUTE_HOP_KEY_V1";
+const ROUTE_HOP_NONCE_DOMAIN: &[u8] = b"STYX_ROUTE_HOP_NONCE_V1";
+const DISCLOSURE_DOMAIN: &[u8] = b"STYX_DISCLOSURE_V1";
+const SESSION_ID_DOMAIN: &[u8] = b"STYX_SESSION_ID_V1";
+
+// ============================================================================
+// EVENTS
+// ============================================================================
+
+/// First field of every event frame, so indexers can tell events apart from payload frames
+pub const MEMO_EVENT_TAG: &[u8] = b"STYX_EVT";
+
+/// Canonical per-instruction event, Borsh-encoded after `MEMO_EVENT_TAG`
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum MemoEvent {
+    PrivateMessage { flags: u8, payload_len: u32, auditor_count: u8 },
+    Routed { hop: u8, hop_count: u8, is_final: bool, payload_len: u32 },
+    Transfer { executed: bool, memo_len: u32 },
+    Ratchet { counter: u64, ciphertext_len: u32 },
+    Compliance { auditor: Pubkey, reveal_type: u8 },
+    DecryptVerify { plaintext_len: u32 },
+    RatchetAdvance { counter: u64, commitment: [u8; 32] },
+    Keybundle { owner: Pubkey, bundle_len: u32, rotated: bool },
+}
+
+fn emit_event(event: &MemoEvent) -> ProgramResult {
+    let bytes = borsh::to_vec(event).map_err(|_| ProgramError::BorshIoError("MemoEvent".into()))?;
+    solana_program::log::sol_log_data(&[MEMO_EVENT_TAG, &bytes]);
+    Ok(())
+}
 
 entrypoint!(process_instruction);
 
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -88,11 +175,15 @@ pub fn process_instruction(
     }
 
     match instruction_data[0] {
-        TAG_PRIVATE_MESSAGE => process_private_message(instruction_data),
+        TAG_PRIVATE_MESSAGE | TAG_PRIVATE_MESSAGE_WIDE => process_private_message(accounts, instruction_data),
         TAG_ROUTED_MESSAGE => process_routed_message(instruction_data),
-        TAG_PRIVATE_TRANSFER => process_private_transfer(accounts, instruction_data),
+        TAG_PRIVATE_TRANSFER => process_private_transfer(program_id, accounts, instruction_data, false),
+        TAG_PRIVATE_TRANSFER_DRY_RUN => process_private_transfer(program_id, accounts, instruction_data, true),
         TAG_RATCHET_MESSAGE => process_ratchet_message(instruction_data),
         TAG_COMPLIANCE_REVEAL => process_compliance_reveal(instruction_data),
+        TAG_DECRYPT_VERIFY => process_decrypt_verify(instruction_data),
+        TAG_RATCHET_ADVANCE => process_ratchet_advance(instruction_data),
+        TAG_REGISTER_KEYBUNDLE => process_register_keybundle(program_id, accounts, instruction_data),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -101,7 +192,7 @@ pub fn process_instruction(
 // CRYPTOGRAPHIC PRIMITIVES
 // ============================================================================
 
-/// Derive encryption key from two pubkeys
+/// Derive encryption key from two pubkeys (legacy: order-dependent, no domain)
 fn derive_shared_key(a: &Pubkey, b: &Pubkey) -> [u8; 32] {
     let mut hasher = Sha256::new();
     hasher.update(a.as_ref());
@@ -113,6 +204,32 @@ fn derive_shared_key(a: &Pubkey, b: &Pubkey) -> [u8; 32] {
     key
 }
 
+/// Role-independent shared key (selected by FLAG_SHARED_KEY_V3)
+///
+/// key = SHA256(SHARED_KEY_DOMAIN || min(a, b) || max(a, b))
+fn derive_shared_key_v3(a: &Pubkey, b: &Pubkey) -> [u8; 32] {
+    let (lo, hi) = if a.as_ref() <= b.as_ref() { (a, b) } else { (b, a) };
+
+    let mut hasher = Sha256::new();
+    hasher.update(SHARED_KEY_DOMAIN);
+    hasher.update(lo.as_ref());
+    hasher.update(hi.as_ref());
+    let result = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&result);
+    key
+}
+
+/// Message key for `flags`: the legacy ordered hash, or the sorted v3 key
+fn message_key(flags: u8, sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
+    if (flags & FLAG_SHARED_KEY_V3) != 0 {
+        derive_shared_key_v3(sender, recipient)
+    } else {
+        derive_shared_key(sender, recipient)
+    }
+}
+
 /// Derive nonce from key material
 fn derive_nonce(domain: &[u8], material: &[u8]) -> [u8; 12] {
     let mut hasher = Sha256::new();
@@ -125,23 +242,54 @@ fn derive_nonce(domain: &[u8], material: &[u8]) -> [u8; 12] {
     nonce
 }
 
+/// Payload nonce for a private message.
+///
+/// Without a counter the nonce depends only on `encrypted_recipient`, so two
+/// messages to the same recipient under the same key reuse a ChaCha20/AES-GCM
+/// nonce, which leaks the XOR of the plaintexts and lets anyone forge tags.
+/// Senders should set `FLAG_MSG_COUNTER` and never repeat a counter per key.
+fn message_nonce(encrypted_recipient: &[u8], counter: Option<u64>) -> [u8; 12] {
+    match counter {
+        Some(counter) => derive_message_nonce(encrypted_recipient, counter),
+        None => derive_nonce(MSG_NONCE_DOMAIN, encrypted_recipient),
+    }
+}
+
+/// Nonce for a private message sent with `FLAG_MSG_COUNTER`.
+///
+/// nonce = SHA256("STYX_MSG_NONCE_V4" || encrypted_recipient || counter_le64)[..12]
+///
+/// `counter` must be unique per (sender, recipient) key; a per-pair counter
+/// that only ever increases is enough.
+pub fn derive_message_nonce(encrypted_recipient: &[u8], counter: u64) -> [u8; 12] {
+    let mut hasher = Sha256::new();
+    hasher.update(MSG_COUNTER_NONCE_DOMAIN);
+    hasher.update(encrypted_recipient);
+    hasher.update(counter.to_le_bytes());
+    let result = hasher.finalize();
+
+    result[..12].try_into().unwrap()
+}
+
 /// Ratchet key derivation - produces next chain key and message key
-#[allow(dead_code)]
+///
+/// next_chain = SHA256(RATCHET_CHAIN_DOMAIN   || chain_key || counter_le64 || 0x01)
+/// msg_key    = SHA256(RATCHET_MESSAGE_DOMAIN || chain_key || counter_le64 || 0x02)
 fn ratchet_derive(chain_key: &[u8; 32], counter: u64) -> ([u8; 32], [u8; 32]) {
     // Derive next chain key
     let mut hasher = Sha256::new();
     hasher.update(RATCHET_CHAIN_DOMAIN);
     hasher.update(chain_key);
-    hasher.update(&counter.to_le_bytes());
-    hasher.update(&[0x01]); // Chain key marker
+    hasher.update(counter.to_le_bytes());
+    hasher.update([0x01]); // Chain key marker
     let next_chain = hasher.finalize();
     
     // Derive message key
     let mut hasher = Sha256::new();
     hasher.update(RATCHET_MESSAGE_DOMAIN);
     hasher.update(chain_key);
-    hasher.update(&counter.to_le_bytes());
-    hasher.update(&[0x02]); // Message key marker
+    hasher.update(counter.to_le_bytes());
+    hasher.update([0x02]); // Message key marker
     let msg_key = hasher.finalize();
     
     let mut next_chain_arr = [0u8; 32];
@@ -152,7 +300,67 @@ fn ratchet_derive(chain_key: &[u8; 32], counter: u64) -> ([u8; 32], [u8; 32]) {
     (next_chain_arr, msg_key_arr)
 }
 
-/// Encrypt recipient metadata
+/// Per-hop onion layer key and nonce
+///
+/// key   = SHA256(ROUTE_HOP_KEY_DOMAIN || session_id || hop_index)
+/// nonce = SHA256(ROUTE_HOP_NONCE_DOMAIN || session_id || hop_index)[..12]
+fn derive_hop_key(session_id: &[u8; 32], hop_index: u8) -> ([u8; 32], [u8; 12]) {
+    let mut hasher = Sha256::new();
+    hasher.update(ROUTE_HOP_KEY_DOMAIN);
+    hasher.update(session_id);
+    hasher.update([hop_index]);
+    let result = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&result);
+
+    let mut material = [0u8; 33];
+    material[..32].copy_from_slice(session_id);
+    material[32] = hop_index;
+
+    (key, derive_nonce(ROUTE_HOP_NONCE_DOMAIN, &material))
+}
+
+/// Session id carried by routed and ratchet messages
+///
+/// session_id = SHA256(SESSION_ID_DOMAIN || initiator || responder || epoch_le64)
+///
+/// Both parties know who opened the session, so each rederives the same id
+/// from the pair and `epoch`; a new epoch starts a fresh session (and fresh
+/// hop keys). The handlers treat the id as opaque and cannot check it.
+pub fn derive_session_id(initiator: &Pubkey, responder: &Pubkey, epoch: u64) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(SESSION_ID_DOMAIN);
+    hasher.update(initiator.as_ref());
+    hasher.update(responder.as_ref());
+    hasher.update(epoch.to_le_bytes());
+    hasher.finalize().into()
+}
+
+/// Client-side: wrap `final_payload` in `hop_count` ChaCha20-Poly1305 layers
+/// for a `TAG_ROUTED_MESSAGE` route, hop 0's layer outermost.
+///
+/// Send the result with `current_hop = 0`; each hop's instruction carries the
+/// inner payload the previous hop logged, and the instruction with
+/// `current_hop == hop_count` logs `final_payload` itself. Layer keys depend
+/// only on `session_id` and the hop index (see `derive_hop_key`).
+pub fn build_onion(session_id: &[u8; 32], hop_count: u8, final_payload: &[u8]) -> Result<Vec<u8>, ProgramError> {
+    let mut onion = final_payload.to_vec();
+    for hop in (0..hop_count).rev() {
+        let (key, nonce) = derive_hop_key(session_id, hop);
+        onion = encrypt_payload(CIPHER_CHACHA20_POLY1305, &key, &nonce, &onion, &[])?;
+    }
+    Ok(onion)
+}
+
+/// Remove the layer for `hop_index`, exactly as the routed-message handler
+/// does at that hop.
+pub fn peel_onion(session_id: &[u8; 32], hop_index: u8, layer: &[u8]) -> Result<Vec<u8>, ProgramError> {
+    let (key, nonce) = derive_hop_key(session_id, hop_index);
+    decrypt_payload(CIPHER_CHACHA20_POLY1305, &key, &nonce, layer, &[])
+}
+
+/// Encrypt recipient metadata (legacy v3 XOR format, kept so old messages parse)
 #[allow(dead_code)]
 fn encrypt_metadata(sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
     let mut hasher = Sha256::new();
@@ -170,7 +378,7 @@ fn encrypt_metadata(sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
     encrypted
 }
 
-/// Decrypt recipient metadata
+/// Decrypt recipient metadata (legacy v3 XOR format)
 fn decrypt_metadata(sender: &Pubkey, encrypted: &[u8; 32]) -> Pubkey {
     let mut hasher = Sha256::new();
     hasher.update(b"STYX_METADATA_KEY_V3");
@@ -186,7 +394,102 @@ fn decrypt_metadata(sender: &Pubkey, encrypted: &[u8; 32]) -> Pubkey {
     Pubkey::new_from_array(decrypted)
 }
 
-/// Derive transfer obfuscation key
+/// Seal recipient metadata with ChaCha20-Poly1305 under a per-message nonce,
+/// giving the `[sealed_recipient:48]` half of a `FLAG_AEAD_METADATA` field
+///
+/// key = SHA256(METADATA_AEAD_DOMAIN || sender); output is recipient(32) || tag(16).
+/// See `metadata_aead_key`: this binds the recipient, it does not hide it.
+pub fn seal_metadata(sender: &Pubkey, recipient: &Pubkey, nonce: &[u8; 12]) -> Result<Vec<u8>, ProgramError> {
+    encrypt_payload(CIPHER_CHACHA20_POLY1305, &metadata_aead_key(sender), nonce, recipient.as_ref(), &[])
+}
+
+/// Open sealed recipient metadata, failing if it was tampered with
+fn open_metadata(sender: &Pubkey, nonce: &[u8; 12], sealed: &[u8]) -> Result<Pubkey, ProgramError> {
+    let recipient = decrypt_payload(CIPHER_CHACHA20_POLY1305, &metadata_aead_key(sender), nonce, sealed, &[])?;
+    let recipient: [u8; 32] = recipient
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    Ok(Pubkey::new_from_array(recipient))
+}
+
+/// Recipient behind a private message's recipient field: `[metadata_nonce:12]
+/// [sealed_recipient:48]` with FLAG_AEAD_METADATA, else the legacy XOR field
+fn open_recipient(flags: u8, sender: &Pubkey, field: &[u8]) -> Result<Pubkey, ProgramError> {
+    if (flags & FLAG_AEAD_METADATA) != 0 {
+        let nonce: [u8; 12] = field[..12]
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        open_metadata(sender, &nonce, &field[12..]).inspect_err(|_| {
+            msg!("ERROR: Recipient metadata failed authentication");
+        })
+    } else {
+        let legacy: [u8; 32] = field
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        Ok(decrypt_metadata(sender, &legacy))
+    }
+}
+
+/// Length of a private message's recipient field for `flags`
+fn recipient_field_len(flags: u8) -> usize {
+    if (flags & FLAG_AEAD_METADATA) != 0 { 12 + 48 } else { 32 }
+}
+
+/// Key for sealed recipient metadata.
+///
+/// Derived from the sender's public key alone, so anyone can recompute it and
+/// open the field. The AEAD tag gives integrity and binds the recipient to the
+/// message; it gives no confidentiality.
+fn metadata_aead_key(sender: &Pubkey) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(METADATA_AEAD_DOMAIN);
+    hasher.update(sender.as_ref());
+    let result = hasher.finalize();
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&result);
+    key
+}
+
+/// `amount_nonce` for a private transfer from `sender` to `recipient`.
+///
+/// The amount is masked by XOR with a hash of `(sender, recipient, nonce)`, so
+/// reusing a triple reveals `amount XOR amount'` to anyone watching. Feed a
+/// per-pair counter that only ever increases to get a fresh nonce each time.
+pub fn derive_amount_nonce(sender: &Pubkey, recipient: &Pubkey, counter: u64) -> [u8; 8] {
+    let mut hasher = Sha256::new();
+    hasher.update(AMOUNT_NONCE_DOMAIN);
+    hasher.update(sender.as_ref());
+    hasher.update(recipient.as_ref());
+    hasher.update(counter.to_le_bytes());
+    let result = hasher.finalize();
+
+    result[..8].try_into().unwrap()
+}
+
+/// Auditor key for one `reveal_type`, scoped from the sender's disclosure master.
+///
+/// The sender seals each section of the disclosure under its own scoped key:
+/// 0 (full) the whole message plaintext, 1 (amount) the transfer amount,
+/// 2 (recipient) the recipient pubkey, 3 (metadata) the remaining message
+/// metadata. One scoped key says nothing about the others or the master.
+/// Returns `None` for a `reveal_type` above 3.
+///
+/// Client-side only: the master must never leave the sender. Anything put in
+/// instruction data is public once the transaction lands, so a compliance
+/// reveal carries the scoped key computed here, never the master.
+pub fn derive_disclosure_key(master: &[u8; 32], reveal_type: u8) -> Option<[u8; 32]> {
+    if reveal_type > 3 {
+        return None;
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(DISCLOSURE_DOMAIN);
+    hasher.update([reveal_type]);
+    hasher.update(master);
+    Some(hasher.finalize().into())
+}
+
+/// Derive transfer obfuscation key; see [`derive_amount_nonce`] on nonce reuse
 fn derive_transfer_mask(sender: &Pubkey, recipient: &Pubkey, amount_nonce: &[u8; 8]) -> u64 {
     let mut hasher = Sha256::new();
     hasher.update(TRANSFER_DOMAIN);
@@ -202,88 +505,249 @@ fn derive_transfer_mask(sender: &Pubkey, recipient: &Pubkey, amount_nonce: &[u8;
 // ENCRYPTION/DECRYPTION
 // ============================================================================
 
-fn encrypt_payload(key: &[u8; 32], nonce: &[u8; 12], plaintext: &[u8]) -> Result<Vec<u8>, ProgramError> {
+/// Cipher byte of a message: the one after the flags if `FLAG_CIPHER_SELECT` is set
+fn read_cipher(flags: u8, data: &[u8], offset: &mut usize) -> Result<u8, ProgramError> {
+    if (flags & FLAG_CIPHER_SELECT) == 0 {
+        return Ok(CIPHER_CHACHA20_POLY1305);
+    }
+    let cipher = data.get(*offset).copied().ok_or(ProgramError::InvalidInstructionData)?;
+    *offset += 1;
+    Ok(cipher)
+}
+
+/// Message counter of a message: the 8 bytes after the cipher byte if `FLAG_MSG_COUNTER` is set
+fn read_msg_counter(flags: u8, data: &[u8], offset: &mut usize) -> Result<Option<u64>, ProgramError> {
+    if (flags & FLAG_MSG_COUNTER) == 0 {
+        return Ok(None);
+    }
+    let bytes = read_bytes(data, offset, 8)?;
+    Ok(Some(u64::from_le_bytes(bytes.try_into().unwrap())))
+}
+
+/// Associated data for a private-message payload
+///
+/// With FLAG_HEADER_AAD: aad = flags || sender || encrypted_recipient, where
+/// `encrypted_recipient` is the field as sent (32 bytes, or 60 with
+/// FLAG_AEAD_METADATA). Decryptors must rebuild it from the same bytes, so a
+/// ciphertext replayed under another sender, recipient or flags byte fails
+/// authentication. Without the flag the AAD is empty, as in earlier messages.
+fn message_aad(flags: u8, sender: &Pubkey, encrypted_recipient: &[u8]) -> Vec<u8> {
+    if (flags & FLAG_HEADER_AAD) == 0 {
+        return Vec::new();
+    }
+    let mut aad = Vec::with_capacity(1 + 32 + encrypted_recipient.len());
+    aad.push(flags);
+    aad.extend_from_slice(sender.as_ref());
+    aad.extend_from_slice(encrypted_recipient);
+    aad
+}
+
+/// AEAD-seal `plaintext` with `algo` (`CIPHER_*`); output is ciphertext || tag(16)
+fn encrypt_payload(
+    algo: u8,
+    key: &[u8; 32],
+    nonce: &[u8; 12],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, ProgramError> {
     let cipher_key = Key::from_slice(key);
-    let cipher = ChaCha20Poly1305::new(cipher_key);
     let cipher_nonce = Nonce::from_slice(nonce);
-    
-    cipher.encrypt(cipher_nonce, plaintext)
-        .map_err(|_| ProgramError::InvalidInstructionData)
+    let plaintext = Payload { msg: plaintext, aad };
+
+    match algo {
+        CIPHER_CHACHA20_POLY1305 => ChaCha20Poly1305::new(cipher_key).encrypt(cipher_nonce, plaintext),
+        CIPHER_AES256_GCM => Aes256Gcm::new(cipher_key).encrypt(cipher_nonce, plaintext),
+        _ => {
+            msg!("ERROR: unknown cipher {}", algo);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    }
+    .map_err(|_| ProgramError::InvalidInstructionData)
 }
 
-#[allow(dead_code)]
-fn decrypt_payload(key: &[u8; 32], nonce: &[u8; 12], ciphertext: &[u8]) -> Result<Vec<u8>, ProgramError> {
+fn decrypt_payload(
+    algo: u8,
+    key: &[u8; 32],
+    nonce: &[u8; 12],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, ProgramError> {
     let cipher_key = Key::from_slice(key);
-    let cipher = ChaCha20Poly1305::new(cipher_key);
     let cipher_nonce = Nonce::from_slice(nonce);
-    
-    cipher.decrypt(cipher_nonce, ciphertext)
-        .map_err(|_| ProgramError::InvalidInstructionData)
+    let ciphertext = Payload { msg: ciphertext, aad };
+
+    match algo {
+        CIPHER_CHACHA20_POLY1305 => ChaCha20Poly1305::new(cipher_key).decrypt(cipher_nonce, ciphertext),
+        CIPHER_AES256_GCM => Aes256Gcm::new(cipher_key).decrypt(cipher_nonce, ciphertext),
+        _ => {
+            msg!("ERROR: unknown cipher {}", algo);
+            return Err(ProgramError::InvalidInstructionData);
+        }
+    }
+    .map_err(|_| ProgramError::InvalidInstructionData)
 }
 
 // ============================================================================
 // INSTRUCTION HANDLERS
 // ============================================================================
 
+/// State of an initialized SPL token account.
+fn token_account_state(account: &AccountInfo) -> Result<spl_token::state::Account, ProgramError> {
+    if account.owner != &spl_token::id() {
+        msg!("ERROR: {:?} is not an SPL token account", account.key);
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    spl_token::state::Account::unpack(&account.data.borrow())
+}
+
+/// Fail unless `key` is among `accounts` and signed the transaction.
+fn require_signer(accounts: &[AccountInfo], key: &Pubkey) -> ProgramResult {
+    if accounts.iter().any(|a| a.key == key && a.is_signer) {
+        Ok(())
+    } else {
+        msg!("ERROR: sender {} did not sign", key);
+        Err(ProgramError::MissingRequiredSignature)
+    }
+}
+
+/// Create the PDA `target` with `space` bytes, owned by `program_id` and funded by `payer`.
+///
+/// `create_account` fails on an address that already holds lamports, and a
+/// PDA's address is predictable, so one transfer to it would block creation
+/// for good. A pre-funded account is instead topped up to rent exemption,
+/// then allocated and assigned under `seeds`.
+fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    target: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    program_id: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    if target.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, target.key, lamports, space as u64, program_id),
+            &[payer.clone(), target.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let top_up = lamports.saturating_sub(target.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, target.key, top_up),
+            &[payer.clone(), target.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, program_id),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+/// Length prefix at `offset`: a `u16`, or a `u32` when `wide`. The offset
+/// arithmetic is checked so a crafted length cannot wrap past the bounds check.
+fn read_len(data: &[u8], offset: &mut usize, wide: bool) -> Result<usize, ProgramError> {
+    let width = if wide { 4 } else { 2 };
+    let end = offset.checked_add(width).ok_or(ProgramError::InvalidInstructionData)?;
+    let field = data.get(*offset..end).ok_or(ProgramError::InvalidInstructionData)?;
+    *offset = end;
+    Ok(if wide {
+        u32::from_le_bytes([field[0], field[1], field[2], field[3]]) as usize
+    } else {
+        u16::from_le_bytes([field[0], field[1]]) as usize
+    })
+}
+
+/// The `len` bytes at `offset`, advancing past them.
+fn read_bytes<'a>(data: &'a [u8], offset: &mut usize, len: usize) -> Result<&'a [u8], ProgramError> {
+    let end = offset.checked_add(len).ok_or(ProgramError::InvalidInstructionData)?;
+    let bytes = data.get(*offset..end).ok_or(ProgramError::InvalidInstructionData)?;
+    *offset = end;
+    Ok(bytes)
+}
+
 /// Process enhanced private message (backward compatible with v2 + new features)
-fn process_private_message(data: &[u8]) -> ProgramResult {
+fn process_private_message(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     // Wire format:
     // [tag:1] [flags:1] [encrypted_recipient:32] [sender:32] [payload_len:2] [payload:var]
+    // Accounts: [sender (signer)]
+    // TAG_PRIVATE_MESSAGE_WIDE is identical except for a [payload_len:4], for
+    // payloads past 65535 bytes.
+    // If FLAG_CIPHER_SELECT, a [cipher:1] byte (CIPHER_*) follows the flags.
+    // If FLAG_MSG_COUNTER, a [msg_counter:8] follows that; see message_nonce on
+    // why senders should always set it.
+    // If FLAG_AEAD_METADATA, the recipient field is replaced by:
+    //   [metadata_nonce:12] [sealed_recipient:48]
+    // With FLAG_ENCRYPT | FLAG_HEADER_AAD the payload is sealed with
+    // message_aad(flags, sender, recipient field) as associated data, which
+    // decryptors must rebuild byte for byte.
     // Optional (if FLAG_COMPLIANCE_ENABLED):
     //   [auditor_count:1] [auditor_pubkeys:32*n] [encrypted_disclosure:var]
-    
-    if data.len() < 1 + 1 + 32 + 32 + 2 {
-        return Err(ProgramError::InvalidInstructionData);
-    }
+    //   Each auditor is emitted as its own [auditor_pubkey, encrypted_disclosure] frame
 
-    let flags = data[1];
+    let flags = data.get(1).copied().ok_or(ProgramError::InvalidInstructionData)?;
     let encrypt = (flags & FLAG_ENCRYPT) != 0;
     let stealth = (flags & FLAG_STEALTH) != 0;
     let compliance = (flags & FLAG_COMPLIANCE_ENABLED) != 0;
+    let wide = data[0] == TAG_PRIVATE_MESSAGE_WIDE;
 
     let mut offset = 2;
+    let cipher = read_cipher(flags, data, &mut offset)?;
+    let msg_counter = read_msg_counter(flags, data, &mut offset)?;
 
-    // Parse encrypted recipient
-    let encrypted_recipient: [u8; 32] = data[offset..offset + 32]
-        .try_into()
-        .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
+    // Parse encrypted recipient (also the payload nonce material)
+    let encrypted_recipient = read_bytes(data, &mut offset, recipient_field_len(flags))?;
 
     // Parse sender
     let sender = Pubkey::new_from_array(
-        data[offset..offset + 32]
+        read_bytes(data, &mut offset, 32)?
             .try_into()
             .map_err(|_| ProgramError::InvalidInstructionData)?
     );
-    offset += 32;
+    require_signer(accounts, &sender)?;
 
     // Parse payload
-    let payload_len = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
-    offset += 2;
-
-    if data.len() < offset + payload_len {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    let payload = &data[offset..offset + payload_len];
-    offset += payload_len;
+    let payload_len = read_len(data, &mut offset, wide)?;
+    let payload = read_bytes(data, &mut offset, payload_len)?;
 
     // Decrypt recipient
-    let recipient = decrypt_metadata(&sender, &encrypted_recipient);
+    let recipient = open_recipient(flags, &sender, encrypted_recipient)?;
 
     // Process encryption
     let final_payload = if encrypt {
-        let key = derive_shared_key(&sender, &recipient);
-        let nonce = derive_nonce(b"STYX_MSG_NONCE_V3", &encrypted_recipient);
-        
-        encrypt_payload(&key, &nonce, payload)?
+        let key = message_key(flags, &sender, &recipient);
+        let nonce = message_nonce(encrypted_recipient, msg_counter);
+        let aad = message_aad(flags, &sender, encrypted_recipient);
+
+        encrypt_payload(cipher, &key, &nonce, payload, &aad)?
     } else {
         payload.to_vec()
     };
 
     // Handle compliance if enabled
+    let mut auditor_count = 0u8;
+    let mut auditors: &[u8] = &[];
+    let mut encrypted_disclosure: &[u8] = &[];
     if compliance && data.len() > offset {
-        let auditor_count = data[offset] as usize;
+        auditor_count = data[offset];
+        let auditors_len = auditor_count as usize * 32;
+        offset += 1;
+
+        auditors = read_bytes(data, &mut offset, auditors_len).inspect_err(|_| {
+            msg!("ERROR: auditor_count={} runs past instruction data", auditor_count);
+        })?;
+
+        // Disclosure is the remainder of the instruction
+        encrypted_disclosure = &data[offset..];
+
         msg!("STYX_PMP3 COMPLIANCE auditors={}", auditor_count);
     }
 
@@ -296,161 +760,289 @@ fn process_private_message(data: &[u8]) -> ProgramResult {
 
     solana_program::log::sol_log_data(&[&final_payload]);
 
-    Ok(())
+    // One frame per auditor so an indexer can match on its own key
+    for auditor in auditors.chunks_exact(32) {
+        solana_program::log::sol_log_data(&[auditor, encrypted_disclosure]);
+    }
+
+    emit_event(&MemoEvent::PrivateMessage {
+        flags,
+        payload_len: final_payload.len() as u32,
+        auditor_count,
+    })
 }
 
 /// Process multi-hop routed message (onion routing style)
 fn process_routed_message(data: &[u8]) -> ProgramResult {
     // Wire format:
-    // [tag:1] [flags:1] [hop_count:1] [session_id:32]
+    // [tag:1] [flags:1] [max_hops:1] [hop_count:1] [session_id:32]
     // [current_hop_index:1] [next_hop_encrypted:32]
     // [layered_payload_len:2] [layered_payload:var]
     //
-    // Each hop peels one layer of encryption
+    // session_id is derive_session_id(initiator, responder, epoch).
+    // max_hops is the session's routing-depth bound (<= MAX_HOPS_CEILING).
+    // Each hop peels one layer of encryption (see derive_hop_key)
     // Only the final recipient can read the message
-    
-    if data.len() < 1 + 1 + 1 + 32 + 1 + 32 + 2 {
+
+    if data.len() < 1 + 1 + 1 + 1 + 32 + 1 + 32 + 2 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
     let _flags = data[1];
-    let hop_count = data[2] as usize;
+    let max_hops = data[2] as usize;
+    let hop_count = data[3] as usize;
+
+    if max_hops > MAX_HOPS_CEILING {
+        msg!("ERROR: max_hops={} exceeds ceiling {}", max_hops, MAX_HOPS_CEILING);
+        return Err(ProgramError::InvalidInstructionData);
+    }
     
-    if hop_count > MAX_HOPS {
-        msg!("ERROR: Too many hops (max={})", MAX_HOPS);
+    if hop_count > max_hops {
+        msg!("ERROR: Too many hops (max={})", max_hops);
         return Err(ProgramError::InvalidInstructionData);
     }
 
-    let mut offset = 3;
+    let mut offset = 4;
 
     // Parse session ID
-    let _session_id: [u8; 32] = data[offset..offset + 32]
+    let session_id: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     // Current hop index
-    let current_hop = data[offset];
-    offset += 1;
+    let current_hop = read_bytes(data, &mut offset, 1)?[0];
+
+    if current_hop as usize > hop_count {
+        msg!("ERROR: Hop index out of range ({}/{})", current_hop, hop_count);
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
     // Next hop (encrypted)
-    let _next_hop_encrypted: [u8; 32] = data[offset..offset + 32]
+    let _next_hop_encrypted: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     // Layered payload
-    let payload_len = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
-    offset += 2;
-
-    if data.len() < offset + payload_len {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    let layered_payload = &data[offset..offset + payload_len];
+    let payload_len = read_len(data, &mut offset, false)?;
+    let layered_payload = read_bytes(data, &mut offset, payload_len)?;
 
     // Log minimal info (hides routing details)
     if current_hop as usize == hop_count {
         msg!("STYX_ROUTED_FINAL len={}", payload_len);
-    } else {
-        msg!("STYX_ROUTED_HOP hop={}/{}", current_hop + 1, hop_count);
+        solana_program::log::sol_log_data(&[layered_payload]);
+        return emit_event(&MemoEvent::Routed {
+            hop: current_hop,
+            hop_count: hop_count as u8,
+            is_final: true,
+            payload_len: payload_len as u32,
+        });
     }
 
-    // Emit the layered payload (next hop will peel a layer)
-    solana_program::log::sol_log_data(&[layered_payload]);
+    // Every intermediate layer carries at least a Poly1305 tag
+    if payload_len < 16 {
+        msg!("ERROR: Layered payload too short");
+        return Err(ProgramError::InvalidInstructionData);
+    }
 
-    Ok(())
+    let inner = peel_onion(&session_id, current_hop, layered_payload).inspect_err(|_| {
+        msg!("ERROR: Failed to peel layer at hop {}", current_hop);
+    })?;
+
+    msg!("STYX_ROUTED_HOP hop={}/{}", current_hop + 1, hop_count);
+
+    // Emit only the peeled inner payload for the next hop
+    solana_program::log::sol_log_data(&[&inner]);
+
+    emit_event(&MemoEvent::Routed {
+        hop: current_hop,
+        hop_count: hop_count as u8,
+        is_final: false,
+        payload_len: inner.len() as u32,
+    })
 }
 
 /// Process private token transfer with encrypted memo
 fn process_private_transfer(
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
+    dry_run: bool,
 ) -> ProgramResult {
     // Wire format:
     // [tag:1] [flags:1] [encrypted_recipient:32] [sender:32]
-    // [encrypted_amount:8] [amount_nonce:8] [memo_len:2] [encrypted_memo:var]
+    // [encrypted_amount:8] [amount_nonce:8] [max_amount:8]
+    // [memo_len:2] [encrypted_memo:var]
+    //
+    // Accounts (optional): [from (signer, == sender), to, system_program, nullifier?]
+    // SPL tokens: [from (signer), to_token_account, token_program,
+    //              from_token_account, nullifier?, system_program (with nullifier)]
+    // The token path is taken when the third account is the SPL token program;
+    // the decrypted recipient must then own `to_token_account`.
+    // If a nullifier account is passed it must be the PDA
+    // [SEED_TRANSFER_NULLIFIER, sender, amount_nonce]; it is created here
+    // (create_pda_account, so lamports sent to it beforehand do not block it)
+    // and a second transfer with the same sender/nonce fails with
+    // ERR_ALREADY_SPENT. Spent means owned by this program.
+    //
+    // Sent as TAG_PRIVATE_TRANSFER_DRY_RUN (same layout), every parse,
+    // decryption and account check still runs and the resolved
+    // recipient/amount is logged, but neither the nullifier nor the transfer
+    // CPI is issued.
     
-    if data.len() < 1 + 1 + 32 + 32 + 8 + 8 + 2 {
+    if data.len() < 1 + 1 + 32 + 32 + 8 + 8 + 8 + 2 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
-    let _flags = data[1];
+    let token = accounts.get(2).is_some_and(|a| a.key == &spl_token::id());
+    let mut unit = String::from("lamports");
     let mut offset = 2;
 
     // Parse encrypted recipient
-    let encrypted_recipient: [u8; 32] = data[offset..offset + 32]
+    let encrypted_recipient: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     // Parse sender
     let sender = Pubkey::new_from_array(
-        data[offset..offset + 32]
+        read_bytes(data, &mut offset, 32)?
             .try_into()
             .map_err(|_| ProgramError::InvalidInstructionData)?
     );
-    offset += 32;
 
     // Parse encrypted amount and nonce
     let encrypted_amount = u64::from_le_bytes(
-        data[offset..offset + 8]
+        read_bytes(data, &mut offset, 8)?
             .try_into()
             .map_err(|_| ProgramError::InvalidInstructionData)?
     );
-    offset += 8;
 
-    let amount_nonce: [u8; 8] = data[offset..offset + 8]
+    let amount_nonce: [u8; 8] = read_bytes(data, &mut offset, 8)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 8;
 
-    // Parse memo
-    let memo_len = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
-    offset += 2;
+    // Upper bound the caller is willing to send
+    let max_amount = u64::from_le_bytes(
+        read_bytes(data, &mut offset, 8)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
 
-    let encrypted_memo = if memo_len > 0 && data.len() >= offset + memo_len {
-        Some(&data[offset..offset + memo_len])
-    } else {
-        None
-    };
+    // Parse memo; a memo running past the data is dropped rather than rejected
+    let memo_len = read_len(data, &mut offset, false)?;
+    let memo_end = offset.checked_add(memo_len).ok_or(ProgramError::InvalidInstructionData)?;
+    let encrypted_memo = if memo_len > 0 { data.get(offset..memo_end) } else { None };
 
     // Decrypt recipient and amount
     let recipient = decrypt_metadata(&sender, &encrypted_recipient);
     let amount_mask = derive_transfer_mask(&sender, &recipient, &amount_nonce);
     let actual_amount = encrypted_amount ^ amount_mask;
 
+    // A wrong mask decrypts to an arbitrary u64; catch it before the CPI
+    if actual_amount == 0 {
+        msg!("ERROR: Decrypted amount is zero (mask mismatch?)");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    if actual_amount > max_amount {
+        msg!("ERROR: Decrypted amount {} exceeds max_amount {}", actual_amount, max_amount);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
     // Execute transfer via CPI (if accounts provided)
     if !accounts.is_empty() {
         let account_iter = &mut accounts.iter();
         let from_account = next_account_info(account_iter)?;
         let to_account = next_account_info(account_iter)?;
-        let system_program = next_account_info(account_iter)?;
-
-        // Verify sender
-        if !from_account.is_signer {
+        let program_account = next_account_info(account_iter)?;
+        let from_token_account = if token { Some(next_account_info(account_iter)?) } else { None };
+        let nullifier_account = account_iter.next();
+        let system_program = if token {
+            nullifier_account.map(|_| next_account_info(account_iter)).transpose()?
+        } else {
+            Some(program_account)
+        };
+
+        // Verify sender: the nullifier is seeded with `sender`, so a signer
+        // naming someone else could burn that sender's nonce ahead of them
+        if !from_account.is_signer || *from_account.key != sender {
+            msg!("ERROR: sender {} did not sign", sender);
             return Err(ProgramError::MissingRequiredSignature);
         }
 
         // Verify recipient matches decrypted value
-        if to_account.key != &recipient {
-            msg!("ERROR: Recipient mismatch - expected {:?}, got {:?}", recipient, to_account.key);
+        let to_owner = if token {
+            let state = token_account_state(to_account)?;
+            unit = format!("base units of mint {}", state.mint);
+            state.owner
+        } else {
+            *to_account.key
+        };
+        if to_owner != recipient {
+            msg!("ERROR: Recipient mismatch - expected {:?}, got {:?}", recipient, to_owner);
             return Err(ProgramError::InvalidAccountData);
         }
 
-        // Create transfer instruction - use decrypted recipient
-        let transfer_ix = system_instruction::transfer(
-            from_account.key,
-            &recipient,  // Fixed: use decrypted recipient, not to_account.key
-            actual_amount,
-        );
+        // Consume the one-time nullifier (replay protection)
+        if let Some(nullifier) = nullifier_account {
+            let (nullifier_pda, bump) = Pubkey::find_program_address(
+                &[SEED_TRANSFER_NULLIFIER, sender.as_ref(), &amount_nonce],
+                program_id,
+            );
+            if nullifier_pda != *nullifier.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            if nullifier.owner == program_id {
+                msg!("ERROR: Transfer already spent");
+                return Err(ProgramError::Custom(ERR_ALREADY_SPENT));
+            }
+
+            if !dry_run {
+                let system_program = system_program.ok_or(ProgramError::NotEnoughAccountKeys)?;
+                create_pda_account(
+                    from_account,
+                    nullifier,
+                    system_program,
+                    0,
+                    program_id,
+                    &[SEED_TRANSFER_NULLIFIER, sender.as_ref(), &amount_nonce, &[bump]],
+                )?;
+            }
+        }
 
-        invoke(
-            &transfer_ix,
-            &[from_account.clone(), to_account.clone(), system_program.clone()],
-        )?;
+        if !dry_run {
+            if let Some(from_token_account) = from_token_account {
+                let transfer_ix = spl_token::instruction::transfer(
+                    program_account.key,
+                    from_token_account.key,
+                    to_account.key,
+                    from_account.key,
+                    &[],
+                    actual_amount,
+                )?;
+
+                invoke(
+                    &transfer_ix,
+                    &[from_token_account.clone(), to_account.clone(), from_account.clone(), program_account.clone()],
+                )?;
+            } else {
+                // Create transfer instruction - use decrypted recipient
+                let transfer_ix = system_instruction::transfer(
+                    from_account.key,
+                    &recipient,  // Fixed: use decrypted recipient, not to_account.key
+                    actual_amount,
+                );
+
+                invoke(
+                    &transfer_ix,
+                    &[from_account.clone(), to_account.clone(), program_account.clone()],
+                )?;
+            }
+
+            msg!("STYX_PRIVATE_TRANSFER complete: {} {} to {:?}", actual_amount, unit, recipient);
+        }
+    }
 
-        msg!("STYX_PRIVATE_TRANSFER complete: {} lamports to {:?}", actual_amount, recipient);
+    if dry_run {
+        msg!("STYX_PRIVATE_TRANSFER dry run: {} {} to {:?}", actual_amount, unit, recipient);
     }
 
     // Log encrypted memo if present
@@ -458,7 +1050,10 @@ fn process_private_transfer(
         solana_program::log::sol_log_data(&[memo]);
     }
 
-    Ok(())
+    emit_event(&MemoEvent::Transfer {
+        executed: !accounts.is_empty() && !dry_run,
+        memo_len: encrypted_memo.map_or(0, |m| m.len() as u32),
+    })
 }
 
 /// Process forward-secret ratchet message
@@ -466,6 +1061,9 @@ fn process_ratchet_message(data: &[u8]) -> ProgramResult {
     // Wire format:
     // [tag:1] [flags:1] [session_id:32] [counter:8]
     // [ephemeral_pubkey:32] [ciphertext_len:2] [ciphertext:var]
+    //
+    // session_id is derive_session_id(initiator, responder, epoch), the same
+    // id a routed message between the pair carries.
     
     if data.len() < 1 + 1 + 32 + 8 + 32 + 2 {
         return Err(ProgramError::InvalidInstructionData);
@@ -474,86 +1072,275 @@ fn process_ratchet_message(data: &[u8]) -> ProgramResult {
     let _flags = data[1];
     let mut offset = 2;
 
-    let _session_id: [u8; 32] = data[offset..offset + 32]
+    let _session_id: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     let counter = u64::from_le_bytes(
-        data[offset..offset + 8]
+        read_bytes(data, &mut offset, 8)?
             .try_into()
             .map_err(|_| ProgramError::InvalidInstructionData)?
     );
-    offset += 8;
 
-    let _ephemeral_pubkey: [u8; 32] = data[offset..offset + 32]
+    let _ephemeral_pubkey: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
-    let ciphertext_len = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
-    offset += 2;
-
-    if data.len() < offset + ciphertext_len {
-        return Err(ProgramError::InvalidInstructionData);
-    }
-
-    let ciphertext = &data[offset..offset + ciphertext_len];
+    let ciphertext_len = read_len(data, &mut offset, false)?;
+    let ciphertext = read_bytes(data, &mut offset, ciphertext_len)?;
 
     // Log with minimal metadata (forward secrecy hides old keys)
     msg!("STYX_RATCHET_MSG counter={} len={}", counter, ciphertext.len());
 
     solana_program::log::sol_log_data(&[ciphertext]);
 
-    Ok(())
+    emit_event(&MemoEvent::Ratchet {
+        counter,
+        ciphertext_len: ciphertext.len() as u32,
+    })
 }
 
 /// Process compliance disclosure (optional audit support)
 fn process_compliance_reveal(data: &[u8]) -> ProgramResult {
     // Wire format:
     // [tag:1] [flags:1] [message_id:32] [auditor:32]
-    // [disclosure_key:32] [reveal_type:1]
+    // [scoped_key:32] [reveal_type:1] [reveal_after_unix:8]
     //
     // reveal_type: 0=full, 1=amount_only, 2=recipient_only, 3=metadata_only
+    // scoped_key is derive_disclosure_key(master, reveal_type), computed by
+    // the sender off-chain; the master itself is never sent. It is logged
+    // only once the cluster clock has reached reveal_after_unix.
+    //
+    // The time-lock gates only the log and event. scoped_key is in the
+    // instruction data, which is public as soon as the transaction lands,
+    // even if it fails with ERR_REVEAL_LOCKED; submit a reveal only once the
+    // key may be disclosed.
     
-    if data.len() < 1 + 1 + 32 + 32 + 32 + 1 {
+    if data.len() < 1 + 1 + 32 + 32 + 32 + 1 + 8 {
         return Err(ProgramError::InvalidInstructionData);
     }
 
     let _flags = data[1];
     let mut offset = 2;
 
-    let _message_id: [u8; 32] = data[offset..offset + 32]
+    let _message_id: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     let auditor = Pubkey::new_from_array(
-        data[offset..offset + 32]
+        read_bytes(data, &mut offset, 32)?
             .try_into()
             .map_err(|_| ProgramError::InvalidInstructionData)?
     );
-    offset += 32;
 
-    let disclosure_key: [u8; 32] = data[offset..offset + 32]
+    let scoped_key: [u8; 32] = read_bytes(data, &mut offset, 32)?
         .try_into()
         .map_err(|_| ProgramError::InvalidInstructionData)?;
-    offset += 32;
 
     let reveal_type = data[offset];
+    offset += 1;
+
+    let reveal_after_unix = i64::from_le_bytes(
+        read_bytes(data, &mut offset, 8)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
 
     let reveal_desc = match reveal_type {
         0 => "full",
         1 => "amount",
         2 => "recipient",
         3 => "metadata",
-        _ => "unknown",
+        _ => {
+            msg!("ERROR: unknown reveal_type={}", reveal_type);
+            return Err(ProgramError::InvalidInstructionData);
+        }
     };
 
+    let now = Clock::get()?.unix_timestamp;
+    if now < reveal_after_unix {
+        msg!("ERROR: reveal locked until {} (now {})", reveal_after_unix, now);
+        return Err(ProgramError::Custom(ERR_REVEAL_LOCKED));
+    }
+
     msg!("STYX_COMPLIANCE_REVEAL auditor={} type={}", auditor, reveal_desc);
 
-    // Emit disclosure key (auditor can use this to decrypt)
-    solana_program::log::sol_log_data(&[&disclosure_key]);
+    solana_program::log::sol_log_data(&[&scoped_key]);
 
-    Ok(())
+    emit_event(&MemoEvent::Compliance { auditor, reveal_type })
+}
+
+/// Verify that a private-message ciphertext decrypts under the sender/recipient key
+fn process_decrypt_verify(data: &[u8]) -> ProgramResult {
+    // Wire format:
+    // [tag:1] [flags:1] [encrypted_recipient:32] [sender:32] [ciphertext_len:2] [ciphertext:var]
+    // If FLAG_CIPHER_SELECT, a [cipher:1] byte follows the flags, then
+    // [msg_counter:8] if FLAG_MSG_COUNTER.
+    // If FLAG_AEAD_METADATA, the recipient field is [metadata_nonce:12]
+    // [sealed_recipient:48], as in the message.
+    //
+    // Recipient, key, nonce, cipher and AAD are derived exactly as in
+    // process_private_message, so the ciphertext it emits verifies here when
+    // sent with the message's own flags byte and recipient field (with
+    // FLAG_HEADER_AAD, bits this handler does not otherwise read are still
+    // bound). The plaintext is never logged.
+
+    let flags = data.get(1).copied().ok_or(ProgramError::InvalidInstructionData)?;
+    let mut offset = 2;
+    let cipher = read_cipher(flags, data, &mut offset)?;
+    let msg_counter = read_msg_counter(flags, data, &mut offset)?;
+
+    let encrypted_recipient = read_bytes(data, &mut offset, recipient_field_len(flags))?;
+
+    let sender = Pubkey::new_from_array(
+        read_bytes(data, &mut offset, 32)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let ciphertext_len = read_len(data, &mut offset, false)?;
+    let ciphertext = read_bytes(data, &mut offset, ciphertext_len)?;
+
+    let recipient = open_recipient(flags, &sender, encrypted_recipient)?;
+    let key = message_key(flags, &sender, &recipient);
+    let nonce = message_nonce(encrypted_recipient, msg_counter);
+
+    let aad = message_aad(flags, &sender, encrypted_recipient);
+
+    match decrypt_payload(cipher, &key, &nonce, ciphertext, &aad) {
+        Ok(plaintext) => {
+            msg!("STYX_DECRYPT_VERIFY ok len={}", plaintext.len());
+            emit_event(&MemoEvent::DecryptVerify {
+                plaintext_len: plaintext.len() as u32,
+            })
+        }
+        Err(e) => {
+            msg!("STYX_DECRYPT_VERIFY failed: authentication tag mismatch");
+            Err(e)
+        }
+    }
+}
+
+/// Advance a ratchet chain by one step and publish a commitment to the message key
+fn process_ratchet_advance(data: &[u8]) -> ProgramResult {
+    // Wire format:
+    // [tag:1] [flags:1] [chain_key:32] [prev_counter:8] [counter:8]
+    //
+    // Emits [counter_le:8] [SHA256(msg_key):32] where msg_key comes from
+    // ratchet_derive(chain_key, counter), so clients can confirm they are in
+    // sync.
+    //
+    // chain_key travels in instruction data and stays in the ledger, so it is
+    // public from then on: anyone can derive every later chain and message
+    // key from it. Only send a chain key that no longer protects anything.
+
+    if data.len() < 1 + 1 + 32 + 8 + 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let mut offset = 2;
+
+    let chain_key: [u8; 32] = read_bytes(data, &mut offset, 32)?
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    let prev_counter = u64::from_le_bytes(
+        read_bytes(data, &mut offset, 8)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    let counter = u64::from_le_bytes(
+        read_bytes(data, &mut offset, 8)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?
+    );
+
+    if counter <= prev_counter {
+        msg!("ERROR: Ratchet counter must increase (prev={}, got={})", prev_counter, counter);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (_next_chain, msg_key) = ratchet_derive(&chain_key, counter);
+    let commitment = Sha256::digest(msg_key);
+
+    msg!("STYX_RATCHET_ADVANCE counter={}", counter);
+    solana_program::log::sol_log_data(&[&counter.to_le_bytes(), &commitment]);
+
+    emit_event(&MemoEvent::RatchetAdvance {
+        counter,
+        commitment: commitment.into(),
+    })
+}
+
+/// Publish or rotate the signer's keybundle in its [SEED_KEYBUNDLE, owner] PDA
+fn process_register_keybundle(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // Wire format:
+    // [tag:1] [flags:1] [bundle_len:2] [bundle:var]
+    //
+    // Accounts: [owner (signer, writable), keybundle PDA (writable), system_program]
+    // The PDA holds [owner:32] [bundle:var]. The first call creates it (see
+    // create_pda_account, which also handles a pre-funded address); later
+    // calls by the same owner overwrite the bundle, resizing the account and
+    // topping up rent from the owner as needed.
+
+    let mut offset = 2;
+    let bundle_len = read_len(data, &mut offset, false)?;
+    let bundle = read_bytes(data, &mut offset, bundle_len)?;
+
+    let account_iter = &mut accounts.iter();
+    let owner = next_account_info(account_iter)?;
+    let keybundle = next_account_info(account_iter)?;
+    let system_program = next_account_info(account_iter)?;
+
+    if !owner.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    let (keybundle_pda, bump) = Pubkey::find_program_address(&[SEED_KEYBUNDLE, owner.key.as_ref()], program_id);
+    if keybundle_pda != *keybundle.key {
+        return Err(ProgramError::InvalidSeeds);
+    }
+
+    let space = 32 + bundle.len();
+    let rotated = keybundle.owner == program_id;
+    if rotated {
+        let lamports = Rent::get()?.minimum_balance(space);
+        if keybundle.data.borrow().get(..32) != Some(owner.key.as_ref()) {
+            msg!("ERROR: keybundle is registered to another owner");
+            return Err(ProgramError::IllegalOwner);
+        }
+        let top_up = lamports.saturating_sub(keybundle.lamports());
+        if top_up > 0 {
+            invoke(
+                &system_instruction::transfer(owner.key, keybundle.key, top_up),
+                &[owner.clone(), keybundle.clone(), system_program.clone()],
+            )?;
+        }
+        keybundle.realloc(space, false)?;
+    } else {
+        create_pda_account(
+            owner,
+            keybundle,
+            system_program,
+            space,
+            program_id,
+            &[SEED_KEYBUNDLE, owner.key.as_ref(), &[bump]],
+        )?;
+    }
+
+    let mut stored = keybundle.data.borrow_mut();
+    stored[..32].copy_from_slice(owner.key.as_ref());
+    stored[32..].copy_from_slice(bundle);
+    drop(stored);
+
+    msg!("STYX_KEYBUNDLE {} len={} rotated={}", owner.key, bundle.len(), rotated);
+    emit_event(&MemoEvent::Keybundle {
+        owner: *owner.key,
+        bundle_len: bundle.len() as u32,
+        rotated,
+    })
 }
@@ -0,0 +1,116 @@
+mod common;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{process_instruction, seal_metadata};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+const FLAG_AEAD_METADATA: u8 = 0b0000_0100;
+
+fn chacha(key: &[u8], nonce: &[u8], plaintext: &[u8]) -> Vec<u8> {
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(Nonce::from_slice(nonce), plaintext)
+        .unwrap()
+}
+
+fn seal_recipient(sender: &Pubkey, recipient: &Pubkey, nonce: &[u8; 12]) -> Vec<u8> {
+    let key = Sha256::new()
+        .chain_update(b"STYX_METADATA_AEAD_V4")
+        .chain_update(sender)
+        .finalize();
+    let mut field = nonce.to_vec();
+    field.extend(chacha(&key, nonce, recipient.as_ref()));
+    field
+}
+
+fn ix(flags: u8, recipient_field: &[u8], sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    ix_with_tag(TAG_PRIVATE_MESSAGE, flags, recipient_field, sender, payload)
+}
+
+fn ix_with_tag(tag: u8, flags: u8, recipient_field: &[u8], sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![tag, flags];
+    data.extend_from_slice(recipient_field);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+#[test]
+fn sealed_recipient_keys_the_payload() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let field = seal_recipient(&sender, &recipient, &[3u8; 12]);
+
+    let data = ix(FLAG_ENCRYPT | FLAG_AEAD_METADATA, &field, &sender, b"hello");
+    common::process_signed(&sender, &data).unwrap();
+
+    let shared = Sha256::new().chain_update(sender).chain_update(recipient).finalize();
+    let nonce = Sha256::new().chain_update(b"STYX_MSG_NONCE_V3").chain_update(&field).finalize();
+    let expected = chacha(&shared, &nonce[..12], b"hello");
+    assert_eq!(common::take_data(), vec![vec![expected]]);
+}
+
+#[test]
+fn seal_metadata_matches_the_wire_format() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let nonce = [5u8; 12];
+
+    let mut field = nonce.to_vec();
+    field.extend(seal_metadata(&sender, &recipient, &nonce).unwrap());
+    assert_eq!(field, seal_recipient(&sender, &recipient, &nonce));
+    common::process_signed(&sender, &ix(FLAG_AEAD_METADATA, &field, &sender, b"hi")).unwrap();
+}
+
+#[test]
+fn tampered_recipient_is_rejected() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    let mut field = seal_recipient(&sender, &Pubkey::new_unique(), &[3u8; 12]);
+    field[20] ^= 1;
+
+    let data = ix(FLAG_AEAD_METADATA, &field, &sender, b"hello");
+    assert_eq!(
+        common::process_signed(&sender, &data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn legacy_recipient_field_still_parses() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    let data = ix(0, &[9u8; 32], &sender, b"hello");
+    common::process_signed(&sender, &data).unwrap();
+    assert_eq!(common::take_data(), vec![vec![b"hello".to_vec()]]);
+}
+
+#[test]
+fn decrypt_verify_opens_sealed_recipient() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    let mut field = seal_recipient(&sender, &Pubkey::new_unique(), &[4u8; 12]);
+    let flags = FLAG_ENCRYPT | FLAG_AEAD_METADATA;
+    common::process_signed(&sender, &ix(flags, &field, &sender, b"hello")).unwrap();
+    let ciphertext = common::take_data().pop().unwrap().pop().unwrap();
+
+    let verify = ix_with_tag(TAG_DECRYPT_VERIFY, flags, &field, &sender, &ciphertext);
+    assert_eq!(process_instruction(&Pubkey::new_unique(), &[], &verify), Ok(()));
+
+    field[20] ^= 1;
+    let verify = ix_with_tag(TAG_DECRYPT_VERIFY, flags, &field, &sender, &ciphertext);
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &verify),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
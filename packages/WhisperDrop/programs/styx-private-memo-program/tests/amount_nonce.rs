@@ -0,0 +1,28 @@
+use std::collections::HashSet;
+
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::Pubkey;
+use styx_private_memo_program::derive_amount_nonce;
+
+#[test]
+fn distinct_counters_give_distinct_nonces() {
+    let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let nonces: HashSet<[u8; 8]> = (0..1_000).map(|c| derive_amount_nonce(&sender, &recipient, c)).collect();
+    assert_eq!(nonces.len(), 1_000);
+}
+
+#[test]
+fn nonce_is_deterministic_and_pair_bound() {
+    let (sender, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let nonce = derive_amount_nonce(&sender, &recipient, 7);
+    assert_eq!(nonce, derive_amount_nonce(&sender, &recipient, 7));
+    assert_ne!(nonce, derive_amount_nonce(&recipient, &sender, 7));
+
+    let h = Sha256::new()
+        .chain_update(b"STYX_AMOUNT_NONCE_V1")
+        .chain_update(sender)
+        .chain_update(recipient)
+        .chain_update(7u64.to_le_bytes())
+        .finalize();
+    assert_eq!(nonce[..], h[..8]);
+}
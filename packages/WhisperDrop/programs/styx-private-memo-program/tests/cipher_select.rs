@@ -0,0 +1,95 @@
+mod common;
+
+use aes_gcm::Aes256Gcm;
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{process_instruction, CIPHER_AES256_GCM, CIPHER_CHACHA20_POLY1305};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+const FLAG_SHARED_KEY_V3: u8 = 0b0000_1000;
+const FLAG_CIPHER_SELECT: u8 = 0b0010_0000;
+
+fn hide_recipient(sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = recipient.as_ref()[i] ^ key[i];
+    }
+    out
+}
+
+/// `cipher` of `None` leaves `FLAG_CIPHER_SELECT` off.
+fn ix(tag: u8, cipher: Option<u8>, sender: &Pubkey, recipient: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let flags = FLAG_ENCRYPT | FLAG_SHARED_KEY_V3 | cipher.map_or(0, |_| FLAG_CIPHER_SELECT);
+    let mut data = vec![tag, flags];
+    data.extend(cipher);
+    data.extend_from_slice(&hide_recipient(sender, recipient));
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn key_and_nonce(sender: &Pubkey, recipient: &Pubkey) -> ([u8; 32], [u8; 12]) {
+    let (lo, hi) = if sender < recipient { (sender, recipient) } else { (recipient, sender) };
+    let key = Sha256::new().chain_update(b"STYX_SHARED_KEY_V3").chain_update(lo).chain_update(hi).finalize();
+    let nonce = Sha256::new()
+        .chain_update(b"STYX_MSG_NONCE_V3")
+        .chain_update(hide_recipient(sender, recipient))
+        .finalize();
+    (key.into(), nonce[..12].try_into().unwrap())
+}
+
+fn send(cipher: Option<u8>, sender: &Pubkey, recipient: &Pubkey, plaintext: &[u8]) -> Result<Vec<u8>, ProgramError> {
+    common::setup();
+    common::process_signed(sender, &ix(TAG_PRIVATE_MESSAGE, cipher, sender, recipient, plaintext))?;
+    Ok(common::take_data().pop().unwrap().pop().unwrap())
+}
+
+fn verify(cipher: Option<u8>, sender: &Pubkey, recipient: &Pubkey, ciphertext: &[u8]) -> bool {
+    common::setup();
+    let data = ix(TAG_DECRYPT_VERIFY, cipher, sender, recipient, ciphertext);
+    process_instruction(&Pubkey::new_unique(), &[], &data).is_ok()
+}
+
+#[test]
+fn aes_gcm_round_trip() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ciphertext = send(Some(CIPHER_AES256_GCM), &alice, &bob, b"for the auditors").unwrap();
+
+    let (key, nonce) = key_and_nonce(&alice, &bob);
+    let opened = Aes256Gcm::new(Key::from_slice(&key)).decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice());
+    assert_eq!(opened.unwrap(), b"for the auditors");
+
+    assert!(verify(Some(CIPHER_AES256_GCM), &alice, &bob, &ciphertext));
+    assert!(!verify(Some(CIPHER_CHACHA20_POLY1305), &alice, &bob, &ciphertext));
+    assert!(!verify(None, &alice, &bob, &ciphertext));
+}
+
+#[test]
+fn chacha_round_trip_and_default() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let ciphertext = send(Some(CIPHER_CHACHA20_POLY1305), &alice, &bob, b"hello").unwrap();
+
+    let (key, nonce) = key_and_nonce(&alice, &bob);
+    let opened = ChaCha20Poly1305::new(Key::from_slice(&key)).decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice());
+    assert_eq!(opened.unwrap(), b"hello");
+
+    // Leaving the cipher byte out is the same as selecting ChaCha20-Poly1305.
+    assert_eq!(send(None, &alice, &bob, b"hello").unwrap(), ciphertext);
+    assert!(verify(None, &alice, &bob, &ciphertext));
+    assert!(!verify(Some(CIPHER_AES256_GCM), &alice, &bob, &ciphertext));
+}
+
+#[test]
+fn unknown_cipher_is_rejected() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    assert_eq!(send(Some(2), &alice, &bob, b"x").unwrap_err(), ProgramError::InvalidInstructionData);
+    assert!(!verify(Some(2), &alice, &bob, &[0; 17]));
+}
@@ -0,0 +1,192 @@
+//! Host-side harness: captures `msg!` / `sol_log_data` output and CPIs per test thread.
+//!
+//! System `create_account`, `transfer`, `allocate` and `assign` CPIs are
+//! applied to the target `AccountInfo` so handlers can write the new
+//! account's state. `create_account` sets lamports and zeroed data only.
+
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use styx_private_memo_program::{process_instruction, MemoEvent, MEMO_EVENT_TAG};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Log {
+    Msg(String),
+    Data(Vec<Vec<u8>>),
+}
+
+thread_local! {
+    static LOGS: RefCell<Vec<Log>> = const { RefCell::new(Vec::new()) };
+    static INVOKES: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static UNIX_TIMESTAMP: Cell<i64> = const { Cell::new(0) };
+}
+
+struct CaptureStubs;
+
+impl SyscallStubs for CaptureStubs {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|l| l.borrow_mut().push(Log::Msg(message.to_string())));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        LOGS.with(|l| l.borrow_mut().push(Log::Data(fields.iter().map(|f| f.to_vec()).collect())));
+    }
+
+    /// Records the CPI; only the system instructions above have any effect.
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id == system_program::id() {
+            let account = |i: usize| {
+                let key = &instruction.accounts[i].pubkey;
+                account_infos.iter().find(|a| a.key == key).expect("system CPI account passed")
+            };
+            let u64_at = |at: usize| u64::from_le_bytes(instruction.data[at..at + 8].try_into().unwrap());
+            match instruction.data[0] {
+                // CreateAccount { lamports, space, owner }
+                0 => {
+                    **account(1).lamports.borrow_mut() = u64_at(4);
+                    *account(1).data.borrow_mut() = Box::leak(vec![0u8; u64_at(12) as usize].into_boxed_slice());
+                }
+                // Assign { owner }
+                1 => account(0).assign(&Pubkey::try_from(&instruction.data[4..36]).unwrap()),
+                // Transfer { lamports }
+                2 => {
+                    **account(0).lamports.borrow_mut() -= u64_at(4);
+                    **account(1).lamports.borrow_mut() += u64_at(4);
+                }
+                // Allocate { space }
+                8 => *account(0).data.borrow_mut() = Box::leak(vec![0u8; u64_at(4) as usize].into_boxed_slice()),
+                _ => {}
+            }
+        }
+        INVOKES.with(|i| i.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: UNIX_TIMESTAMP.with(Cell::get),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+}
+
+/// Install the capturing stubs (once per process) and clear this thread's log,
+/// CPIs and clock.
+pub fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(CaptureStubs));
+    });
+    LOGS.with(|l| l.borrow_mut().clear());
+    INVOKES.with(|i| i.borrow_mut().clear());
+    UNIX_TIMESTAMP.with(|t| t.set(0));
+}
+
+/// Set the `Clock::unix_timestamp` this thread's handlers observe.
+pub fn set_unix_timestamp(ts: i64) {
+    UNIX_TIMESTAMP.with(|t| t.set(ts));
+}
+
+/// Run `data` with `signer` passed as the only account, marked as a signer.
+pub fn process_signed(signer: &Pubkey, data: &[u8]) -> ProgramResult {
+    let (mut lamports, mut bytes, owner) = (0u64, [0u8; 0], Pubkey::default());
+    let account = AccountInfo::new(signer, true, false, &mut lamports, &mut bytes, &owner, false, 0);
+    process_instruction(&Pubkey::new_unique(), &[account], data)
+}
+
+/// A leaked `AccountInfo` laid out like the runtime's input buffer: the
+/// original data length sits 4 bytes before the key and there are 8 spare
+/// bytes before the data, which `realloc` reads and writes.
+pub fn runtime_account(key: Pubkey, owner: Pubkey, lamports: u64, data: &[u8], is_signer: bool) -> AccountInfo<'static> {
+    let mut key_buf = (data.len() as u32).to_le_bytes().to_vec();
+    key_buf.extend_from_slice(key.as_ref());
+    let key_buf: &'static [u8] = Box::leak(key_buf.into_boxed_slice());
+    let key = unsafe { &*(key_buf[4..].as_ptr() as *const Pubkey) };
+
+    let mut data_buf = vec![0u8; 8];
+    data_buf.extend_from_slice(data);
+    // Room for `realloc` to grow in place.
+    data_buf.resize(8 + data.len() + 1024, 0);
+    let data_buf: &'static mut [u8] = Box::leak(data_buf.into_boxed_slice());
+
+    AccountInfo::new(
+        key,
+        is_signer,
+        true,
+        Box::leak(Box::new(lamports)),
+        &mut data_buf[8..8 + data.len()],
+        Box::leak(Box::new(owner)),
+        false,
+        0,
+    )
+}
+
+/// The AAD a private message sent with `FLAG_HEADER_AAD` is sealed with:
+/// `flags || sender || recipient_field`.
+pub fn message_aad(flags: u8, sender: &Pubkey, recipient_field: &[u8]) -> Vec<u8> {
+    [&[flags][..], sender.as_ref(), recipient_field].concat()
+}
+
+/// Drain the CPIs recorded on this thread.
+pub fn take_invokes() -> Vec<Instruction> {
+    INVOKES.with(|i| std::mem::take(&mut *i.borrow_mut()))
+}
+
+/// Drain the logs captured on this thread.
+pub fn take_logs() -> Vec<Log> {
+    LOGS.with(|l| std::mem::take(&mut *l.borrow_mut()))
+}
+
+fn is_event(frame: &[Vec<u8>]) -> bool {
+    frame.first().map(Vec::as_slice) == Some(MEMO_EVENT_TAG)
+}
+
+/// Every non-event `sol_log_data` frame captured on this thread, in order.
+pub fn take_data() -> Vec<Vec<Vec<u8>>> {
+    take_logs()
+        .into_iter()
+        .filter_map(|l| match l {
+            Log::Data(d) if !is_event(&d) => Some(d),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Every `MemoEvent` frame captured on this thread, decoded.
+pub fn take_events() -> Vec<MemoEvent> {
+    take_logs()
+        .into_iter()
+        .filter_map(|l| match l {
+            Log::Data(d) if is_event(&d) => {
+                assert_eq!(d.len(), 2, "event frame is [tag, borsh]");
+                Some(MemoEvent::try_from_slice(&d[1]).expect("borsh MemoEvent"))
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const FLAG_COMPLIANCE_ENABLED: u8 = 0b0001_0000;
+
+const SENDER: Pubkey = Pubkey::new_from_array([5u8; 32]);
+
+fn ix(auditor_count: u8, auditors: &[Pubkey], disclosure: &[u8]) -> Vec<u8> {
+    let mut data = vec![TAG_PRIVATE_MESSAGE, FLAG_COMPLIANCE_ENABLED];
+    data.extend_from_slice(&[9u8; 32]);
+    data.extend_from_slice(SENDER.as_ref());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(b"hi");
+    data.push(auditor_count);
+    for a in auditors {
+        data.extend_from_slice(a.as_ref());
+    }
+    data.extend_from_slice(disclosure);
+    data
+}
+
+#[test]
+fn emits_one_frame_per_auditor() {
+    common::setup();
+    let auditors = [Pubkey::new_unique(), Pubkey::new_unique()];
+    common::process_signed(&SENDER, &ix(2, &auditors, b"sealed")).unwrap();
+
+    let frames = common::take_data();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[0], vec![b"hi".to_vec()]);
+    for (frame, auditor) in frames[1..].iter().zip(auditors) {
+        assert_eq!(frame, &vec![auditor.to_bytes().to_vec(), b"sealed".to_vec()]);
+    }
+}
+
+#[test]
+fn auditor_count_past_buffer_is_rejected() {
+    common::setup();
+    let data = ix(3, &[Pubkey::new_unique(), Pubkey::new_unique()], b"");
+    assert_eq!(
+        common::process_signed(&SENDER, &data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{derive_disclosure_key, process_instruction};
+
+const TAG_COMPLIANCE_REVEAL: u8 = 8;
+const MASTER: [u8; 32] = [5u8; 32];
+const ERR_REVEAL_LOCKED: u32 = 2;
+
+fn ix(reveal_type: u8) -> Vec<u8> {
+    locked_ix(reveal_type, 0)
+}
+
+/// Carries the key scoped from `MASTER` (or a zero key for an unknown type).
+fn locked_ix(reveal_type: u8, reveal_after_unix: i64) -> Vec<u8> {
+    let mut data = vec![TAG_COMPLIANCE_REVEAL, 0];
+    data.extend_from_slice(&[4u8; 32]);
+    data.extend_from_slice(Pubkey::new_unique().as_ref());
+    data.extend_from_slice(&derive_disclosure_key(&MASTER, reveal_type).unwrap_or_default());
+    data.push(reveal_type);
+    data.extend_from_slice(&reveal_after_unix.to_le_bytes());
+    data
+}
+
+#[test]
+fn scoped_key_matches_its_derivation() {
+    for reveal_type in 0..=3u8 {
+        let expected = Sha256::new()
+            .chain_update(b"STYX_DISCLOSURE_V1")
+            .chain_update([reveal_type])
+            .chain_update(MASTER)
+            .finalize();
+        assert_eq!(derive_disclosure_key(&MASTER, reveal_type).unwrap(), expected[..], "type {reveal_type}");
+    }
+}
+
+#[test]
+fn reveal_emits_the_scoped_key_and_never_carries_the_master() {
+    for reveal_type in 0..=3u8 {
+        common::setup();
+        let data = ix(reveal_type);
+        assert!(!data.windows(32).any(|w| w == MASTER));
+        process_instruction(&Pubkey::new_unique(), &[], &data).unwrap();
+
+        let scoped = derive_disclosure_key(&MASTER, reveal_type).unwrap();
+        assert_eq!(common::take_data(), vec![vec![scoped.to_vec()]], "type {reveal_type}");
+    }
+}
+
+#[test]
+fn scoped_keys_differ_per_type() {
+    let keys: Vec<_> = (0..=3).map(|t| derive_disclosure_key(&MASTER, t).unwrap()).collect();
+    for (i, a) in keys.iter().enumerate() {
+        assert_ne!(a, &MASTER);
+        assert!(keys[i + 1..].iter().all(|b| a != b));
+    }
+}
+
+#[test]
+fn unknown_reveal_type_is_rejected() {
+    common::setup();
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &ix(4)),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert!(common::take_data().is_empty());
+    assert_eq!(derive_disclosure_key(&MASTER, 4), None);
+}
+
+#[test]
+fn reveal_is_locked_until_reveal_after_unix() {
+    const UNLOCK: i64 = 1_700_000_000;
+    let data = locked_ix(0, UNLOCK);
+
+    common::setup();
+    common::set_unix_timestamp(UNLOCK - 1);
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &data),
+        Err(ProgramError::Custom(ERR_REVEAL_LOCKED))
+    );
+    assert!(common::take_data().is_empty());
+
+    for now in [UNLOCK, UNLOCK + 1] {
+        common::setup();
+        common::set_unix_timestamp(now);
+        process_instruction(&Pubkey::new_unique(), &[], &data).unwrap();
+        assert_eq!(common::take_data(), vec![vec![derive_disclosure_key(&MASTER, 0).unwrap().to_vec()]]);
+    }
+}
+
+#[test]
+fn missing_reveal_after_is_rejected() {
+    common::setup();
+    let data = ix(0);
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &data[..data.len() - 8]),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
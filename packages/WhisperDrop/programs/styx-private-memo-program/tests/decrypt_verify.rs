@@ -0,0 +1,62 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+
+fn ix(tag: u8, flags: u8, encrypted_recipient: &[u8; 32], sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![tag, flags];
+    data.extend_from_slice(encrypted_recipient);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn encrypt_on_chain(encrypted_recipient: &[u8; 32], sender: &Pubkey, plaintext: &[u8]) -> Vec<u8> {
+    common::setup();
+    let data = ix(TAG_PRIVATE_MESSAGE, FLAG_ENCRYPT, encrypted_recipient, sender, plaintext);
+    common::process_signed(sender, &data).unwrap();
+    let frames = common::take_data();
+    frames.last().expect("ciphertext frame")[0].clone()
+}
+
+#[test]
+fn ciphertext_from_private_message_verifies() {
+    let sender = Pubkey::new_unique();
+    let encrypted_recipient = [7u8; 32];
+    let ciphertext = encrypt_on_chain(&encrypted_recipient, &sender, b"hello recipient");
+    assert_eq!(ciphertext.len(), b"hello recipient".len() + 16);
+
+    let data = ix(TAG_DECRYPT_VERIFY, 0, &encrypted_recipient, &sender, &ciphertext);
+    assert_eq!(process_instruction(&Pubkey::new_unique(), &[], &data), Ok(()));
+    assert!(common::take_logs()
+        .contains(&common::Log::Msg("STYX_DECRYPT_VERIFY ok len=15".into())));
+}
+
+#[test]
+fn tampered_ciphertext_fails_tag_check() {
+    let sender = Pubkey::new_unique();
+    let encrypted_recipient = [8u8; 32];
+    let mut ciphertext = encrypt_on_chain(&encrypted_recipient, &sender, b"hello recipient");
+    ciphertext[0] ^= 1;
+
+    let data = ix(TAG_DECRYPT_VERIFY, 0, &encrypted_recipient, &sender, &ciphertext);
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &data),
+        Err(ProgramError::InvalidInstructionData)
+    );
+}
+
+#[test]
+fn wrong_sender_fails_tag_check() {
+    let sender = Pubkey::new_unique();
+    let encrypted_recipient = [9u8; 32];
+    let ciphertext = encrypt_on_chain(&encrypted_recipient, &sender, b"hello recipient");
+
+    let data = ix(TAG_DECRYPT_VERIFY, 0, &encrypted_recipient, &Pubkey::new_unique(), &ciphertext);
+    assert!(process_instruction(&Pubkey::new_unique(), &[], &data).is_err());
+}
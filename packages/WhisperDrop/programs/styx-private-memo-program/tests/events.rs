@@ -0,0 +1,56 @@
+mod common;
+
+use solana_program::pubkey::Pubkey;
+use styx_private_memo_program::{process_instruction, MemoEvent};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_COMPLIANCE_REVEAL: u8 = 8;
+const TAG_RATCHET_MESSAGE: u8 = 7;
+
+fn run(data: &[u8]) -> Vec<MemoEvent> {
+    common::setup();
+    process_instruction(&Pubkey::new_unique(), &[], data).unwrap();
+    common::take_events()
+}
+
+#[test]
+fn private_message_emits_one_event() {
+    let sender = Pubkey::new_unique();
+    let mut data = vec![TAG_PRIVATE_MESSAGE, 0];
+    data.extend_from_slice(&[1u8; 32]);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&3u16.to_le_bytes());
+    data.extend_from_slice(b"abc");
+
+    common::setup();
+    common::process_signed(&sender, &data).unwrap();
+    assert_eq!(
+        common::take_events(),
+        vec![MemoEvent::PrivateMessage { flags: 0, payload_len: 3, auditor_count: 0 }]
+    );
+}
+
+#[test]
+fn ratchet_message_emits_counter() {
+    let mut data = vec![TAG_RATCHET_MESSAGE, 0];
+    data.extend_from_slice(&[2u8; 32]);
+    data.extend_from_slice(&7u64.to_le_bytes());
+    data.extend_from_slice(&[3u8; 32]);
+    data.extend_from_slice(&4u16.to_le_bytes());
+    data.extend_from_slice(b"ctxt");
+
+    assert_eq!(run(&data), vec![MemoEvent::Ratchet { counter: 7, ciphertext_len: 4 }]);
+}
+
+#[test]
+fn compliance_reveal_emits_auditor() {
+    let auditor = Pubkey::new_unique();
+    let mut data = vec![TAG_COMPLIANCE_REVEAL, 0];
+    data.extend_from_slice(&[4u8; 32]);
+    data.extend_from_slice(auditor.as_ref());
+    data.extend_from_slice(&[5u8; 32]);
+    data.push(1);
+    data.extend_from_slice(&0i64.to_le_bytes());
+
+    assert_eq!(run(&data), vec![MemoEvent::Compliance { auditor, reveal_type: 1 }]);
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+const FLAG_STEALTH: u8 = 0b0000_0010;
+const FLAG_HEADER_AAD: u8 = 0b1000_0000;
+
+fn hide_recipient(sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = recipient.as_ref()[i] ^ key[i];
+    }
+    out
+}
+
+fn ix(tag: u8, flags: u8, recipient_field: &[u8; 32], sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![tag, flags];
+    data.extend_from_slice(recipient_field);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn send(flags: u8, field: &[u8; 32], sender: &Pubkey, plaintext: &[u8]) -> Vec<u8> {
+    common::setup();
+    common::process_signed(sender, &ix(TAG_PRIVATE_MESSAGE, flags, field, sender, plaintext)).unwrap();
+    common::take_data().pop().unwrap().pop().unwrap()
+}
+
+fn verify(flags: u8, field: &[u8; 32], sender: &Pubkey, ciphertext: &[u8]) -> Result<(), ProgramError> {
+    common::setup();
+    process_instruction(&Pubkey::new_unique(), &[], &ix(TAG_DECRYPT_VERIFY, flags, field, sender, ciphertext))
+}
+
+#[test]
+fn payload_is_sealed_over_the_header() {
+    let sender = Pubkey::new_unique();
+    let recipient = Pubkey::new_unique();
+    let field = hide_recipient(&sender, &recipient);
+    let flags = FLAG_ENCRYPT | FLAG_HEADER_AAD;
+    let ciphertext = send(flags, &field, &sender, b"bound");
+
+    let key = Sha256::new().chain_update(sender).chain_update(recipient).finalize();
+    let nonce = Sha256::new().chain_update(b"STYX_MSG_NONCE_V3").chain_update(field).finalize();
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+    let aad = common::message_aad(flags, &sender, &field);
+    let opened = cipher.decrypt(Nonce::from_slice(&nonce[..12]), Payload { msg: &ciphertext, aad: &aad });
+    assert_eq!(opened.unwrap(), b"bound");
+
+    // Without the flag the same key and nonce seal with no AAD.
+    let unbound = send(FLAG_ENCRYPT, &field, &sender, b"bound");
+    assert_ne!(unbound, ciphertext);
+    assert_eq!(cipher.decrypt(Nonce::from_slice(&nonce[..12]), unbound.as_slice()).unwrap(), b"bound");
+}
+
+#[test]
+fn decrypt_verify_rebuilds_the_header_aad() {
+    let sender = Pubkey::new_unique();
+    let field = hide_recipient(&sender, &Pubkey::new_unique());
+    let flags = FLAG_ENCRYPT | FLAG_HEADER_AAD;
+    let ciphertext = send(flags, &field, &sender, b"bound");
+
+    assert_eq!(verify(flags, &field, &sender, &ciphertext), Ok(()));
+    assert_eq!(verify(FLAG_ENCRYPT, &field, &sender, &ciphertext), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn tampered_header_fails_tag_check() {
+    let sender = Pubkey::new_unique();
+    let field = hide_recipient(&sender, &Pubkey::new_unique());
+    let flags = FLAG_ENCRYPT | FLAG_HEADER_AAD;
+    let ciphertext = send(flags, &field, &sender, b"bound");
+
+    // Same key and nonce, different flags byte: only the AAD changes.
+    assert_eq!(
+        verify(flags | FLAG_STEALTH, &field, &sender, &ciphertext),
+        Err(ProgramError::InvalidInstructionData)
+    );
+    assert!(verify(flags, &field, &Pubkey::new_unique(), &ciphertext).is_err());
+}
@@ -0,0 +1,151 @@
+mod common;
+
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_program,
+};
+use styx_private_memo_program::{process_instruction, MemoEvent, SEED_KEYBUNDLE};
+
+const TAG_REGISTER_KEYBUNDLE: u8 = 11;
+
+fn ix(bundle: &[u8]) -> Vec<u8> {
+    let mut data = vec![TAG_REGISTER_KEYBUNDLE, 0];
+    data.extend_from_slice(&(bundle.len() as u16).to_le_bytes());
+    data.extend_from_slice(bundle);
+    data
+}
+
+fn pda(program_id: &Pubkey, owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[SEED_KEYBUNDLE, owner.as_ref()], program_id).0
+}
+
+/// `[owner, keybundle, system_program]` with the keybundle account as given.
+fn keybundle_accounts(signer: &Pubkey, keybundle: AccountInfo<'static>) -> [AccountInfo<'static>; 3] {
+    [
+        common::runtime_account(*signer, system_program::id(), 1_000_000_000, &[], true),
+        keybundle,
+        common::runtime_account(system_program::id(), system_program::id(), 1, &[], false),
+    ]
+}
+
+fn stored(program_id: &Pubkey, owner: &Pubkey, bundle: &[u8]) -> AccountInfo<'static> {
+    let mut data = owner.to_bytes().to_vec();
+    data.extend_from_slice(bundle);
+    let lamports = Rent::default().minimum_balance(data.len());
+    common::runtime_account(pda(program_id, owner), *program_id, lamports, &data, false)
+}
+
+#[test]
+fn register_creates_the_pda_and_stores_the_bundle() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let fresh = common::runtime_account(pda(&program_id, &owner), system_program::id(), 0, &[], false);
+    let accounts = keybundle_accounts(&owner, fresh);
+
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(b"bundle-v1")).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(invokes[0].accounts[1].pubkey, *accounts[1].key);
+    let data = accounts[1].data.borrow();
+    assert_eq!(&data[..32], owner.as_ref());
+    assert_eq!(&data[32..], b"bundle-v1");
+    drop(data);
+
+    process_instruction(&program_id, &keybundle_accounts(&owner, stored(&program_id, &owner, b"x")), &ix(b"y")).unwrap();
+    assert_eq!(
+        common::take_events(),
+        vec![
+            MemoEvent::Keybundle { owner, bundle_len: 9, rotated: false },
+            MemoEvent::Keybundle { owner, bundle_len: 1, rotated: true },
+        ]
+    );
+}
+
+#[test]
+fn owner_can_rotate_to_a_larger_bundle() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let accounts = keybundle_accounts(&owner, stored(&program_id, &owner, b"old"));
+
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(b"a much longer rotated bundle")).unwrap();
+
+    let data = accounts[1].data.borrow();
+    assert_eq!(&data[..32], owner.as_ref());
+    assert_eq!(&data[32..], b"a much longer rotated bundle");
+    // The owner pays the extra rent; nothing is re-created.
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(invokes[0].accounts[0].pubkey, owner);
+}
+
+#[test]
+fn owner_can_rotate_to_a_smaller_bundle() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let accounts = keybundle_accounts(&owner, stored(&program_id, &owner, b"a fairly long original bundle"));
+
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(b"short")).unwrap();
+    assert_eq!(&accounts[1].data.borrow()[32..], b"short");
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn someone_else_cannot_overwrite() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let attacker = Pubkey::new_unique();
+
+    // The attacker's signature does not derive the owner's PDA.
+    let accounts = keybundle_accounts(&attacker, stored(&program_id, &owner, b"owner bundle"));
+    common::setup();
+    assert_eq!(process_instruction(&program_id, &accounts, &ix(b"evil")), Err(ProgramError::InvalidSeeds));
+    assert_eq!(&accounts[1].data.borrow()[32..], b"owner bundle");
+}
+
+#[test]
+fn owner_must_sign() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut accounts = keybundle_accounts(&owner, stored(&program_id, &owner, b"owner bundle"));
+    accounts[0] = common::runtime_account(owner, system_program::id(), 1_000_000_000, &[], false);
+
+    common::setup();
+    assert_eq!(
+        process_instruction(&program_id, &accounts, &ix(b"unsigned")),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+}
+
+#[test]
+fn pre_funded_pda_is_allocated_and_assigned() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    // Someone sent a few lamports to the PDA before the owner registered.
+    let griefed = common::runtime_account(pda(&program_id, &owner), system_program::id(), 1_000, &[], false);
+    let accounts = keybundle_accounts(&owner, griefed);
+
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(b"bundle-v1")).unwrap();
+
+    let kinds: Vec<u8> = common::take_invokes().iter().map(|ix| ix.data[0]).collect();
+    assert_eq!(kinds, [2, 8, 1], "transfer, allocate, assign");
+    assert_eq!(accounts[1].owner, &program_id);
+    assert_eq!(accounts[1].lamports(), Rent::default().minimum_balance(32 + 9));
+    assert_eq!(&accounts[1].data.borrow()[32..], b"bundle-v1");
+}
+
+#[test]
+fn bundle_length_past_the_data_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let accounts = keybundle_accounts(&owner, stored(&program_id, &owner, b"owner bundle"));
+    let mut data = ix(b"short");
+    data[2..4].copy_from_slice(&u16::MAX.to_le_bytes());
+
+    common::setup();
+    assert_eq!(process_instruction(&program_id, &accounts, &data), Err(ProgramError::InvalidInstructionData));
+    assert!(common::take_invokes().is_empty());
+}
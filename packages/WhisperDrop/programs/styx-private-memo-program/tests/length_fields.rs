@@ -0,0 +1,90 @@
+//! Length prefixes at their largest values, and instructions cut off inside
+//! them, must fail cleanly with `InvalidInstructionData`.
+
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_ROUTED_MESSAGE: u8 = 4;
+const TAG_RATCHET_MESSAGE: u8 = 7;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const TAG_PRIVATE_MESSAGE_WIDE: u8 = 12;
+
+fn run(sender: &Pubkey, data: &[u8]) -> Result<(), ProgramError> {
+    common::setup();
+    common::process_signed(sender, data)
+}
+
+/// Fixed fields of each parser up to (not including) its length prefix.
+fn prefixes(sender: &Pubkey) -> Vec<(&'static str, Vec<u8>, usize)> {
+    let mut message = vec![TAG_PRIVATE_MESSAGE, 0];
+    message.extend_from_slice(&[1u8; 32]);
+    message.extend_from_slice(sender.as_ref());
+
+    let mut wide = message.clone();
+    wide[0] = TAG_PRIVATE_MESSAGE_WIDE;
+
+    let mut verify = message.clone();
+    verify[0] = TAG_DECRYPT_VERIFY;
+
+    let mut routed = vec![TAG_ROUTED_MESSAGE, 0, 5, 1];
+    routed.extend_from_slice(&[2u8; 32]);
+    routed.push(1);
+    routed.extend_from_slice(&[3u8; 32]);
+
+    let mut ratchet = vec![TAG_RATCHET_MESSAGE, 0];
+    ratchet.extend_from_slice(&[4u8; 32]);
+    ratchet.extend_from_slice(&7u64.to_le_bytes());
+    ratchet.extend_from_slice(&[5u8; 32]);
+
+    vec![
+        ("private_message", message, 2),
+        ("private_message_wide", wide, 4),
+        ("decrypt_verify", verify, 2),
+        ("routed_message", routed, 2),
+        ("ratchet_message", ratchet, 2),
+    ]
+}
+
+#[test]
+fn max_length_prefix_past_the_data_is_rejected() {
+    let sender = Pubkey::new_unique();
+    for (name, mut data, width) in prefixes(&sender) {
+        data.extend_from_slice(&vec![0xff; width]);
+        data.extend_from_slice(&[0u8; 64]);
+        assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData), "{name}");
+    }
+}
+
+#[test]
+fn length_one_past_the_data_is_rejected() {
+    let sender = Pubkey::new_unique();
+    for (name, mut data, width) in prefixes(&sender) {
+        data.extend_from_slice(&65u32.to_le_bytes()[..width]);
+        data.extend_from_slice(&[0u8; 64]);
+        assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData), "{name}");
+    }
+}
+
+#[test]
+fn cut_inside_the_length_prefix_is_rejected() {
+    let sender = Pubkey::new_unique();
+    for (name, mut data, width) in prefixes(&sender) {
+        data.extend_from_slice(&vec![0xff; width - 1]);
+        assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData), "{name}");
+    }
+}
+
+#[test]
+fn auditor_count_past_the_data_is_rejected() {
+    const FLAG_COMPLIANCE_ENABLED: u8 = 0b0001_0000;
+    let sender = Pubkey::new_unique();
+    let mut data = vec![TAG_PRIVATE_MESSAGE, FLAG_COMPLIANCE_ENABLED];
+    data.extend_from_slice(&[1u8; 32]);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.push(u8::MAX);
+    data.extend_from_slice(&[0u8; 32]);
+    assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData));
+}
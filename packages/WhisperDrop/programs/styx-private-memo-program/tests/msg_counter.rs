@@ -0,0 +1,89 @@
+mod common;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{derive_message_nonce, process_instruction};
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+const FLAG_SHARED_KEY_V3: u8 = 0b0000_1000;
+const FLAG_MSG_COUNTER: u8 = 0b0100_0000;
+
+const RECIPIENT_FIELD: [u8; 32] = [7; 32];
+
+fn ix(tag: u8, counter: Option<u64>, sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let flags = FLAG_ENCRYPT | FLAG_SHARED_KEY_V3 | counter.map_or(0, |_| FLAG_MSG_COUNTER);
+    let mut data = vec![tag, flags];
+    if let Some(counter) = counter {
+        data.extend_from_slice(&counter.to_le_bytes());
+    }
+    data.extend_from_slice(&RECIPIENT_FIELD);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn send(counter: Option<u64>, sender: &Pubkey, plaintext: &[u8]) -> Vec<u8> {
+    common::setup();
+    common::process_signed(sender, &ix(TAG_PRIVATE_MESSAGE, counter, sender, plaintext)).unwrap();
+    common::take_data().pop().unwrap().pop().unwrap()
+}
+
+fn verify(counter: Option<u64>, sender: &Pubkey, ciphertext: &[u8]) -> Result<(), ProgramError> {
+    common::setup();
+    process_instruction(&Pubkey::new_unique(), &[], &ix(TAG_DECRYPT_VERIFY, counter, sender, ciphertext))
+}
+
+#[test]
+fn same_recipient_different_counters_get_distinct_nonces() {
+    assert_ne!(derive_message_nonce(&RECIPIENT_FIELD, 1), derive_message_nonce(&RECIPIENT_FIELD, 2));
+    assert_eq!(derive_message_nonce(&RECIPIENT_FIELD, 1), derive_message_nonce(&RECIPIENT_FIELD, 1));
+
+    let sender = Pubkey::new_unique();
+    let first = send(Some(1), &sender, b"same words");
+    let second = send(Some(2), &sender, b"same words");
+    assert_ne!(first, second);
+
+    // Without a counter the nonce repeats, so equal plaintexts give equal ciphertexts.
+    assert_eq!(send(None, &sender, b"same words"), send(None, &sender, b"same words"));
+}
+
+#[test]
+fn counter_nonce_matches_the_published_derivation() {
+    let sender = Pubkey::new_unique();
+    let ciphertext = send(Some(42), &sender, b"hello");
+
+    // The recipient field is not a real hidden recipient; recover the key the program used.
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let recipient: Vec<u8> = RECIPIENT_FIELD.iter().zip(key.iter()).map(|(a, b)| a ^ b).collect();
+    let recipient = Pubkey::try_from(recipient.as_slice()).unwrap();
+    let (lo, hi) = if sender < recipient { (sender, recipient) } else { (recipient, sender) };
+    let key = Sha256::new().chain_update(b"STYX_SHARED_KEY_V3").chain_update(lo).chain_update(hi).finalize();
+
+    let nonce = derive_message_nonce(&RECIPIENT_FIELD, 42);
+    let opened = ChaCha20Poly1305::new(Key::from_slice(&key)).decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice());
+    assert_eq!(opened.unwrap(), b"hello");
+}
+
+#[test]
+fn decrypt_verify_needs_the_same_counter() {
+    let sender = Pubkey::new_unique();
+    let ciphertext = send(Some(9), &sender, b"counted");
+    assert_eq!(verify(Some(9), &sender, &ciphertext), Ok(()));
+    assert_eq!(verify(Some(10), &sender, &ciphertext), Err(ProgramError::InvalidInstructionData));
+    assert_eq!(verify(None, &sender, &ciphertext), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn truncated_counter_is_rejected() {
+    let sender = Pubkey::new_unique();
+    common::setup();
+    let data = [TAG_PRIVATE_MESSAGE, FLAG_ENCRYPT | FLAG_MSG_COUNTER, 1, 2, 3];
+    assert_eq!(common::process_signed(&sender, &data), Err(ProgramError::InvalidInstructionData));
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{process_instruction, MemoEvent};
+
+const TAG_PRIVATE_TRANSFER: u8 = 5;
+const AMOUNT_NONCE: [u8; 8] = [4; 8];
+const ENCRYPTED_RECIPIENT: [u8; 32] = [6; 32];
+
+fn mask(sender: &Pubkey) -> u64 {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let recipient: Vec<u8> = ENCRYPTED_RECIPIENT.iter().zip(key).map(|(a, b)| a ^ b).collect();
+    let h = Sha256::new()
+        .chain_update(b"STYX_TRANSFER_V1")
+        .chain_update(sender)
+        .chain_update(recipient)
+        .chain_update(AMOUNT_NONCE)
+        .finalize();
+    u64::from_le_bytes(h[..8].try_into().unwrap())
+}
+
+fn ix(sender: &Pubkey, amount: u64, max_amount: u64) -> Vec<u8> {
+    let mut data = vec![TAG_PRIVATE_TRANSFER, 0];
+    data.extend_from_slice(&ENCRYPTED_RECIPIENT);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(amount ^ mask(sender)).to_le_bytes());
+    data.extend_from_slice(&AMOUNT_NONCE);
+    data.extend_from_slice(&max_amount.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+fn run(data: &[u8]) -> Result<(), ProgramError> {
+    common::setup();
+    process_instruction(&Pubkey::new_unique(), &[], data)
+}
+
+#[test]
+fn amount_within_bound_is_accepted() {
+    let sender = Pubkey::new_unique();
+    assert_eq!(run(&ix(&sender, 1_000, 1_000)), Ok(()));
+}
+
+#[test]
+fn amount_above_bound_is_rejected() {
+    let sender = Pubkey::new_unique();
+    assert_eq!(run(&ix(&sender, 1_001, 1_000)), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn zero_amount_is_rejected() {
+    let sender = Pubkey::new_unique();
+    assert_eq!(run(&ix(&sender, 0, 1_000)), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn memo_length_past_the_data_drops_the_memo() {
+    let sender = Pubkey::new_unique();
+    let mut data = ix(&sender, 1_000, 1_000);
+    let len_at = data.len() - 2;
+    data[len_at..].copy_from_slice(&u16::MAX.to_le_bytes());
+    data.extend_from_slice(b"memo");
+    assert_eq!(run(&data), Ok(()));
+    assert_eq!(common::take_events(), vec![MemoEvent::Transfer { executed: false, memo_len: 0 }]);
+}
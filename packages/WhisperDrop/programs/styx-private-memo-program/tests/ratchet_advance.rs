@@ -0,0 +1,47 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::process_instruction;
+
+const TAG_RATCHET_ADVANCE: u8 = 10;
+
+fn ix(chain_key: &[u8; 32], prev_counter: u64, counter: u64) -> Vec<u8> {
+    let mut data = vec![TAG_RATCHET_ADVANCE, 0];
+    data.extend_from_slice(chain_key);
+    data.extend_from_slice(&prev_counter.to_le_bytes());
+    data.extend_from_slice(&counter.to_le_bytes());
+    data
+}
+
+#[test]
+fn emits_commitment_to_message_key() {
+    common::setup();
+    let chain_key = [0x42u8; 32];
+    process_instruction(&Pubkey::new_unique(), &[], &ix(&chain_key, 4, 5)).unwrap();
+
+    // Spelled out so the TS client can mirror it byte for byte.
+    let mut h = Sha256::new();
+    h.update(b"STYX_RATCHET_MSG_V1");
+    h.update(chain_key);
+    h.update(5u64.to_le_bytes());
+    h.update([0x02]);
+    let msg_key = h.finalize();
+    let commitment = Sha256::digest(msg_key);
+
+    let frames = common::take_data();
+    assert_eq!(frames, vec![vec![5u64.to_le_bytes().to_vec(), commitment.to_vec()]]);
+}
+
+#[test]
+fn rejects_non_increasing_counter() {
+    common::setup();
+    let chain_key = [0x42u8; 32];
+    for (prev, counter) in [(5, 5), (6, 5)] {
+        assert_eq!(
+            process_instruction(&Pubkey::new_unique(), &[], &ix(&chain_key, prev, counter)),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::{build_onion, peel_onion, process_instruction};
+
+const TAG_ROUTED_MESSAGE: u8 = 4;
+const SESSION: [u8; 32] = [0x5a; 32];
+
+fn ix(hop_count: u8, current_hop: u8, payload: &[u8]) -> Vec<u8> {
+    ix_with_max(5, hop_count, current_hop, payload)
+}
+
+fn ix_with_max(max_hops: u8, hop_count: u8, current_hop: u8, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![TAG_ROUTED_MESSAGE, 0, max_hops, hop_count];
+    data.extend_from_slice(&SESSION);
+    data.push(current_hop);
+    data.extend_from_slice(&[0u8; 32]);
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn wrap(hop: u8, inner: &[u8]) -> Vec<u8> {
+    let key = Sha256::new()
+        .chain_update(b"STYX_ROUTE_HOP_KEY_V1")
+        .chain_update(SESSION)
+        .chain_update([hop])
+        .finalize();
+    let nonce = Sha256::new()
+        .chain_update(b"STYX_ROUTE_HOP_NONCE_V1")
+        .chain_update(SESSION)
+        .chain_update([hop])
+        .finalize();
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(&nonce[..12]), inner)
+        .unwrap()
+}
+
+fn run(data: &[u8]) -> Result<Vec<Vec<Vec<u8>>>, ProgramError> {
+    common::setup();
+    process_instruction(&Pubkey::new_unique(), &[], data)?;
+    Ok(common::take_data())
+}
+
+#[test]
+fn each_hop_peels_one_layer() {
+    let plaintext = b"final ciphertext for recipient".to_vec();
+    let onion = wrap(0, &wrap(1, &plaintext));
+
+    let frames = run(&ix(2, 0, &onion)).unwrap();
+    let after_first = frames[0][0].clone();
+    assert_eq!(after_first, wrap(1, &plaintext));
+
+    let frames = run(&ix(2, 1, &after_first)).unwrap();
+    assert_eq!(frames, vec![vec![plaintext.clone()]]);
+
+    let frames = run(&ix(2, 2, &plaintext)).unwrap();
+    assert_eq!(frames, vec![vec![plaintext]]);
+}
+
+#[test]
+fn malformed_layer_is_rejected_without_emitting() {
+    let mut onion = wrap(0, b"inner");
+    onion[0] ^= 1;
+    for data in [ix(2, 0, &onion), ix(2, 0, &[0u8; 8]), ix(2, 3, &onion), ix(2, 1, &wrap(0, b"inner"))] {
+        assert_eq!(run(&data), Err(ProgramError::InvalidInstructionData));
+        assert!(common::take_data().is_empty());
+    }
+}
+
+#[test]
+fn hop_count_is_bounded_by_session_max() {
+    let plaintext = b"payload".to_vec();
+    assert!(run(&ix_with_max(16, 16, 16, &plaintext)).is_ok());
+    assert_eq!(run(&ix_with_max(2, 3, 3, &plaintext)), Err(ProgramError::InvalidInstructionData));
+    assert_eq!(run(&ix_with_max(17, 3, 3, &plaintext)), Err(ProgramError::InvalidInstructionData));
+}
+
+#[test]
+fn built_onion_peels_back_to_payload() {
+    let plaintext = b"three hops deep".to_vec();
+    let onion = build_onion(&SESSION, 3, &plaintext).unwrap();
+    assert_eq!(onion, wrap(0, &wrap(1, &wrap(2, &plaintext))));
+
+    let mut layer = onion;
+    for hop in 0..3 {
+        let inner = peel_onion(&SESSION, hop, &layer).unwrap();
+        assert_eq!(run(&ix(3, hop, &layer)).unwrap(), vec![vec![inner.clone()]]);
+        layer = inner;
+    }
+    assert_eq!(layer, plaintext);
+    assert_eq!(run(&ix(3, 3, &layer)).unwrap(), vec![vec![plaintext]]);
+}
+
+#[test]
+fn peeling_out_of_order_fails() {
+    let onion = build_onion(&SESSION, 2, b"payload").unwrap();
+    assert_eq!(peel_onion(&SESSION, 1, &onion), Err(ProgramError::InvalidInstructionData));
+    assert_eq!(peel_onion(&[0; 32], 0, &onion), Err(ProgramError::InvalidInstructionData));
+    assert_eq!(build_onion(&SESSION, 0, b"payload").unwrap(), b"payload");
+}
@@ -0,0 +1,55 @@
+mod common;
+
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+
+fn ix(sender: &Pubkey) -> Vec<u8> {
+    let mut data = vec![TAG_PRIVATE_MESSAGE, 0];
+    data.extend_from_slice(&[9u8; 32]);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(b"hi");
+    data
+}
+
+fn run(account: &Pubkey, is_signer: bool, sender: &Pubkey) -> Result<(), ProgramError> {
+    let (mut lamports, mut bytes, owner) = (0u64, [0u8; 0], Pubkey::default());
+    let accounts = [AccountInfo::new(account, is_signer, false, &mut lamports, &mut bytes, &owner, false, 0)];
+    process_instruction(&Pubkey::new_unique(), &accounts, &ix(sender))
+}
+
+#[test]
+fn signed_sender_is_accepted() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    assert_eq!(run(&sender, true, &sender), Ok(()));
+    assert_eq!(common::take_data(), vec![vec![b"hi".to_vec()]]);
+}
+
+#[test]
+fn sender_without_accounts_is_rejected() {
+    common::setup();
+    assert_eq!(
+        process_instruction(&Pubkey::new_unique(), &[], &ix(&Pubkey::new_unique())),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn unsigned_sender_account_is_rejected() {
+    common::setup();
+    let sender = Pubkey::new_unique();
+    assert_eq!(run(&sender, false, &sender), Err(ProgramError::MissingRequiredSignature));
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn forged_sender_is_rejected() {
+    common::setup();
+    let signer = Pubkey::new_unique();
+    assert_eq!(run(&signer, true, &Pubkey::new_unique()), Err(ProgramError::MissingRequiredSignature));
+    assert!(common::take_data().is_empty());
+}
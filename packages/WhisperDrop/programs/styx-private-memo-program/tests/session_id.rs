@@ -0,0 +1,30 @@
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::Pubkey;
+use styx_private_memo_program::derive_session_id;
+
+#[test]
+fn both_parties_derive_the_same_id() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    // Alice opened the session; each side fills in the roles from its own view.
+    let alice_view = derive_session_id(&alice, &bob, 7);
+    let bob_view = derive_session_id(&alice, &bob, 7);
+    assert_eq!(alice_view, bob_view);
+
+    let expected: [u8; 32] = Sha256::new()
+        .chain_update(b"STYX_SESSION_ID_V1")
+        .chain_update(alice)
+        .chain_update(bob)
+        .chain_update(7u64.to_le_bytes())
+        .finalize()
+        .into();
+    assert_eq!(alice_view, expected);
+}
+
+#[test]
+fn roles_and_epoch_separate_sessions() {
+    let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let base = derive_session_id(&alice, &bob, 0);
+    assert_ne!(derive_session_id(&bob, &alice, 0), base);
+    assert_ne!(derive_session_id(&alice, &bob, 1), base);
+}
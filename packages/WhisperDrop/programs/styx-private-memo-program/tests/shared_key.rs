@@ -0,0 +1,79 @@
+mod common;
+
+use chacha20poly1305::{
+    aead::{Aead, NewAead},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use sha2::{Digest, Sha256};
+use solana_program::pubkey::Pubkey;
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_DECRYPT_VERIFY: u8 = 9;
+const FLAG_ENCRYPT: u8 = 0b0000_0001;
+const FLAG_SHARED_KEY_V3: u8 = 0b0000_1000;
+
+fn hide_recipient(sender: &Pubkey, recipient: &Pubkey) -> [u8; 32] {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = recipient.as_ref()[i] ^ key[i];
+    }
+    out
+}
+
+fn ix(tag: u8, flags: u8, sender: &Pubkey, recipient: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = vec![tag, flags];
+    data.extend_from_slice(&hide_recipient(sender, recipient));
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+/// Send `plaintext` on chain and decrypt the emitted ciphertext with `key`.
+fn send_and_open(key: &[u8], sender: &Pubkey, recipient: &Pubkey, plaintext: &[u8]) -> Option<Vec<u8>> {
+    common::setup();
+    let data = ix(TAG_PRIVATE_MESSAGE, FLAG_ENCRYPT | FLAG_SHARED_KEY_V3, sender, recipient, plaintext);
+    common::process_signed(sender, &data).unwrap();
+    let ciphertext = common::take_data().pop().unwrap().pop().unwrap();
+
+    let nonce = Sha256::new()
+        .chain_update(b"STYX_MSG_NONCE_V3")
+        .chain_update(hide_recipient(sender, recipient))
+        .finalize();
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(&nonce[..12]), ciphertext.as_slice())
+        .ok()
+}
+
+#[test]
+fn both_roles_derive_the_same_key() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+    let (lo, hi) = if alice < bob { (alice, bob) } else { (bob, alice) };
+    let key = Sha256::new()
+        .chain_update(b"STYX_SHARED_KEY_V3")
+        .chain_update(lo)
+        .chain_update(hi)
+        .finalize();
+
+    assert_eq!(send_and_open(&key, &alice, &bob, b"hi bob").as_deref(), Some(&b"hi bob"[..]));
+    assert_eq!(send_and_open(&key, &bob, &alice, b"hi alice").as_deref(), Some(&b"hi alice"[..]));
+}
+
+#[test]
+fn decrypt_verify_honours_the_flag() {
+    let alice = Pubkey::new_unique();
+    let bob = Pubkey::new_unique();
+
+    common::setup();
+    let data = ix(TAG_PRIVATE_MESSAGE, FLAG_ENCRYPT | FLAG_SHARED_KEY_V3, &alice, &bob, b"hello");
+    common::process_signed(&alice, &data).unwrap();
+    let ciphertext = common::take_data().pop().unwrap().pop().unwrap();
+
+    let verify = ix(TAG_DECRYPT_VERIFY, FLAG_SHARED_KEY_V3, &alice, &bob, &ciphertext);
+    assert!(process_instruction(&Pubkey::new_unique(), &[], &verify).is_ok());
+    let legacy = ix(TAG_DECRYPT_VERIFY, 0, &alice, &bob, &ciphertext);
+    assert!(process_instruction(&Pubkey::new_unique(), &[], &legacy).is_err());
+}
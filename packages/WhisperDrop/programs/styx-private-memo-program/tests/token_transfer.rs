@@ -0,0 +1,127 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, system_program,
+};
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_TRANSFER: u8 = 5;
+const AMOUNT_NONCE: [u8; 8] = [4; 8];
+const ENCRYPTED_RECIPIENT: [u8; 32] = [6; 32];
+
+fn recipient(sender: &Pubkey) -> Pubkey {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = ENCRYPTED_RECIPIENT[i] ^ key[i];
+    }
+    Pubkey::new_from_array(out)
+}
+
+fn ix(sender: &Pubkey, amount: u64) -> Vec<u8> {
+    let h = Sha256::new()
+        .chain_update(b"STYX_TRANSFER_V1")
+        .chain_update(sender)
+        .chain_update(recipient(sender))
+        .chain_update(AMOUNT_NONCE)
+        .finalize();
+    let mask = u64::from_le_bytes(h[..8].try_into().unwrap());
+
+    let mut data = vec![TAG_PRIVATE_TRANSFER, 0];
+    data.extend_from_slice(&ENCRYPTED_RECIPIENT);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(amount ^ mask).to_le_bytes());
+    data.extend_from_slice(&AMOUNT_NONCE);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey) -> Vec<u8> {
+    let account = spl_token::state::Account {
+        mint: *mint,
+        owner: *owner,
+        amount: 1_000,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    };
+    let mut data = vec![0u8; spl_token::state::Account::LEN];
+    spl_token::state::Account::pack(account, &mut data).unwrap();
+    data
+}
+
+/// Token transfer from `sender` into a token account owned by `to_owner`,
+/// optionally with the transfer nullifier and the system program.
+fn transfer(sender: &Pubkey, to_owner: &Pubkey, with_nullifier: bool) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (from_ata, to_ata) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let nullifier =
+        Pubkey::find_program_address(&[b"transfer_nullifier", sender.as_ref(), &AMOUNT_NONCE], &program_id).0;
+    let (token, system) = (spl_token::id(), system_program::id());
+
+    let (mut l0, mut l1, mut l2, mut l3, mut l4, mut l5) = (1_000_000u64, 1u64, 1u64, 1u64, 0u64, 1u64);
+    let (mut d1, mut d3) = (token_account(&mint, to_owner), token_account(&mint, sender));
+    let (mut d0, mut d2, mut d4, mut d5) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+    let mut accounts = vec![
+        AccountInfo::new(sender, true, true, &mut l0, &mut d0, &system, false, 0),
+        AccountInfo::new(&to_ata, false, true, &mut l1, &mut d1, &token, false, 0),
+        AccountInfo::new(&token, false, false, &mut l2, &mut d2, &system, true, 0),
+        AccountInfo::new(&from_ata, false, true, &mut l3, &mut d3, &token, false, 0),
+    ];
+    if with_nullifier {
+        accounts.push(AccountInfo::new(&nullifier, false, true, &mut l4, &mut d4, &system, false, 0));
+        accounts.push(AccountInfo::new(&system, false, false, &mut l5, &mut d5, &system, true, 0));
+    }
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(sender, 500))
+}
+
+#[test]
+fn token_transfer_issues_spl_transfer() {
+    let sender = Pubkey::new_unique();
+    transfer(&sender, &recipient(&sender), false).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    let ix = &invokes[0];
+    assert_eq!(ix.program_id, spl_token::id());
+    assert_eq!(
+        spl_token::instruction::TokenInstruction::unpack(&ix.data).unwrap(),
+        spl_token::instruction::TokenInstruction::Transfer { amount: 500 }
+    );
+    // source, destination, authority
+    assert_eq!(ix.accounts[2].pubkey, sender);
+    assert!(ix.accounts[2].is_signer);
+}
+
+#[test]
+fn token_transfer_logs_the_amount_in_mint_units() {
+    let sender = Pubkey::new_unique();
+    transfer(&sender, &recipient(&sender), false).unwrap();
+
+    let logs = common::take_logs();
+    assert!(logs.iter().any(|l| matches!(
+        l,
+        common::Log::Msg(m) if m.starts_with("STYX_PRIVATE_TRANSFER complete: 500 base units of mint ")
+    )));
+}
+
+#[test]
+fn token_transfer_consumes_the_nullifier_first() {
+    let sender = Pubkey::new_unique();
+    transfer(&sender, &recipient(&sender), true).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 2);
+    assert_eq!(invokes[0].program_id, system_program::id());
+    assert_eq!(invokes[1].program_id, spl_token::id());
+}
+
+#[test]
+fn destination_must_be_owned_by_the_recipient() {
+    let sender = Pubkey::new_unique();
+    assert_eq!(transfer(&sender, &Pubkey::new_unique(), false), Err(ProgramError::InvalidAccountData));
+    assert!(common::take_invokes().is_empty());
+}
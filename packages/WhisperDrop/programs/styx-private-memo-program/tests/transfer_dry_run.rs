@@ -0,0 +1,100 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, system_program,
+};
+use styx_private_memo_program::{process_instruction, MemoEvent};
+
+const TAG_PRIVATE_TRANSFER: u8 = 5;
+const TAG_PRIVATE_TRANSFER_DRY_RUN: u8 = 13;
+const FLAG_HEADER_AAD: u8 = 0b1000_0000;
+const AMOUNT_NONCE: [u8; 8] = [4; 8];
+const ENCRYPTED_RECIPIENT: [u8; 32] = [6; 32];
+
+fn recipient(sender: &Pubkey) -> Pubkey {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = ENCRYPTED_RECIPIENT[i] ^ key[i];
+    }
+    Pubkey::new_from_array(out)
+}
+
+fn ix(tag: u8, flags: u8, sender: &Pubkey, amount: u64) -> Vec<u8> {
+    let h = Sha256::new()
+        .chain_update(b"STYX_TRANSFER_V1")
+        .chain_update(sender)
+        .chain_update(recipient(sender))
+        .chain_update(AMOUNT_NONCE)
+        .finalize();
+    let mask = u64::from_le_bytes(h[..8].try_into().unwrap());
+
+    let mut data = vec![tag, flags];
+    data.extend_from_slice(&ENCRYPTED_RECIPIENT);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(amount ^ mask).to_le_bytes());
+    data.extend_from_slice(&AMOUNT_NONCE);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+/// Run a transfer from `sender` to `to`, with the sender's transfer nullifier PDA.
+fn transfer(tag: u8, flags: u8, sender: &Pubkey, to: &Pubkey) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let nullifier =
+        Pubkey::find_program_address(&[b"transfer_nullifier", sender.as_ref(), &AMOUNT_NONCE], &program_id).0;
+    let system = system_program::id();
+    let (mut l0, mut l1, mut l2, mut l3) = (1_000_000u64, 0u64, 1u64, 0u64);
+    let (mut d0, mut d1, mut d2, mut d3) = ([0u8; 0], [0u8; 0], [0u8; 0], [0u8; 0]);
+    let accounts = [
+        AccountInfo::new(sender, true, true, &mut l0, &mut d0, &system, false, 0),
+        AccountInfo::new(to, false, true, &mut l1, &mut d1, &system, false, 0),
+        AccountInfo::new(&system, false, false, &mut l2, &mut d2, &system, true, 0),
+        AccountInfo::new(&nullifier, false, true, &mut l3, &mut d3, &system, false, 0),
+    ];
+    common::setup();
+    process_instruction(&program_id, &accounts, &ix(tag, flags, sender, 500))
+}
+
+#[test]
+fn dry_run_moves_nothing_and_logs_the_resolution() {
+    let sender = Pubkey::new_unique();
+    let to = recipient(&sender);
+    transfer(TAG_PRIVATE_TRANSFER_DRY_RUN, 0, &sender, &to).unwrap();
+
+    assert!(common::take_invokes().is_empty());
+    let logs = common::take_logs();
+    let expected = format!("STYX_PRIVATE_TRANSFER dry run: 500 lamports to {:?}", to);
+    assert!(logs.contains(&common::Log::Msg(expected)));
+
+    transfer(TAG_PRIVATE_TRANSFER_DRY_RUN, 0, &sender, &to).unwrap();
+    assert_eq!(common::take_events(), vec![MemoEvent::Transfer { executed: false, memo_len: 0 }]);
+}
+
+#[test]
+fn dry_run_still_rejects_a_recipient_mismatch() {
+    let sender = Pubkey::new_unique();
+    assert_eq!(transfer(TAG_PRIVATE_TRANSFER_DRY_RUN, 0, &sender, &Pubkey::new_unique()), Err(ProgramError::InvalidAccountData));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn the_plain_tag_runs_the_transfer() {
+    let sender = Pubkey::new_unique();
+    transfer(TAG_PRIVATE_TRANSFER, 0, &sender, &recipient(&sender)).unwrap();
+    assert_eq!(common::take_invokes().len(), 2);
+}
+
+#[test]
+fn header_aad_bit_does_not_select_dry_run() {
+    let sender = Pubkey::new_unique();
+    let to = recipient(&sender);
+
+    transfer(TAG_PRIVATE_TRANSFER, FLAG_HEADER_AAD, &sender, &to).unwrap();
+    assert_eq!(common::take_invokes().len(), 2);
+
+    transfer(TAG_PRIVATE_TRANSFER_DRY_RUN, FLAG_HEADER_AAD, &sender, &to).unwrap();
+    assert!(common::take_invokes().is_empty());
+}
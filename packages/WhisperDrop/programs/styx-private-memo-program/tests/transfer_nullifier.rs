@@ -0,0 +1,146 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, system_program,
+};
+use styx_private_memo_program::process_instruction;
+
+const TAG_PRIVATE_TRANSFER: u8 = 5;
+const AMOUNT_NONCE: [u8; 8] = [4; 8];
+const ENCRYPTED_RECIPIENT: [u8; 32] = [6; 32];
+
+fn recipient(sender: &Pubkey) -> Pubkey {
+    let key = Sha256::new().chain_update(b"STYX_METADATA_KEY_V3").chain_update(sender).finalize();
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = ENCRYPTED_RECIPIENT[i] ^ key[i];
+    }
+    Pubkey::new_from_array(out)
+}
+
+fn ix(sender: &Pubkey, amount: u64) -> Vec<u8> {
+    let h = Sha256::new()
+        .chain_update(b"STYX_TRANSFER_V1")
+        .chain_update(sender)
+        .chain_update(recipient(sender))
+        .chain_update(AMOUNT_NONCE)
+        .finalize();
+    let mask = u64::from_le_bytes(h[..8].try_into().unwrap());
+
+    let mut data = vec![TAG_PRIVATE_TRANSFER, 0];
+    data.extend_from_slice(&ENCRYPTED_RECIPIENT);
+    data.extend_from_slice(sender.as_ref());
+    data.extend_from_slice(&(amount ^ mask).to_le_bytes());
+    data.extend_from_slice(&AMOUNT_NONCE);
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+/// Run a transfer from `sender` with the given nullifier account.
+fn transfer(program_id: &Pubkey, sender: &Pubkey, nullifier: &AccountInfo<'static>) -> Result<(), ProgramError> {
+    transfer_signed_by(program_id, sender, sender, nullifier)
+}
+
+/// Like `transfer`, but the paying account is `signer` while the data names `sender`.
+fn transfer_signed_by(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    sender: &Pubkey,
+    nullifier: &AccountInfo<'static>,
+) -> Result<(), ProgramError> {
+    let system = system_program::id();
+    let mut system_account = common::runtime_account(system, system, 1, &[], false);
+    system_account.executable = true;
+    let accounts = [
+        common::runtime_account(*signer, system, 1_000_000, &[], true),
+        common::runtime_account(recipient(sender), system, 0, &[], false),
+        system_account,
+        nullifier.clone(),
+    ];
+    common::setup();
+    process_instruction(program_id, &accounts, &ix(sender, 500))
+}
+
+/// Nullifier account at `key`, owned by `owner` and holding `lamports`.
+fn nullifier_account(key: Pubkey, owner: Pubkey, lamports: u64) -> AccountInfo<'static> {
+    common::runtime_account(key, owner, lamports, &[], false)
+}
+
+fn nullifier_pda(program_id: &Pubkey, sender: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"transfer_nullifier", sender.as_ref(), &AMOUNT_NONCE], program_id).0
+}
+
+#[test]
+fn first_transfer_creates_nullifier_before_paying() {
+    let program_id = Pubkey::new_unique();
+    let sender = Pubkey::new_unique();
+    let nullifier = nullifier_pda(&program_id, &sender);
+
+    transfer(&program_id, &sender, &nullifier_account(nullifier, system_program::id(), 0)).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 2);
+    assert_eq!(invokes[0].program_id, system_program::id());
+    assert_eq!(invokes[0].accounts[1].pubkey, nullifier);
+    assert_eq!(invokes[1].accounts[1].pubkey, recipient(&sender));
+}
+
+#[test]
+fn existing_nullifier_rejects_replay() {
+    let program_id = Pubkey::new_unique();
+    let sender = Pubkey::new_unique();
+    let nullifier = nullifier_pda(&program_id, &sender);
+
+    let spent = nullifier_account(nullifier, program_id, 890_880);
+    assert_eq!(transfer(&program_id, &sender, &spent), Err(ProgramError::Custom(1)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn pre_funded_nullifier_is_still_consumed() {
+    let program_id = Pubkey::new_unique();
+    let sender = Pubkey::new_unique();
+    // Someone sent lamports to the predictable PDA ahead of the transfer.
+    let griefed = nullifier_account(nullifier_pda(&program_id, &sender), system_program::id(), 1_000);
+
+    transfer(&program_id, &sender, &griefed).unwrap();
+
+    let kinds: Vec<_> = common::take_invokes().iter().map(|ix| (ix.program_id, ix.data[0])).collect();
+    let system = system_program::id();
+    // Rent top-up, allocate, assign, then the payment itself.
+    assert_eq!(kinds, [(system, 2), (system, 8), (system, 1), (system, 2)]);
+    assert_eq!(griefed.owner, &program_id);
+
+    // Now it is spent.
+    assert_eq!(transfer(&program_id, &sender, &griefed), Err(ProgramError::Custom(1)));
+}
+
+#[test]
+fn wrong_nullifier_address_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let sender = Pubkey::new_unique();
+
+    assert_eq!(
+        transfer(&program_id, &sender, &nullifier_account(Pubkey::new_unique(), system_program::id(), 0)),
+        Err(ProgramError::InvalidSeeds)
+    );
+}
+
+#[test]
+fn third_party_cannot_consume_another_senders_nullifier() {
+    let program_id = Pubkey::new_unique();
+    let victim = Pubkey::new_unique();
+    let nullifier = nullifier_account(nullifier_pda(&program_id, &victim), system_program::id(), 0);
+
+    assert_eq!(
+        transfer_signed_by(&program_id, &Pubkey::new_unique(), &victim, &nullifier),
+        Err(ProgramError::MissingRequiredSignature)
+    );
+    assert!(common::take_invokes().is_empty());
+    assert_eq!(nullifier.owner, &system_program::id());
+
+    // The victim's own transfer still goes through.
+    transfer(&program_id, &victim, &nullifier).unwrap();
+}
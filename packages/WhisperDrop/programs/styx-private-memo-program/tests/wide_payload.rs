@@ -0,0 +1,82 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_private_memo_program::MemoEvent;
+
+const TAG_PRIVATE_MESSAGE: u8 = 3;
+const TAG_PRIVATE_MESSAGE_WIDE: u8 = 12;
+
+fn header(tag: u8, sender: &Pubkey) -> Vec<u8> {
+    let mut data = vec![tag, 0];
+    data.extend_from_slice(&[1u8; 32]);
+    data.extend_from_slice(sender.as_ref());
+    data
+}
+
+fn message(sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = header(TAG_PRIVATE_MESSAGE, sender);
+    data.extend_from_slice(&u16::try_from(payload.len()).unwrap().to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn wide_message(sender: &Pubkey, payload: &[u8]) -> Vec<u8> {
+    let mut data = header(TAG_PRIVATE_MESSAGE_WIDE, sender);
+    data.extend_from_slice(&u32::try_from(payload.len()).unwrap().to_le_bytes());
+    data.extend_from_slice(payload);
+    data
+}
+
+fn run(sender: &Pubkey, data: &[u8]) -> Result<(), ProgramError> {
+    common::setup();
+    common::process_signed(sender, data)
+}
+
+fn event(payload_len: u32) -> Vec<MemoEvent> {
+    vec![MemoEvent::PrivateMessage { flags: 0, payload_len, auditor_count: 0 }]
+}
+
+#[test]
+fn u16_tag_carries_up_to_65535_bytes() {
+    let sender = Pubkey::new_unique();
+    let payload = vec![0xab; u16::MAX as usize];
+    run(&sender, &message(&sender, &payload)).unwrap();
+    assert_eq!(common::take_events(), event(65_535));
+}
+
+#[test]
+fn wide_tag_matches_u16_tag_at_the_old_boundary() {
+    let sender = Pubkey::new_unique();
+    let payload = vec![0xab; u16::MAX as usize];
+    run(&sender, &message(&sender, &payload)).unwrap();
+    let narrow = common::take_data();
+    run(&sender, &wide_message(&sender, &payload)).unwrap();
+    assert_eq!(common::take_data(), narrow);
+}
+
+#[test]
+fn wide_tag_carries_past_65535_bytes() {
+    let sender = Pubkey::new_unique();
+    let payload = vec![0xcd; u16::MAX as usize + 1];
+    run(&sender, &wide_message(&sender, &payload)).unwrap();
+    assert_eq!(common::take_events(), event(65_536));
+}
+
+#[test]
+fn wide_length_past_the_data_is_rejected() {
+    let sender = Pubkey::new_unique();
+    for len in [65_537u32, u32::MAX] {
+        let mut data = header(TAG_PRIVATE_MESSAGE_WIDE, &sender);
+        data.extend_from_slice(&len.to_le_bytes());
+        data.extend_from_slice(&[0u8; 65_536]);
+        assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData), "len {len}");
+    }
+}
+
+#[test]
+fn truncated_wide_length_is_rejected() {
+    let sender = Pubkey::new_unique();
+    let mut data = header(TAG_PRIVATE_MESSAGE_WIDE, &sender);
+    data.extend_from_slice(&[0xff, 0xff]);
+    assert_eq!(run(&sender, &data), Err(ProgramError::InvalidInstructionData));
+}
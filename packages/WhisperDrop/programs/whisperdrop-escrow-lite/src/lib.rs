@@ -1,4 +1,5 @@
 #![deny(clippy::all)]
+#![allow(unexpected_cfgs)]
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
@@ -6,18 +7,26 @@ use solana_program::{
     msg,
     program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_pack::Pack,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction,
     sysvar::Sysvar,
 };
 use spl_token::instruction as token_ix;
+use whisperdrop_merkle as merkle;
 
 solana_program::entrypoint!(process_instruction);
 
 const SEED_CAMPAIGN: &[u8] = b"campaign";
-const SEED_ESCROW: &[u8] = b"escrow";
 const SEED_NULLIFIER: &[u8] = b"nullifier";
+const SEED_RECIPIENT: &[u8] = b"recipient";
+
+/// Upper bound on claims per `ClaimBatch`, keeping proofs + CPIs within compute limits.
+pub const MAX_BATCH_CLAIMS: usize = 8;
+
+/// Longest accepted merkle proof (trees of up to 2^32 leaves).
+pub const MAX_PROOF_LEN: usize = 32;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum Instruction {
@@ -28,12 +37,39 @@ pub enum Instruction {
         mint: Pubkey,
         expiry_unix: i64,
         authority: Pubkey,
+        max_per_recipient: u64,
+        require_recipient_sig: bool,
     },
     Claim {
         allocation: u64,
         nonce16: [u8; 16],
         proof: Vec<[u8; 32]>,
     },
+    /// Claim several leaves for one recipient; all-or-nothing.
+    ClaimBatch {
+        claims: Vec<(u64, [u8; 16], Vec<[u8; 32]>)>,
+    },
+    /// After expiry, return the remaining escrow balance to the authority.
+    Sweep,
+    /// Fund the escrow from the authority's token account before expiry.
+    Deposit {
+        amount: u64,
+    },
+    /// Push back the campaign expiry; never shortens it.
+    ExtendExpiry {
+        new_expiry_unix: i64,
+    },
+    /// Stop (or resume) claims without touching expiry or escrow.
+    SetPaused {
+        paused: bool,
+    },
+    /// `Claim`, after checking `sha256(manifest)` against the campaign's `manifest_hash`.
+    ClaimWithManifest {
+        allocation: u64,
+        nonce16: [u8; 16],
+        proof: Vec<[u8; 32]>,
+        manifest: Vec<u8>,
+    },
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
@@ -45,6 +81,20 @@ pub struct Campaign {
     pub expiry_unix: i64,
     pub authority: Pubkey,
     pub bump: u8,
+    /// Cap on the sum of all leaves one recipient may claim (`u64::MAX` for none).
+    pub max_per_recipient: u64,
+    /// While set, `Claim` and `ClaimBatch` fail; sweep and deposit are unaffected.
+    pub paused: bool,
+    /// Sum of every allocation paid out, so progress is one account read.
+    pub total_claimed: u64,
+    /// When set, claims need the recipient's signature, not just any payer's.
+    pub require_recipient_sig: bool,
+}
+
+/// Running total claimed by one recipient, PDA `["recipient", campaign_pda, recipient]`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, Default)]
+pub struct RecipientClaimed {
+    pub claimed: u64,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
@@ -54,50 +104,96 @@ pub struct Nullifier {
     pub nonce16: [u8; 16],
 }
 
+/// Emitted via `sol_log_data` (Borsh) once per claimed leaf.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ClaimEvent {
+    pub campaign_id: [u8; 32],
+    pub recipient: Pubkey,
+    pub allocation: u64,
+    pub nonce16: [u8; 16],
+}
+
+fn emit_claim(campaign_id: [u8;32], recipient: &Pubkey, allocation: u64, nonce16: [u8;16]) -> ProgramResult {
+    let event = ClaimEvent { campaign_id, recipient: *recipient, allocation, nonce16 };
+    solana_program::log::sol_log_data(&[&borsh::to_vec(&event)?]);
+    Ok(())
+}
+
 fn sha256(data: &[u8]) -> [u8; 32] {
     use solana_program::hash::hash;
     hash(data).to_bytes()
 }
 
-fn hash_pair(a: &[u8;32], b: &[u8;32]) -> [u8;32] {
-    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
-    let mut buf = [0u8; 64];
-    buf[..32].copy_from_slice(lo);
-    buf[32..].copy_from_slice(hi);
-    sha256(&buf)
-}
+/// Single-leaf proof check; see [`whisperdrop_merkle`] for the tree layout.
+pub use whisperdrop_merkle::verify as verify_merkle;
 
-fn verify_merkle(leaf: [u8;32], proof: &[[u8;32]], root: [u8;32]) -> bool {
-    let mut cur = leaf;
-    for p in proof {
-        cur = hash_pair(&cur, p);
+/// Verify several leaves against `root` at once, sharing internal nodes
+/// (OpenZeppelin multiproof layout). Step `i` hashes the next known node
+/// with either another known node (`flags[i]`) or the next `proof` element;
+/// known nodes are `leaves` in order, then the hashes computed so far.
+///
+/// `leaves` must be in the order the proof was generated for. Every leaf,
+/// proof element and intermediate hash must be used exactly once.
+pub fn verify_multiproof(leaves: &[[u8;32]], proof: &[[u8;32]], flags: &[bool], root: [u8;32]) -> bool {
+    if leaves.is_empty() || leaves.len() + proof.len() != flags.len() + 1 {
+        return false;
+    }
+    if flags.is_empty() {
+        return leaves[0] == root;
+    }
+    let mut hashes: Vec<[u8;32]> = Vec::with_capacity(flags.len());
+    let (mut leaf_pos, mut hash_pos, mut proof_pos) = (0, 0, 0);
+    let mut next_known = |hashes: &[[u8;32]]| {
+        if leaf_pos < leaves.len() {
+            leaf_pos += 1;
+            Some(leaves[leaf_pos - 1])
+        } else {
+            hash_pos += 1;
+            hashes.get(hash_pos - 1).copied()
+        }
+    };
+    for &flag in flags {
+        let Some(a) = next_known(&hashes) else { return false };
+        let b = if flag {
+            next_known(&hashes)
+        } else {
+            proof_pos += 1;
+            proof.get(proof_pos - 1).copied()
+        };
+        let Some(b) = b else { return false };
+        hashes.push(merkle::parent(&a, &b));
     }
-    cur == root
+    proof_pos == proof.len() && hashes[flags.len() - 1] == root
 }
 
+/// Leaf tag for this program's trees; see [`whisperdrop_merkle::leaf_hash`].
+const CLAIM_LEAF_TAG: &[u8] = b"wd:claim:v1";
+
 fn claim_leaf(campaign_id: [u8;32], recipient: &Pubkey, allocation: u64, nonce16: [u8;16]) -> [u8;32] {
-    // leaf = sha256( "wd:claim:v1" || campaign_id || recipient || allocation_le || nonce16 )
-    let mut buf = Vec::with_capacity(12 + 32 + 32 + 8 + 16);
-    buf.extend_from_slice(b"wd:claim:v1");
-    buf.extend_from_slice(&campaign_id);
-    buf.extend_from_slice(recipient.as_ref());
-    buf.extend_from_slice(&allocation.to_le_bytes());
-    buf.extend_from_slice(&nonce16);
-    sha256(&buf)
+    merkle::leaf_hash(CLAIM_LEAF_TAG, &campaign_id, recipient, allocation, &nonce16)
 }
 
 pub fn process_instruction(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
     let ix = Instruction::try_from_slice(data).map_err(|_| ProgramError::InvalidInstructionData)?;
     match ix {
-        Instruction::InitCampaign { campaign_id, manifest_hash, merkle_root, mint, expiry_unix, authority } => {
-            process_init_campaign(program_id, accounts, campaign_id, manifest_hash, merkle_root, mint, expiry_unix, authority)
+        Instruction::InitCampaign { campaign_id, manifest_hash, merkle_root, mint, expiry_unix, authority, max_per_recipient, require_recipient_sig } => {
+            process_init_campaign(program_id, accounts, campaign_id, manifest_hash, merkle_root, mint, expiry_unix, authority, max_per_recipient, require_recipient_sig)
         }
         Instruction::Claim { allocation, nonce16, proof } => {
             process_claim(program_id, accounts, allocation, nonce16, proof)
         }
+        Instruction::ClaimBatch { claims } => process_claim_batch(program_id, accounts, claims),
+        Instruction::Sweep => process_sweep(program_id, accounts),
+        Instruction::Deposit { amount } => process_deposit(program_id, accounts, amount),
+        Instruction::ExtendExpiry { new_expiry_unix } => process_extend_expiry(program_id, accounts, new_expiry_unix),
+        Instruction::SetPaused { paused } => process_set_paused(program_id, accounts, paused),
+        Instruction::ClaimWithManifest { allocation, nonce16, proof, manifest } => {
+            process_claim_with_manifest(program_id, accounts, allocation, nonce16, proof, &manifest)
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_init_campaign(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -107,6 +203,8 @@ fn process_init_campaign(
     mint: Pubkey,
     expiry_unix: i64,
     authority: Pubkey,
+    max_per_recipient: u64,
+    require_recipient_sig: bool,
 ) -> ProgramResult {
     let acc_iter = &mut accounts.iter();
     let payer = next_account_info(acc_iter)?; // signer, pays rent
@@ -120,7 +218,7 @@ fn process_init_campaign(
     if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
 
     let rent = Rent::from_account_info(rent_sysvar)?;
-    let mut state = Campaign {
+    let state = Campaign {
         campaign_id,
         manifest_hash,
         merkle_root,
@@ -128,8 +226,12 @@ fn process_init_campaign(
         expiry_unix,
         authority,
         bump,
+        max_per_recipient,
+        paused: false,
+        total_claimed: 0,
+        require_recipient_sig,
     };
-    let bytes = state.try_to_vec()?;
+    let bytes = borsh::to_vec(&state)?;
     let space = bytes.len();
     let lamports = rent.minimum_balance(space);
 
@@ -142,12 +244,28 @@ fn process_init_campaign(
         )?;
         campaign_ai.data.borrow_mut()[..space].copy_from_slice(&bytes);
         msg!("campaign initialized");
+    } else if campaign_ai.data_len() < space {
+        // Written by an older build; its root predates the 0x00/0x01 leaf and
+        // parent prefixes, so it has to be re-published, not grown in place.
+        msg!("campaign is {} bytes, an older layout; re-publish it under a new campaign_id", campaign_ai.data_len());
+        return Err(ProgramError::InvalidAccountData);
     } else {
         msg!("campaign already exists");
     }
     Ok(())
 }
 
+/// Add `amount` to the campaign's `total_claimed` and write it back.
+fn add_total_claimed(campaign_ai: &AccountInfo, campaign: &mut Campaign, amount: u64) -> ProgramResult {
+    campaign.total_claimed = campaign.total_claimed.checked_add(amount).ok_or_else(|| {
+        msg!("total_claimed overflow");
+        ProgramError::Custom(9)
+    })?;
+    let bytes = borsh::to_vec(campaign)?;
+    campaign_ai.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+}
+
 fn process_claim(
     program_id: &Pubkey,
     accounts: &[AccountInfo],
@@ -155,10 +273,12 @@ fn process_claim(
     nonce16: [u8;16],
     proof: Vec<[u8;32]>,
 ) -> ProgramResult {
+    check_proof_len(&proof)?;
+
     let acc_iter = &mut accounts.iter();
     let mint_ai = next_account_info(acc_iter)?; // readonly
     let campaign_ai = next_account_info(acc_iter)?; // writable
-    let recipient_ai = next_account_info(acc_iter)?; // readonly
+    let recipient_ai = next_account_info(acc_iter)?; // readonly; signer if require_recipient_sig
     let escrow_ai = next_account_info(acc_iter)?; // writable token acct
     let nullifier_ai = next_account_info(acc_iter)?; // writable (system acct)
     let payer_ai = next_account_info(acc_iter)?; // signer
@@ -166,11 +286,17 @@ fn process_claim(
     let system_ai = next_account_info(acc_iter)?;
     let token_ai = next_account_info(acc_iter)?;
     let rent_sysvar = next_account_info(acc_iter)?;
+    let recipient_claimed_ai = next_account_info(acc_iter)?; // writable (system acct)
 
     if !payer_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
 
-    let campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    let mut campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
     if campaign.mint != *mint_ai.key { return Err(ProgramError::InvalidAccountData); }
+    if campaign.paused {
+        msg!("campaign paused");
+        return Err(ProgramError::Custom(7));
+    }
+    check_recipient_sig(&campaign, recipient_ai)?;
 
     // expiry check (optional hard fail)
     let now = solana_program::clock::Clock::get()?.unix_timestamp;
@@ -183,57 +309,305 @@ fn process_claim(
     let (campaign_pda, bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
     if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
 
-    // verify escrow PDA is correct owner/signer seed
-    let (escrow_pda, escrow_bump) = Pubkey::find_program_address(&[SEED_ESCROW, campaign_pda.as_ref()], program_id);
-    if escrow_pda != *escrow_ai.owner {
-        // For token accounts, owner is token program; the escrow *authority* must be campaign PDA.
-        // We can't read token account authority without unpacking; keep lightweight: rely on invoke failing if authority mismatch.
-        msg!("note: token account owner mismatch check skipped; token program will enforce authority");
+    check_escrow(escrow_ai, &campaign_pda, &campaign.mint)?;
+
+    // create nullifier account (one-time claim)
+    let rent = Rent::from_account_info(rent_sysvar)?;
+    create_nullifier(program_id, payer_ai, nullifier_ai, system_ai, &rent, &campaign_pda, recipient_ai.key, nonce16)?;
+
+    // verify proof
+    let leaf = claim_leaf(campaign.campaign_id, recipient_ai.key, allocation, nonce16);
+    if !verify_merkle(leaf, &proof, campaign.merkle_root) {
+        msg!("invalid proof");
+        return Err(ProgramError::Custom(3));
     }
 
-    // derive nullifier PDA (account address)
-    let (nullifier_pda, null_bump) = Pubkey::find_program_address(&[SEED_NULLIFIER, campaign_pda.as_ref(), recipient_ai.key.as_ref()], program_id);
+    add_recipient_claimed(program_id, payer_ai, recipient_claimed_ai, system_ai, &rent, &campaign_pda, recipient_ai.key, allocation, campaign.max_per_recipient)?;
+    add_total_claimed(campaign_ai, &mut campaign, allocation)?;
+
+    // token transfer from escrow token account -> recipient ATA
+    // Expect escrow token account authority is campaign PDA.
+    let ix = token_ix::transfer(
+        token_ai.key,
+        escrow_ai.key,
+        recipient_ata_ai.key,
+        &campaign_pda,
+        &[],
+        allocation,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[escrow_ai.clone(), recipient_ata_ai.clone(), token_ai.clone()],
+        &[&[SEED_CAMPAIGN, &campaign.campaign_id, &[bump]]],
+    )?;
+
+    emit_claim(campaign.campaign_id, recipient_ai.key, allocation, nonce16)?;
+    msg!("claimed");
+    Ok(())
+}
+
+/// Bind the claim to the manifest the caller reviewed, then claim as usual.
+fn process_claim_with_manifest(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    allocation: u64,
+    nonce16: [u8;16],
+    proof: Vec<[u8;32]>,
+    manifest: &[u8],
+) -> ProgramResult {
+    let campaign_ai = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if sha256(manifest) != campaign.manifest_hash {
+        msg!("manifest does not match campaign manifest_hash");
+        return Err(ProgramError::Custom(8));
+    }
+    process_claim(program_id, accounts, allocation, nonce16, proof)
+}
+
+/// In recipient-authorized campaigns, only the recipient may trigger its claim.
+fn check_recipient_sig(campaign: &Campaign, recipient_ai: &AccountInfo) -> ProgramResult {
+    if campaign.require_recipient_sig && !recipient_ai.is_signer {
+        msg!("recipient must sign claims for this campaign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Reject oversized proofs before spending compute on hashing them, and log
+/// the depth of accepted ones so operators can see it in transaction logs.
+fn check_proof_len(proof: &[[u8;32]]) -> ProgramResult {
+    if proof.len() > MAX_PROOF_LEN {
+        msg!("proof too long ({} > {})", proof.len(), MAX_PROOF_LEN);
+        return Err(ProgramError::Custom(5));
+    }
+    msg!("proof depth {}", proof.len());
+    Ok(())
+}
+
+/// Unpack the escrow token account and require it to hold `mint` under the campaign PDA's authority.
+fn check_escrow(escrow_ai: &AccountInfo, campaign_pda: &Pubkey, mint: &Pubkey) -> Result<spl_token::state::Account, ProgramError> {
+    if *escrow_ai.owner != spl_token::id() {
+        msg!("escrow is not a token account");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let escrow = spl_token::state::Account::unpack(&escrow_ai.data.borrow())?;
+    if escrow.owner != *campaign_pda {
+        msg!("escrow authority is not the campaign PDA");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    if escrow.mint != *mint {
+        msg!("escrow mint does not match campaign mint");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(escrow)
+}
+
+/// Create the per-leaf nullifier PDA `["nullifier", campaign_pda, recipient, nonce16]`.
+#[allow(clippy::too_many_arguments)]
+fn create_nullifier<'a>(
+    program_id: &Pubkey,
+    payer_ai: &AccountInfo<'a>,
+    nullifier_ai: &AccountInfo<'a>,
+    system_ai: &AccountInfo<'a>,
+    rent: &Rent,
+    campaign_pda: &Pubkey,
+    recipient: &Pubkey,
+    nonce16: [u8;16],
+) -> ProgramResult {
+    let (nullifier_pda, null_bump) = Pubkey::find_program_address(&[SEED_NULLIFIER, campaign_pda.as_ref(), recipient.as_ref(), &nonce16], program_id);
     if nullifier_pda != *nullifier_ai.key { return Err(ProgramError::InvalidSeeds); }
 
-    // create nullifier account (one-time claim)
     if !nullifier_ai.data_is_empty() {
         msg!("already claimed");
         return Err(ProgramError::Custom(2));
     }
 
-    let rent = Rent::from_account_info(rent_sysvar)?;
     let null_state = Nullifier {
-        campaign: campaign_pda,
-        recipient: *recipient_ai.key,
+        campaign: *campaign_pda,
+        recipient: *recipient,
         nonce16,
     };
-    let null_bytes = null_state.try_to_vec()?;
+    let null_bytes = borsh::to_vec(&null_state)?;
     let space = null_bytes.len();
     let lamports = rent.minimum_balance(space);
 
     invoke_signed(
         &system_instruction::create_account(payer_ai.key, nullifier_ai.key, lamports, space as u64, program_id),
         &[payer_ai.clone(), nullifier_ai.clone(), system_ai.clone()],
-        &[&[SEED_NULLIFIER, campaign_pda.as_ref(), recipient_ai.key.as_ref(), &[null_bump]]],
+        &[&[SEED_NULLIFIER, campaign_pda.as_ref(), recipient.as_ref(), &nonce16, &[null_bump]]],
     )?;
     nullifier_ai.data.borrow_mut()[..space].copy_from_slice(&null_bytes);
+    Ok(())
+}
 
-    // verify proof
-    let leaf = claim_leaf(campaign.campaign_id, recipient_ai.key, allocation, nonce16);
-    if !verify_merkle(leaf, &proof, campaign.merkle_root) {
-        msg!("invalid proof");
-        return Err(ProgramError::Custom(3));
+/// Create the PDA `target` with `space` bytes, owned by `program_id` and funded by `payer`.
+///
+/// `create_account` fails on an address that already holds lamports, so a
+/// transfer to a predictable PDA would block it for good. A pre-funded
+/// account is instead topped up to rent exemption, then allocated and
+/// assigned under `seeds`.
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    target: &AccountInfo<'a>,
+    system: &AccountInfo<'a>,
+    rent: &Rent,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = rent.minimum_balance(space);
+    if target.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, target.key, lamports, space as u64, program_id),
+            &[payer.clone(), target.clone(), system.clone()],
+            &[seeds],
+        );
     }
 
-    // token transfer from escrow token account -> recipient ATA
-    // Expect escrow token account authority is campaign PDA.
+    let top_up = lamports.saturating_sub(target.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, target.key, top_up),
+            &[payer.clone(), target.clone(), system.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, program_id),
+        &[target.clone(), system.clone()],
+        &[seeds],
+    )
+}
+
+/// Add `amount` to the recipient's running total, creating its PDA on first
+/// claim, and fail if the total would exceed `cap`.
+#[allow(clippy::too_many_arguments)]
+fn add_recipient_claimed<'a>(
+    program_id: &Pubkey,
+    payer_ai: &AccountInfo<'a>,
+    claimed_ai: &AccountInfo<'a>,
+    system_ai: &AccountInfo<'a>,
+    rent: &Rent,
+    campaign_pda: &Pubkey,
+    recipient: &Pubkey,
+    amount: u64,
+    cap: u64,
+) -> ProgramResult {
+    let (claimed_pda, claimed_bump) = Pubkey::find_program_address(&[SEED_RECIPIENT, campaign_pda.as_ref(), recipient.as_ref()], program_id);
+    if claimed_pda != *claimed_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    let mut state = if claimed_ai.data_is_empty() {
+        let space = borsh::to_vec(&RecipientClaimed::default())?.len();
+        create_pda_account(
+            program_id,
+            payer_ai,
+            claimed_ai,
+            system_ai,
+            rent,
+            space,
+            &[SEED_RECIPIENT, campaign_pda.as_ref(), recipient.as_ref(), &[claimed_bump]],
+        )?;
+        RecipientClaimed::default()
+    } else {
+        if claimed_ai.owner != program_id { return Err(ProgramError::IllegalOwner); }
+        RecipientClaimed::try_from_slice(&claimed_ai.data.borrow())?
+    };
+
+    state.claimed = state.claimed.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    if state.claimed > cap {
+        msg!("recipient cap exceeded ({} > {})", state.claimed, cap);
+        return Err(ProgramError::Custom(6));
+    }
+
+    let bytes = borsh::to_vec(&state)?;
+    claimed_ai.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+    Ok(())
+}
+
+/// Accounts are those of `Claim` minus the nullifier, followed by one
+/// nullifier per claim in order:
+/// `[mint, campaign, recipient, escrow, payer, recipient_ata, system, token, rent, recipient_claimed, nullifier_0..n]`
+fn process_claim_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    claims: Vec<(u64, [u8;16], Vec<[u8;32]>)>,
+) -> ProgramResult {
+    if claims.is_empty() || claims.len() > MAX_BATCH_CLAIMS {
+        msg!("batch size must be 1..={}", MAX_BATCH_CLAIMS);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    for (_, _, proof) in &claims {
+        check_proof_len(proof)?;
+    }
+
+    let acc_iter = &mut accounts.iter();
+    let mint_ai = next_account_info(acc_iter)?; // readonly
+    let campaign_ai = next_account_info(acc_iter)?; // writable
+    let recipient_ai = next_account_info(acc_iter)?; // readonly; signer if require_recipient_sig
+    let escrow_ai = next_account_info(acc_iter)?; // writable token acct
+    let payer_ai = next_account_info(acc_iter)?; // signer
+    let recipient_ata_ai = next_account_info(acc_iter)?; // writable token acct
+    let system_ai = next_account_info(acc_iter)?;
+    let token_ai = next_account_info(acc_iter)?;
+    let rent_sysvar = next_account_info(acc_iter)?;
+    let recipient_claimed_ai = next_account_info(acc_iter)?; // writable (system acct)
+
+    if !payer_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+
+    let mut campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if campaign.mint != *mint_ai.key { return Err(ProgramError::InvalidAccountData); }
+    if campaign.paused {
+        msg!("campaign paused");
+        return Err(ProgramError::Custom(7));
+    }
+    check_recipient_sig(&campaign, recipient_ai)?;
+
+    let now = solana_program::clock::Clock::get()?.unix_timestamp;
+    if now > campaign.expiry_unix {
+        msg!("campaign expired");
+        return Err(ProgramError::Custom(1));
+    }
+
+    let (campaign_pda, bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
+    if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    check_escrow(escrow_ai, &campaign_pda, &campaign.mint)?;
+
+    // verify every proof before touching any account, so a bad leaf claims nothing
+    let mut total: u64 = 0;
+    for (i, (allocation, nonce16, proof)) in claims.iter().enumerate() {
+        if claims[..i].iter().any(|(_, n, _)| n == nonce16) {
+            msg!("duplicate claim in batch");
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        let leaf = claim_leaf(campaign.campaign_id, recipient_ai.key, *allocation, *nonce16);
+        if !verify_merkle(leaf, proof, campaign.merkle_root) {
+            msg!("invalid proof at index {}", i);
+            return Err(ProgramError::Custom(3));
+        }
+        total = total.checked_add(*allocation).ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+
+    let rent = Rent::from_account_info(rent_sysvar)?;
+    for (_, nonce16, _) in &claims {
+        let nullifier_ai = next_account_info(acc_iter)?;
+        create_nullifier(program_id, payer_ai, nullifier_ai, system_ai, &rent, &campaign_pda, recipient_ai.key, *nonce16)?;
+    }
+    add_recipient_claimed(program_id, payer_ai, recipient_claimed_ai, system_ai, &rent, &campaign_pda, recipient_ai.key, total, campaign.max_per_recipient)?;
+    add_total_claimed(campaign_ai, &mut campaign, total)?;
+
     let ix = token_ix::transfer(
         token_ai.key,
         escrow_ai.key,
         recipient_ata_ai.key,
         &campaign_pda,
         &[],
-        allocation,
+        total,
     )?;
 
     invoke_signed(
@@ -242,6 +616,153 @@ fn process_claim(
         &[&[SEED_CAMPAIGN, &campaign.campaign_id, &[bump]]],
     )?;
 
-    msg!("claimed");
+    for (allocation, nonce16, _) in &claims {
+        emit_claim(campaign.campaign_id, recipient_ai.key, *allocation, *nonce16)?;
+    }
+    msg!("claimed {} leaves", claims.len());
+    Ok(())
+}
+
+fn process_sweep(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let acc_iter = &mut accounts.iter();
+    let campaign_ai = next_account_info(acc_iter)?; // readonly
+    let authority_ai = next_account_info(acc_iter)?; // signer
+    let escrow_ai = next_account_info(acc_iter)?; // writable token acct
+    let authority_ata_ai = next_account_info(acc_iter)?; // writable token acct
+    let token_ai = next_account_info(acc_iter)?;
+
+    if !authority_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+
+    let campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if campaign.authority != *authority_ai.key {
+        msg!("signer is not campaign authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (campaign_pda, bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
+    if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    let now = solana_program::clock::Clock::get()?.unix_timestamp;
+    if now <= campaign.expiry_unix {
+        msg!("campaign not expired");
+        return Err(ProgramError::Custom(4));
+    }
+
+    let remaining = check_escrow(escrow_ai, &campaign_pda, &campaign.mint)?.amount;
+
+    let ix = token_ix::transfer(
+        token_ai.key,
+        escrow_ai.key,
+        authority_ata_ai.key,
+        &campaign_pda,
+        &[],
+        remaining,
+    )?;
+
+    invoke_signed(
+        &ix,
+        &[escrow_ai.clone(), authority_ata_ai.clone(), token_ai.clone()],
+        &[&[SEED_CAMPAIGN, &campaign.campaign_id, &[bump]]],
+    )?;
+
+    msg!("swept {}", remaining);
+    Ok(())
+}
+
+fn process_deposit(program_id: &Pubkey, accounts: &[AccountInfo], amount: u64) -> ProgramResult {
+    let acc_iter = &mut accounts.iter();
+    let campaign_ai = next_account_info(acc_iter)?; // readonly
+    let authority_ai = next_account_info(acc_iter)?; // signer
+    let authority_ata_ai = next_account_info(acc_iter)?; // writable token acct
+    let escrow_ai = next_account_info(acc_iter)?; // writable token acct
+    let token_ai = next_account_info(acc_iter)?;
+
+    if !authority_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+
+    let campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if campaign.authority != *authority_ai.key {
+        msg!("signer is not campaign authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (campaign_pda, _bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
+    if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    let now = solana_program::clock::Clock::get()?.unix_timestamp;
+    if now > campaign.expiry_unix {
+        msg!("campaign expired");
+        return Err(ProgramError::Custom(1));
+    }
+
+    check_escrow(escrow_ai, &campaign_pda, &campaign.mint)?;
+
+    let ix = token_ix::transfer(
+        token_ai.key,
+        authority_ata_ai.key,
+        escrow_ai.key,
+        authority_ai.key,
+        &[],
+        amount,
+    )?;
+
+    invoke(
+        &ix,
+        &[authority_ata_ai.clone(), escrow_ai.clone(), authority_ai.clone(), token_ai.clone()],
+    )?;
+
+    msg!("deposited {}", amount);
+    Ok(())
+}
+
+fn process_extend_expiry(program_id: &Pubkey, accounts: &[AccountInfo], new_expiry_unix: i64) -> ProgramResult {
+    let acc_iter = &mut accounts.iter();
+    let campaign_ai = next_account_info(acc_iter)?; // writable
+    let authority_ai = next_account_info(acc_iter)?; // signer
+
+    if !authority_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+
+    let mut campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if campaign.authority != *authority_ai.key {
+        msg!("signer is not campaign authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (campaign_pda, _bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
+    if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    if new_expiry_unix < campaign.expiry_unix {
+        msg!("expiry can only be extended");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    campaign.expiry_unix = new_expiry_unix;
+    let bytes = borsh::to_vec(&campaign)?;
+    campaign_ai.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+
+    msg!("expiry extended to {}", new_expiry_unix);
+    Ok(())
+}
+
+fn process_set_paused(program_id: &Pubkey, accounts: &[AccountInfo], paused: bool) -> ProgramResult {
+    let acc_iter = &mut accounts.iter();
+    let campaign_ai = next_account_info(acc_iter)?; // writable
+    let authority_ai = next_account_info(acc_iter)?; // signer
+
+    if !authority_ai.is_signer { return Err(ProgramError::MissingRequiredSignature); }
+
+    let mut campaign: Campaign = Campaign::try_from_slice(&campaign_ai.data.borrow())?;
+    if campaign.authority != *authority_ai.key {
+        msg!("signer is not campaign authority");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let (campaign_pda, _bump) = Pubkey::find_program_address(&[SEED_CAMPAIGN, &campaign.campaign_id], program_id);
+    if campaign_pda != *campaign_ai.key { return Err(ProgramError::InvalidSeeds); }
+
+    campaign.paused = paused;
+    let bytes = borsh::to_vec(&campaign)?;
+    campaign_ai.data.borrow_mut()[..bytes.len()].copy_from_slice(&bytes);
+
+    msg!("campaign paused: {}", paused);
     Ok(())
 }
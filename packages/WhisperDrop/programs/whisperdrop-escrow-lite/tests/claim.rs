@@ -0,0 +1,128 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{Fixture, TestAccount, CAMPAIGN_ID, NOW};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use whisperdrop_escrow_lite::{ClaimEvent, Instruction as EscrowIx};
+
+#[test]
+fn same_recipient_claims_two_leaves_but_not_a_replay() {
+    let f = Fixture::new();
+
+    for claim in &f.claims[..2] {
+        f.claim(claim, false).unwrap();
+        let invokes = common::take_invokes();
+        assert_eq!(invokes[0].program_id, system_program::id());
+        assert_eq!(invokes[0].accounts[1].pubkey, f.nullifier(&claim.1));
+    }
+    assert_ne!(f.nullifier(&f.claims[0].1), f.nullifier(&f.claims[1].1));
+
+    assert_eq!(f.claim(&f.claims[0], true), Err(ProgramError::Custom(2)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn claim_emits_event() {
+    let f = Fixture::new();
+    let (allocation, nonce16, _) = f.claims[1].clone();
+    f.claim(&f.claims[1], false).unwrap();
+
+    let frames = common::take_data();
+    assert_eq!(frames.len(), 1);
+    assert_eq!(frames[0].len(), 1);
+    assert_eq!(
+        ClaimEvent::try_from_slice(&frames[0][0]).unwrap(),
+        ClaimEvent { campaign_id: CAMPAIGN_ID, recipient: f.recipient, allocation, nonce16 }
+    );
+}
+
+#[test]
+fn oversized_proof_is_rejected_early() {
+    let f = Fixture::new();
+    let (allocation, nonce16, _) = f.claims[0].clone();
+    let proof = vec![[0u8; 32]; whisperdrop_escrow_lite::MAX_PROOF_LEN + 1];
+
+    assert_eq!(f.claim(&(allocation, nonce16, proof), false), Err(ProgramError::Custom(5)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn empty_proof_is_rejected_when_leaf_is_not_root() {
+    let f = Fixture::new();
+    let (allocation, nonce16, _) = f.claims[0].clone();
+    assert_eq!(f.claim(&(allocation, nonce16, Vec::new()), false), Err(ProgramError::Custom(3)));
+}
+
+/// Init a campaign over a tree built with `claim_leaf`, then claim from the
+/// funded escrow. `Claim` takes, in order:
+/// `[mint, campaign, recipient, escrow, nullifier, payer (signer), recipient_ata, system, token, rent, recipient_claimed]`.
+#[test]
+fn init_then_claim_against_off_chain_tree() {
+    let f = Fixture::new();
+    let program_id = f.program_id;
+    let (mint, recipient) = (Pubkey::new_unique(), Pubkey::new_unique());
+    let (campaign_pda, _) = Pubkey::find_program_address(&[b"campaign", &CAMPAIGN_ID], &program_id);
+
+    let leaves: Vec<(u64, [u8; 16])> = vec![(250, [7; 16]), (500, [8; 16])];
+    let hashes: Vec<[u8; 32]> = leaves.iter().map(|(a, n)| common::claim_leaf(CAMPAIGN_ID, &recipient, *a, *n)).collect();
+    let (merkle_root, proofs) = common::merkle(&hashes);
+
+    // InitCampaign: [payer (signer), campaign, system, rent]
+    let mut init = vec![
+        TestAccount::signer(Pubkey::new_unique()),
+        TestAccount::new(campaign_pda),
+        TestAccount::new(system_program::id()),
+        TestAccount::rent(),
+    ];
+    let ix = EscrowIx::InitCampaign {
+        campaign_id: CAMPAIGN_ID,
+        manifest_hash: [0; 32],
+        merkle_root,
+        mint,
+        expiry_unix: NOW + 60,
+        authority: Pubkey::new_unique(),
+        max_per_recipient: u64::MAX,
+        require_recipient_sig: false,
+    };
+    f.run_at(NOW, &mut init, &ix).unwrap();
+    let mut campaign = init[1].clone();
+    campaign.owner = program_id;
+    let (recipient_claimed, _) =
+        Pubkey::find_program_address(&[b"recipient", campaign_pda.as_ref(), recipient.as_ref()], &program_id);
+
+    let (allocation, nonce16) = leaves[1];
+    let claim_accounts = |spent: bool| {
+        vec![
+            TestAccount::new(mint),
+            campaign.clone(),
+            TestAccount::new(recipient),
+            TestAccount::token(Pubkey::new_unique(), mint, campaign_pda, 1_000),
+            f.nullifier_account_for(&recipient, &nonce16, spent),
+            TestAccount::signer(Pubkey::new_unique()),
+            TestAccount::new(Pubkey::new_unique()),
+            TestAccount::new(system_program::id()),
+            TestAccount::new(spl_token::id()),
+            TestAccount::rent(),
+            TestAccount::new(recipient_claimed),
+        ]
+    };
+
+    let claim = EscrowIx::Claim { allocation, nonce16, proof: proofs[1].clone() };
+    let accounts = claim_accounts(false);
+    f.run(&accounts, &claim).unwrap();
+    let transfer = common::take_invokes().pop().unwrap();
+    assert_eq!(transfer.program_id, spl_token::id());
+    assert_eq!(transfer.accounts[0].pubkey, accounts[3].key);
+    assert_eq!(transfer.accounts[1].pubkey, accounts[6].key);
+    assert_eq!(transfer.accounts[2].pubkey, campaign_pda);
+    assert_eq!(transfer.data[1..], allocation.to_le_bytes());
+
+    assert_eq!(f.run(&claim_accounts(true), &claim), Err(ProgramError::Custom(2)));
+
+    let mut bad = proofs[1].clone();
+    bad[0][0] ^= 1;
+    let forged = EscrowIx::Claim { allocation, nonce16, proof: bad };
+    assert_eq!(f.run(&claim_accounts(false), &forged), Err(ProgramError::Custom(3)));
+    let wrong_leaf = EscrowIx::Claim { allocation: allocation + 1, nonce16, proof: proofs[1].clone() };
+    assert_eq!(f.run(&claim_accounts(false), &wrong_leaf), Err(ProgramError::Custom(3)));
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{Fixture, TestAccount};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use whisperdrop_escrow_lite::MAX_BATCH_CLAIMS;
+
+#[test]
+fn batch_creates_each_nullifier_and_pays_once() {
+    let f = Fixture::new();
+    let claims = f.claims[..3].to_vec();
+    let nullifiers: Vec<Pubkey> = claims.iter().map(|(_, n, _)| f.nullifier(n)).collect();
+
+    f.claim_batch(claims).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 5);
+    for (ix, nullifier) in invokes[..3].iter().zip(&nullifiers) {
+        assert_eq!(ix.program_id, system_program::id());
+        assert_eq!(ix.accounts[1].pubkey, *nullifier);
+    }
+    assert_eq!(invokes[3].accounts[1].pubkey, f.accounts[9].key);
+    assert_eq!(invokes[4].program_id, spl_token::id());
+    let transfer = spl_token::instruction::TokenInstruction::unpack(&invokes[4].data).unwrap();
+    assert_eq!(transfer, spl_token::instruction::TokenInstruction::Transfer { amount: 100 + 200 + 300 });
+    assert_eq!(common::take_data().len(), 3);
+}
+
+#[test]
+fn bad_last_proof_claims_nothing() {
+    let f = Fixture::new();
+    let mut claims = f.claims[..3].to_vec();
+    claims[2].0 += 1;
+
+    assert_eq!(f.claim_batch(claims), Err(ProgramError::Custom(3)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn oversized_or_duplicate_batch_is_rejected() {
+    let f = Fixture::new();
+    let claims = vec![f.claims[0].clone(); MAX_BATCH_CLAIMS + 1];
+    assert_eq!(f.claim_batch(claims), Err(ProgramError::InvalidInstructionData));
+
+    let f = Fixture::new();
+    let claims = vec![f.claims[0].clone(), f.claims[0].clone()];
+    assert_eq!(f.claim_batch(claims), Err(ProgramError::InvalidInstructionData));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn escrow_must_belong_to_campaign() {
+    let mut f = Fixture::new();
+    let mint = f.accounts[0].key;
+    f.accounts[3] = TestAccount::token(Pubkey::new_unique(), mint, Pubkey::new_unique(), 1_000);
+    let claims = f.claims[..1].to_vec();
+    assert_eq!(f.claim_batch(claims), Err(ProgramError::InvalidAccountData));
+
+    let mut f = Fixture::new();
+    let campaign_pda = f.accounts[1].key;
+    f.accounts[3] = TestAccount::token(Pubkey::new_unique(), Pubkey::new_unique(), campaign_pda, 1_000);
+    let claims = f.claims[..1].to_vec();
+    assert_eq!(f.claim_batch(claims), Err(ProgramError::InvalidAccountData));
+    assert!(common::take_invokes().is_empty());
+}
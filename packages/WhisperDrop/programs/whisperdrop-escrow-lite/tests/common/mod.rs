@@ -0,0 +1,337 @@
+//! Host-side harness: stubs the clock/rent sysvars and records CPIs, `msg!`
+//! lines and `sol_log_data` frames per test thread.
+//!
+//! System `create_account` and `allocate` CPIs are applied to the target
+//! `AccountInfo` (lamports and zeroed data) so handlers can write the new
+//! account's state.
+
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    hash::hash,
+    instruction::Instruction,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use borsh::BorshDeserialize;
+use whisperdrop_escrow_lite::{process_instruction, Campaign, Instruction as EscrowIx, RecipientClaimed};
+
+thread_local! {
+    static CLOCK_NOW: Cell<i64> = const { Cell::new(0) };
+    static INVOKES: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static DATA: RefCell<Vec<Vec<Vec<u8>>>> = const { RefCell::new(Vec::new()) };
+    static LOGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+struct HostStubs;
+
+impl SyscallStubs for HostStubs {
+    fn sol_log(&self, message: &str) {
+        LOGS.with(|l| l.borrow_mut().push(message.to_string()));
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        DATA.with(|d| d.borrow_mut().push(fields.iter().map(|f| f.to_vec()).collect()));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id == system_program::id() && instruction.data[..4] == [0, 0, 0, 0] {
+            // SystemInstruction::CreateAccount { lamports, space, owner }
+            let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(instruction.data[12..20].try_into().unwrap()) as usize;
+            let target = &instruction.accounts[1].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("created account passed");
+            **ai.lamports.borrow_mut() = lamports;
+            *ai.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+        } else if instruction.program_id == system_program::id() && instruction.data[..4] == [8, 0, 0, 0] {
+            // SystemInstruction::Allocate { space }
+            let space = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap()) as usize;
+            let target = &instruction.accounts[0].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("allocated account passed");
+            *ai.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+        }
+        INVOKES.with(|i| i.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: CLOCK_NOW.with(Cell::get), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+}
+
+/// Install the stubs (once per process), set the clock and clear recorded CPIs and logs.
+pub fn setup(now: i64) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(HostStubs));
+    });
+    CLOCK_NOW.with(|n| n.set(now));
+    INVOKES.with(|i| i.borrow_mut().clear());
+    DATA.with(|d| d.borrow_mut().clear());
+    LOGS.with(|l| l.borrow_mut().clear());
+}
+
+/// Drain the CPIs recorded on this thread.
+pub fn take_invokes() -> Vec<Instruction> {
+    INVOKES.with(|i| std::mem::take(&mut *i.borrow_mut()))
+}
+
+/// Drain the `sol_log_data` frames recorded on this thread.
+pub fn take_data() -> Vec<Vec<Vec<u8>>> {
+    DATA.with(|d| std::mem::take(&mut *d.borrow_mut()))
+}
+
+/// Drain the `msg!` lines recorded on this thread.
+pub fn take_logs() -> Vec<String> {
+    LOGS.with(|l| std::mem::take(&mut *l.borrow_mut()))
+}
+
+/// Account storage for one `AccountInfo`; `info` borrows it for a test's duration.
+#[derive(Clone)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey) -> Self {
+        Self { key, owner: system_program::id(), lamports: 0, data: Vec::new(), is_signer: false, is_writable: true }
+    }
+
+    pub fn signer(key: Pubkey) -> Self {
+        Self { is_signer: true, lamports: 1_000_000_000, ..Self::new(key) }
+    }
+
+    pub fn with_data(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self { owner, data, ..Self::new(key) }
+    }
+
+    /// An initialized SPL token account holding `amount` of `mint` under `authority`.
+    pub fn token(key: Pubkey, mint: Pubkey, authority: Pubkey, amount: u64) -> Self {
+        let account = spl_token::state::Account {
+            mint,
+            owner: authority,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        Self::with_data(key, spl_token::id(), data)
+    }
+
+    /// The rent sysvar account as `Rent::from_account_info` expects it.
+    pub fn rent() -> Self {
+        let rent = Rent::default();
+        let mut data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        data.push(rent.burn_percent);
+        Self::with_data(solana_program::sysvar::rent::id(), solana_program::sysvar::id(), data)
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            false,
+            0,
+        )
+    }
+}
+
+/// Merkle leaf as built by the program: sha256(0x00 || "wd:claim:v1" || campaign_id || recipient || allocation_le || nonce16).
+pub fn claim_leaf(campaign_id: [u8; 32], recipient: &Pubkey, allocation: u64, nonce16: [u8; 16]) -> [u8; 32] {
+    let mut buf = vec![0x00];
+    buf.extend_from_slice(b"wd:claim:v1");
+    buf.extend_from_slice(&campaign_id);
+    buf.extend_from_slice(recipient.as_ref());
+    buf.extend_from_slice(&allocation.to_le_bytes());
+    buf.extend_from_slice(&nonce16);
+    hash(&buf).to_bytes()
+}
+
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    let mut buf = vec![0x01];
+    buf.extend_from_slice(lo);
+    buf.extend_from_slice(hi);
+    hash(&buf).to_bytes()
+}
+
+/// Root and per-leaf proofs for a tree with a power-of-two number of leaves.
+pub fn merkle(leaves: &[[u8; 32]]) -> ([u8; 32], Vec<Vec<[u8; 32]>>) {
+    let mut proofs = vec![Vec::new(); leaves.len()];
+    let mut level = leaves.to_vec();
+    let mut index: Vec<usize> = (0..leaves.len()).collect();
+    while level.len() > 1 {
+        for (leaf, pos) in index.iter_mut().enumerate() {
+            proofs[leaf].push(level[*pos ^ 1]);
+            *pos /= 2;
+        }
+        level = level.chunks(2).map(|p| hash_pair(&p[0], &p[1])).collect();
+    }
+    (level[0], proofs)
+}
+
+pub const CAMPAIGN_ID: [u8; 32] = [1; 32];
+pub const NOW: i64 = 1_000;
+
+pub type Claim = (u64, [u8; 16], Vec<[u8; 32]>);
+
+/// A live campaign with four leaves for one recipient and a funded escrow.
+///
+/// `accounts` holds `[mint, campaign, recipient, escrow, payer, recipient_ata, system, token, rent, recipient_claimed]`.
+pub struct Fixture {
+    pub program_id: Pubkey,
+    pub recipient: Pubkey,
+    pub authority: Pubkey,
+    pub claims: Vec<Claim>,
+    pub accounts: Vec<TestAccount>,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let leaves: Vec<(u64, [u8; 16])> = (0..4u8).map(|i| (100 * (i as u64 + 1), [i; 16])).collect();
+        let hashes: Vec<[u8; 32]> = leaves.iter().map(|(a, n)| claim_leaf(CAMPAIGN_ID, &recipient, *a, *n)).collect();
+        let (root, proofs) = merkle(&hashes);
+        let claims = leaves.into_iter().zip(proofs).map(|((a, n), p)| (a, n, p)).collect();
+
+        let (campaign_pda, bump) = Pubkey::find_program_address(&[b"campaign", &CAMPAIGN_ID], &program_id);
+        let campaign = Campaign {
+            campaign_id: CAMPAIGN_ID,
+            manifest_hash: [0; 32],
+            merkle_root: root,
+            mint,
+            expiry_unix: NOW + 60,
+            authority,
+            bump,
+            max_per_recipient: u64::MAX,
+            paused: false,
+            total_claimed: 0,
+            require_recipient_sig: false,
+        };
+        let (recipient_claimed, _) = Pubkey::find_program_address(
+            &[b"recipient", campaign_pda.as_ref(), recipient.as_ref()],
+            &program_id,
+        );
+
+        let accounts = vec![
+            TestAccount::new(mint),
+            TestAccount::with_data(campaign_pda, program_id, borsh::to_vec(&campaign).unwrap()),
+            TestAccount::new(recipient),
+            TestAccount::token(Pubkey::new_unique(), mint, campaign_pda, 1_000),
+            TestAccount::signer(Pubkey::new_unique()),
+            TestAccount::new(Pubkey::new_unique()),
+            TestAccount::new(system_program::id()),
+            TestAccount::new(spl_token::id()),
+            TestAccount::rent(),
+            TestAccount::new(recipient_claimed),
+        ];
+        Self { program_id, recipient, authority, claims, accounts }
+    }
+
+    /// Rewrite the campaign with a per-recipient cap.
+    pub fn set_cap(&mut self, max_per_recipient: u64) {
+        let mut campaign = Campaign::try_from_slice(&self.accounts[1].data).unwrap();
+        campaign.max_per_recipient = max_per_recipient;
+        self.accounts[1].data = borsh::to_vec(&campaign).unwrap();
+    }
+
+    /// Mark the recipient as having already claimed `claimed` in total.
+    pub fn set_claimed(&mut self, claimed: u64) {
+        let account = &mut self.accounts[9];
+        account.owner = self.program_id;
+        account.lamports = 1;
+        account.data = borsh::to_vec(&RecipientClaimed { claimed }).unwrap();
+    }
+
+    pub fn nullifier(&self, nonce16: &[u8; 16]) -> Pubkey {
+        self.nullifier_for(&self.recipient, nonce16)
+    }
+
+    pub fn nullifier_for(&self, recipient: &Pubkey, nonce16: &[u8; 16]) -> Pubkey {
+        let (campaign_pda, _) = Pubkey::find_program_address(&[b"campaign", &CAMPAIGN_ID], &self.program_id);
+        Pubkey::find_program_address(
+            &[b"nullifier", campaign_pda.as_ref(), recipient.as_ref(), nonce16],
+            &self.program_id,
+        )
+        .0
+    }
+
+    /// A nullifier account for `nonce16`, optionally already created by an earlier claim.
+    pub fn nullifier_account(&self, nonce16: &[u8; 16], spent: bool) -> TestAccount {
+        self.nullifier_account_for(&self.recipient, nonce16, spent)
+    }
+
+    pub fn nullifier_account_for(&self, recipient: &Pubkey, nonce16: &[u8; 16], spent: bool) -> TestAccount {
+        let mut account = TestAccount::new(self.nullifier_for(recipient, nonce16));
+        if spent {
+            account.owner = self.program_id;
+            account.lamports = 1;
+            account.data = vec![1; 32 + 32 + 16];
+        }
+        account
+    }
+
+    pub fn run(&self, accounts: &[TestAccount], ix: &EscrowIx) -> Result<(), ProgramError> {
+        self.run_at(NOW, &mut accounts.to_vec(), ix)
+    }
+
+    /// Run `ix` at unix time `now`; data the program writes, including into
+    /// accounts it creates, lands back in `accounts`.
+    pub fn run_at(&self, now: i64, accounts: &mut [TestAccount], ix: &EscrowIx) -> Result<(), ProgramError> {
+        let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+        setup(now);
+        let result = process_instruction(&self.program_id, &infos, &borsh::to_vec(ix).unwrap());
+        let data: Vec<Vec<u8>> = infos.iter().map(|i| i.data.borrow().to_vec()).collect();
+        drop(infos);
+        for (account, data) in accounts.iter_mut().zip(data) {
+            account.data = data;
+        }
+        result
+    }
+
+    /// Single `Claim` of `claim`; `spent` marks its nullifier as already existing.
+    pub fn claim(&self, claim: &Claim, spent: bool) -> Result<(), ProgramError> {
+        let (allocation, nonce16, proof) = claim.clone();
+        let mut accounts = self.accounts.clone();
+        accounts.insert(4, self.nullifier_account(&nonce16, spent));
+        self.run(&accounts, &EscrowIx::Claim { allocation, nonce16, proof })
+    }
+
+    pub fn claim_batch(&self, claims: Vec<Claim>) -> Result<(), ProgramError> {
+        let mut accounts = self.accounts.clone();
+        accounts.extend(claims.iter().map(|(_, n, _)| self.nullifier_account(n, false)));
+        self.run(&accounts, &EscrowIx::ClaimBatch { claims })
+    }
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{Fixture, TestAccount, NOW};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use spl_token::instruction::TokenInstruction;
+use whisperdrop_escrow_lite::Instruction;
+
+fn deposit(f: &Fixture, signer: Pubkey, now: i64) -> Result<(), ProgramError> {
+    let mint = f.accounts[0].key;
+    let mut accounts = [
+        f.accounts[1].clone(),
+        TestAccount::signer(signer),
+        TestAccount::token(Pubkey::new_unique(), mint, signer, 5_000),
+        f.accounts[3].clone(),
+        f.accounts[7].clone(),
+    ];
+    f.run_at(now, &mut accounts, &Instruction::Deposit { amount: 2_500 })
+}
+
+#[test]
+fn authority_deposits_into_escrow() {
+    let f = Fixture::new();
+    deposit(&f, f.authority, NOW).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(invokes[0].accounts[1].pubkey, f.accounts[3].key);
+    assert_eq!(TokenInstruction::unpack(&invokes[0].data).unwrap(), TokenInstruction::Transfer { amount: 2_500 });
+}
+
+#[test]
+fn non_authority_cannot_deposit() {
+    let f = Fixture::new();
+    assert_eq!(deposit(&f, Pubkey::new_unique(), NOW), Err(ProgramError::InvalidAccountData));
+}
+
+#[test]
+fn expired_campaign_rejects_deposit() {
+    let f = Fixture::new();
+    assert_eq!(deposit(&f, f.authority, NOW + 61), Err(ProgramError::Custom(1)));
+    assert!(common::take_invokes().is_empty());
+}
@@ -0,0 +1,32 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{Fixture, TestAccount, NOW};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use whisperdrop_escrow_lite::{Campaign, Instruction};
+
+/// Returns the result and the campaign's expiry afterwards.
+fn extend(f: &Fixture, signer: Pubkey, new_expiry_unix: i64) -> (Result<(), ProgramError>, i64) {
+    let mut accounts = [f.accounts[1].clone(), TestAccount::signer(signer)];
+    let result = f.run_at(NOW, &mut accounts, &Instruction::ExtendExpiry { new_expiry_unix });
+    let campaign = Campaign::try_from_slice(&accounts[0].data).unwrap();
+    (result, campaign.expiry_unix)
+}
+
+#[test]
+fn authority_extends_expiry() {
+    let f = Fixture::new();
+    assert_eq!(extend(&f, f.authority, NOW + 3_600), (Ok(()), NOW + 3_600));
+}
+
+#[test]
+fn non_authority_cannot_extend() {
+    let f = Fixture::new();
+    assert_eq!(extend(&f, Pubkey::new_unique(), NOW + 3_600), (Err(ProgramError::InvalidAccountData), NOW + 60));
+}
+
+#[test]
+fn expiry_cannot_be_shortened() {
+    let f = Fixture::new();
+    assert_eq!(extend(&f, f.authority, NOW + 59), (Err(ProgramError::InvalidArgument), NOW + 60));
+}
@@ -0,0 +1,46 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::Fixture;
+use solana_program::{hash::hash, program_error::ProgramError, system_program};
+use whisperdrop_escrow_lite::{Campaign, Instruction};
+
+const MANIFEST: &[u8] = br#"{"name":"drop","leaves":4}"#;
+
+/// Fixture whose campaign commits to `MANIFEST`.
+fn fixture() -> Fixture {
+    let mut f = Fixture::new();
+    let mut campaign = Campaign::try_from_slice(&f.accounts[1].data).unwrap();
+    campaign.manifest_hash = hash(MANIFEST).to_bytes();
+    f.accounts[1].data = borsh::to_vec(&campaign).unwrap();
+    f
+}
+
+fn claim_with_manifest(f: &Fixture, manifest: &[u8]) -> Result<(), ProgramError> {
+    let (allocation, nonce16, proof) = f.claims[0].clone();
+    let mut accounts = f.accounts.clone();
+    accounts.insert(4, f.nullifier_account(&nonce16, false));
+    f.run(&accounts, &Instruction::ClaimWithManifest { allocation, nonce16, proof, manifest: manifest.to_vec() })
+}
+
+#[test]
+fn matching_manifest_claims() {
+    let f = fixture();
+    claim_with_manifest(&f, MANIFEST).unwrap();
+    let invokes = common::take_invokes();
+    assert_eq!(invokes[0].program_id, system_program::id());
+    assert_eq!(invokes.last().unwrap().program_id, spl_token::id());
+}
+
+#[test]
+fn other_manifest_is_rejected_before_claiming() {
+    let f = fixture();
+    assert_eq!(claim_with_manifest(&f, b"{}"), Err(ProgramError::Custom(8)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn plain_claim_ignores_the_manifest() {
+    let f = fixture();
+    f.claim(&f.claims[0], false).unwrap();
+}
@@ -0,0 +1,62 @@
+//! Fixed vector for the domain-separated tree; off-chain builders must reproduce these bytes.
+
+mod common;
+
+use common::{Fixture, TestAccount, CAMPAIGN_ID};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use whisperdrop_escrow_lite::Campaign;
+
+// campaign_id = [1; 32]
+// leaf A: recipient [2; 32], allocation 100, nonce16 [3; 16]
+// leaf B: recipient [4; 32], allocation 200, nonce16 [5; 16]
+const LEAF_A: &str = "63d3d1c95118068b8cba676f0591fb37b41fa4cd3675912719b8be1ba66e100e";
+const LEAF_B: &str = "e16b9ea8c40879fa7c746d90eab766c83695acd1959a2579c4a4573864d43fd3";
+const ROOT: &str = "798b2d6f41036e8a276bec67c9f07a56aefc2404eb76e036fc5d44856620528a";
+const UNPREFIXED_LEAF_B: &str = "01a8330442268596d14eeed8fb6f6d7900e0f01e01f8d813b2070951007bc8f3";
+const UNPREFIXED_ROOT: &str = "af3fa67b2f2f1261d64c83224de173039c661bcb4c0c76491bfb152aedf1efd7";
+
+fn hex32(s: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    out
+}
+
+/// Fixture whose campaign root and recipient are the vector's.
+fn vector_fixture(root: [u8; 32]) -> Fixture {
+    let mut f = Fixture::new();
+    let recipient = Pubkey::new_from_array([2; 32]);
+    let mut campaign: Campaign = borsh::from_slice(&f.accounts[1].data).unwrap();
+    campaign.merkle_root = root;
+    f.accounts[1].data = borsh::to_vec(&campaign).unwrap();
+    f.accounts[2] = TestAccount::new(recipient);
+    f.recipient = recipient;
+    let campaign_pda = f.accounts[1].key;
+    let (recipient_claimed, _) =
+        Pubkey::find_program_address(&[b"recipient", campaign_pda.as_ref(), recipient.as_ref()], &f.program_id);
+    f.accounts[9] = TestAccount::new(recipient_claimed);
+    f
+}
+
+#[test]
+fn leaves_and_root_match_vector() {
+    let a = common::claim_leaf(CAMPAIGN_ID, &Pubkey::new_from_array([2; 32]), 100, [3; 16]);
+    let b = common::claim_leaf(CAMPAIGN_ID, &Pubkey::new_from_array([4; 32]), 200, [5; 16]);
+    assert_eq!(a, hex32(LEAF_A));
+    assert_eq!(b, hex32(LEAF_B));
+    assert_eq!(common::merkle(&[a, b]).0, hex32(ROOT));
+}
+
+#[test]
+fn program_accepts_vector_proof() {
+    let f = vector_fixture(hex32(ROOT));
+    f.claim(&(100, [3; 16], vec![hex32(LEAF_B)]), false).unwrap();
+}
+
+#[test]
+fn unprefixed_tree_no_longer_verifies() {
+    // The same two leaves hashed without the 0x00/0x01 prefixes.
+    let f = vector_fixture(hex32(UNPREFIXED_ROOT));
+    assert_eq!(f.claim(&(100, [3; 16], vec![hex32(UNPREFIXED_LEAF_B)]), false), Err(ProgramError::Custom(3)));
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{claim_leaf, hash_pair, merkle, CAMPAIGN_ID};
+use solana_program::pubkey::Pubkey;
+use whisperdrop_escrow_lite::{verify_merkle, verify_multiproof};
+
+/// OpenZeppelin tree layout: root at 0, children of `i` at `2i + 1` and `2i + 2`,
+/// leaf `k` at `len - 1 - k`.
+fn oz_tree(leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    let len = 2 * leaves.len() - 1;
+    let mut tree = vec![[0u8; 32]; len];
+    for (k, leaf) in leaves.iter().enumerate() {
+        tree[len - 1 - k] = *leaf;
+    }
+    for i in (0..leaves.len() - 1).rev() {
+        tree[i] = hash_pair(&tree[2 * i + 1], &tree[2 * i + 2]);
+    }
+    tree
+}
+
+/// Leaves (in proof order), proof and flags for the leaves at `indices`.
+fn multiproof(tree: &[[u8; 32]], leaf_count: usize, indices: &[usize]) -> (Vec<[u8; 32]>, Vec<[u8; 32]>, Vec<bool>) {
+    let mut queue: Vec<usize> = indices.iter().map(|k| tree.len() - 1 - k).collect();
+    queue.sort_unstable_by(|a, b| b.cmp(a));
+    let leaves = queue.iter().map(|&i| tree[i]).collect();
+    assert!(queue.iter().all(|&i| i >= leaf_count - 1));
+
+    let (mut proof, mut flags) = (Vec::new(), Vec::new());
+    while queue[0] != 0 {
+        let j = queue.remove(0);
+        let sibling = if j % 2 == 1 { j + 1 } else { j - 1 };
+        if queue.first() == Some(&sibling) {
+            flags.push(true);
+            queue.remove(0);
+        } else {
+            flags.push(false);
+            proof.push(tree[sibling]);
+        }
+        queue.push((j - 1) / 2);
+    }
+    (leaves, proof, flags)
+}
+
+fn leaves(n: u8) -> Vec<[u8; 32]> {
+    let recipient = Pubkey::new_unique();
+    (0..n).map(|i| claim_leaf(CAMPAIGN_ID, &recipient, 100 * (i as u64 + 1), [i; 16])).collect()
+}
+
+#[test]
+fn multiproof_agrees_with_single_proofs() {
+    let hashes = leaves(8);
+    let tree = oz_tree(&hashes);
+    let (root, proofs) = merkle(&hashes);
+    assert_eq!(tree[0], root);
+
+    for indices in [vec![0], vec![0, 1], vec![2, 5], vec![1, 2, 3, 6], (0..8).collect()] {
+        let (batch, proof, flags) = multiproof(&tree, hashes.len(), &indices);
+        assert!(verify_multiproof(&batch, &proof, &flags, root), "{indices:?}");
+        assert!(indices.iter().all(|&k| verify_merkle(hashes[k], &proofs[k], root)));
+        // Shared ancestors are hashed once, so the batch never needs more siblings.
+        assert!(proof.len() <= indices.iter().map(|&k| proofs[k].len()).sum());
+    }
+}
+
+#[test]
+fn tampered_leaf_fails_like_a_single_proof() {
+    let hashes = leaves(8);
+    let tree = oz_tree(&hashes);
+    let (root, proofs) = merkle(&hashes);
+    let (mut batch, proof, flags) = multiproof(&tree, hashes.len(), &[1, 4, 7]);
+
+    batch[1][0] ^= 1;
+    assert!(!verify_multiproof(&batch, &proof, &flags, root));
+    assert!(!verify_merkle(batch[1], &proofs[4], root));
+}
+
+#[test]
+fn malformed_proofs_are_rejected() {
+    let hashes = leaves(8);
+    let tree = oz_tree(&hashes);
+    let root = tree[0];
+    let (batch, proof, flags) = multiproof(&tree, hashes.len(), &[2, 5]);
+
+    let mut extra = proof.clone();
+    extra.push([0; 32]);
+    assert!(!verify_multiproof(&batch, &extra, &flags, root));
+
+    let mut flipped = flags.clone();
+    let i = flipped.iter().position(|f| !f).unwrap();
+    flipped[i] = true;
+    assert!(!verify_multiproof(&batch, &proof, &flipped, root));
+
+    assert!(!verify_multiproof(&[], &[root], &[], root));
+    assert!(!verify_multiproof(&batch[..1], &proof, &flags, root));
+}
+
+#[test]
+fn single_leaf_tree_needs_no_steps() {
+    let leaf = leaves(1)[0];
+    assert!(verify_multiproof(&[leaf], &[], &[], leaf));
+    assert!(!verify_multiproof(&[leaf], &[], &[], [0; 32]));
+}
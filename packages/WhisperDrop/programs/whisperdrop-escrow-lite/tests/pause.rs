@@ -0,0 +1,37 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{Fixture, TestAccount, NOW};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use whisperdrop_escrow_lite::{Campaign, Instruction};
+
+/// Run `SetPaused` signed by `signer`, keeping the campaign update on success.
+fn set_paused(f: &mut Fixture, signer: Pubkey, paused: bool) -> Result<(), ProgramError> {
+    let mut accounts = [f.accounts[1].clone(), TestAccount::signer(signer)];
+    f.run_at(NOW, &mut accounts, &Instruction::SetPaused { paused })?;
+    f.accounts[1] = accounts[0].clone();
+    Ok(())
+}
+
+#[test]
+fn claims_fail_while_paused_and_resume_after_unpause() {
+    let mut f = Fixture::new();
+    let authority = f.authority;
+
+    set_paused(&mut f, authority, true).unwrap();
+    assert!(Campaign::try_from_slice(&f.accounts[1].data).unwrap().paused);
+    assert_eq!(f.claim(&f.claims[0], false), Err(ProgramError::Custom(7)));
+    assert_eq!(f.claim_batch(f.claims[..2].to_vec()), Err(ProgramError::Custom(7)));
+    assert!(common::take_invokes().is_empty());
+
+    set_paused(&mut f, authority, false).unwrap();
+    f.claim(&f.claims[0], false).unwrap();
+    f.claim_batch(f.claims[1..3].to_vec()).unwrap();
+}
+
+#[test]
+fn non_authority_cannot_pause() {
+    let mut f = Fixture::new();
+    assert_eq!(set_paused(&mut f, Pubkey::new_unique(), true), Err(ProgramError::InvalidAccountData));
+    f.claim(&f.claims[0], false).unwrap();
+}
@@ -0,0 +1,46 @@
+//! `proof depth <n>` logging on accepted claims.
+
+mod common;
+
+use common::Fixture;
+use whisperdrop_escrow_lite::{Campaign, MAX_PROOF_LEN};
+
+/// Fixture whose root commits to `claims[0]` under a proof of `depth` arbitrary siblings.
+fn deep_fixture(depth: usize) -> (Fixture, common::Claim) {
+    let mut f = Fixture::new();
+    let (allocation, nonce16, _) = f.claims[0].clone();
+    let proof: Vec<[u8; 32]> = (0..depth).map(|i| [i as u8 + 1; 32]).collect();
+    let leaf = common::claim_leaf(common::CAMPAIGN_ID, &f.recipient, allocation, nonce16);
+    let root = proof.iter().fold(leaf, |acc, sibling| common::hash_pair(&acc, sibling));
+
+    let mut campaign: Campaign = borsh::from_slice(&f.accounts[1].data).unwrap();
+    campaign.merkle_root = root;
+    f.accounts[1].data = borsh::to_vec(&campaign).unwrap();
+    (f, (allocation, nonce16, proof))
+}
+
+#[test]
+fn claim_logs_proof_depth() {
+    let (f, claim) = deep_fixture(MAX_PROOF_LEN);
+    f.claim(&claim, false).unwrap();
+    assert!(common::take_logs().contains(&format!("proof depth {MAX_PROOF_LEN}")));
+}
+
+#[test]
+fn batch_logs_each_proof_depth() {
+    let f = Fixture::new();
+    f.claim_batch(f.claims[..2].to_vec()).unwrap();
+    let depth = f.claims[0].2.len();
+    let logged = common::take_logs().iter().filter(|l| **l == format!("proof depth {depth}")).count();
+    assert_eq!(logged, 2);
+}
+
+#[test]
+fn depth_is_not_logged_past_the_cap() {
+    let (f, (allocation, nonce16, mut proof)) = deep_fixture(MAX_PROOF_LEN);
+    proof.push([0xee; 32]);
+    assert!(f.claim(&(allocation, nonce16, proof), false).is_err());
+    let logs = common::take_logs();
+    assert!(logs.iter().any(|l| l.starts_with("proof too long")));
+    assert!(!logs.iter().any(|l| l.starts_with("proof depth")));
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::Fixture;
+use solana_program::{program_error::ProgramError, system_program};
+use whisperdrop_escrow_lite::{Instruction, RecipientClaimed};
+
+#[test]
+fn claims_up_to_the_cap_then_rejects() {
+    let mut f = Fixture::new();
+    f.set_cap(300);
+
+    f.claim_batch(f.claims[..2].to_vec()).unwrap();
+    common::take_invokes();
+
+    f.set_claimed(300);
+    assert_eq!(f.claim(&f.claims[2], false), Err(ProgramError::Custom(6)));
+}
+
+#[test]
+fn batch_over_the_cap_is_rejected_whole() {
+    let mut f = Fixture::new();
+    f.set_cap(500);
+    assert_eq!(f.claim_batch(f.claims[..3].to_vec()), Err(ProgramError::Custom(6)));
+}
+
+#[test]
+fn prior_total_counts_toward_the_cap() {
+    let mut f = Fixture::new();
+    f.set_cap(400);
+    f.set_claimed(200);
+
+    assert_eq!(f.claim(&f.claims[2], false), Err(ProgramError::Custom(6)));
+    f.claim(&f.claims[1], false).unwrap();
+}
+
+#[test]
+fn pre_funded_recipient_pda_is_still_created() {
+    let mut f = Fixture::new();
+    // Someone sent lamports to the predictable PDA ahead of the first claim.
+    f.accounts[9].lamports = 1_000;
+
+    let (allocation, nonce16, proof) = f.claims[0].clone();
+    let mut accounts = f.accounts.clone();
+    accounts.insert(4, f.nullifier_account(&nonce16, false));
+    f.run_at(common::NOW, &mut accounts, &Instruction::Claim { allocation, nonce16, proof }).unwrap();
+
+    let system: Vec<u8> = common::take_invokes()
+        .iter()
+        .filter(|ix| ix.program_id == system_program::id())
+        .map(|ix| ix.data[0])
+        .collect();
+    // Nullifier create_account, then the recipient PDA's top-up, allocate and assign.
+    assert_eq!(system, [0, 2, 8, 1]);
+    assert_eq!(RecipientClaimed::try_from_slice(&accounts[10].data).unwrap().claimed, 100);
+}
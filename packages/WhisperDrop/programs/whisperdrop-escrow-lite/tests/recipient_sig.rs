@@ -0,0 +1,37 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::Fixture;
+use solana_program::program_error::ProgramError;
+use whisperdrop_escrow_lite::Campaign;
+
+fn fixture(require_recipient_sig: bool, recipient_signs: bool) -> Fixture {
+    let mut f = Fixture::new();
+    let mut campaign = Campaign::try_from_slice(&f.accounts[1].data).unwrap();
+    campaign.require_recipient_sig = require_recipient_sig;
+    f.accounts[1].data = borsh::to_vec(&campaign).unwrap();
+    f.accounts[2].is_signer = recipient_signs;
+    f
+}
+
+#[test]
+fn permissionless_campaign_accepts_any_payer() {
+    let f = fixture(false, false);
+    f.claim(&f.claims[0], false).unwrap();
+    f.claim_batch(f.claims[1..3].to_vec()).unwrap();
+}
+
+#[test]
+fn authorized_campaign_rejects_unsigned_recipient() {
+    let f = fixture(true, false);
+    assert_eq!(f.claim(&f.claims[0], false), Err(ProgramError::MissingRequiredSignature));
+    assert_eq!(f.claim_batch(f.claims[1..3].to_vec()), Err(ProgramError::MissingRequiredSignature));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn authorized_campaign_accepts_signing_recipient() {
+    let f = fixture(true, true);
+    f.claim(&f.claims[0], false).unwrap();
+    f.claim_batch(f.claims[1..3].to_vec()).unwrap();
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::TestAccount;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use spl_token::instruction::TokenInstruction;
+use whisperdrop_escrow_lite::{process_instruction, Campaign, Instruction};
+
+const CAMPAIGN_ID: [u8; 32] = [2; 32];
+const EXPIRY: i64 = 1_000;
+
+fn sweep(now: i64, signer_is_authority: bool) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let (campaign_pda, bump) = Pubkey::find_program_address(&[b"campaign", &CAMPAIGN_ID], &program_id);
+    let campaign = Campaign {
+        campaign_id: CAMPAIGN_ID,
+        manifest_hash: [0; 32],
+        merkle_root: [0; 32],
+        mint,
+        expiry_unix: EXPIRY,
+        authority,
+        bump,
+        max_per_recipient: u64::MAX,
+        paused: false,
+        total_claimed: 0,
+        require_recipient_sig: false,
+    };
+
+    let signer = if signer_is_authority { authority } else { Pubkey::new_unique() };
+    let mut accounts = [
+        TestAccount::with_data(campaign_pda, program_id, borsh::to_vec(&campaign).unwrap()),
+        TestAccount::signer(signer),
+        TestAccount::token(Pubkey::new_unique(), mint, campaign_pda, 750),
+        TestAccount::new(Pubkey::new_unique()),
+        TestAccount::new(spl_token::id()),
+    ];
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    common::setup(now);
+    process_instruction(&program_id, &infos, &borsh::to_vec(&Instruction::Sweep).unwrap())
+}
+
+#[test]
+fn sweep_before_expiry_fails() {
+    assert_eq!(sweep(EXPIRY, true), Err(ProgramError::Custom(4)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn sweep_after_expiry_moves_remaining_balance() {
+    sweep(EXPIRY + 1, true).unwrap();
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(TokenInstruction::unpack(&invokes[0].data).unwrap(), TokenInstruction::Transfer { amount: 750 });
+}
+
+#[test]
+fn sweep_requires_campaign_authority() {
+    assert_eq!(sweep(EXPIRY + 1, false), Err(ProgramError::InvalidAccountData));
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::{Fixture, TestAccount, NOW};
+use solana_program::{
+    instruction::Instruction as Ix, program_error::ProgramError, pubkey::Pubkey, rent::Rent, system_program,
+};
+use whisperdrop_escrow_lite::{process_instruction, Campaign, Instruction};
+
+fn campaign(f: &Fixture) -> Campaign {
+    Campaign::try_from_slice(&f.accounts[1].data).unwrap()
+}
+
+/// Run `ix` over `accounts`, keeping the campaign it writes.
+fn run_keep(f: &mut Fixture, mut accounts: Vec<TestAccount>, ix: Instruction) -> Result<(), ProgramError> {
+    f.run_at(NOW, &mut accounts, &ix)?;
+    f.accounts[1] = accounts[1].clone();
+    Ok(())
+}
+
+#[test]
+fn counter_tracks_single_and_batch_claims() {
+    let mut f = Fixture::new();
+    let (allocation, nonce16, proof) = f.claims[0].clone();
+    let mut accounts = f.accounts.clone();
+    accounts.insert(4, f.nullifier_account(&nonce16, false));
+    run_keep(&mut f, accounts, Instruction::Claim { allocation, nonce16, proof }).unwrap();
+    assert_eq!(campaign(&f).total_claimed, 100);
+
+    f.set_claimed(100);
+    let claims = f.claims[1..3].to_vec();
+    let mut accounts = f.accounts.clone();
+    accounts.extend(claims.iter().map(|(_, n, _)| f.nullifier_account(n, false)));
+    run_keep(&mut f, accounts, Instruction::ClaimBatch { claims }).unwrap();
+    assert_eq!(campaign(&f).total_claimed, 100 + 200 + 300);
+}
+
+#[test]
+fn counter_overflow_is_rejected() {
+    let mut f = Fixture::new();
+    let mut state = campaign(&f);
+    state.total_claimed = u64::MAX - 50;
+    f.accounts[1].data = borsh::to_vec(&state).unwrap();
+
+    assert_eq!(f.claim(&f.claims[0], false), Err(ProgramError::Custom(9)));
+}
+
+/// Run `InitCampaign` over `f`'s campaign cut to its first `short_len` bytes,
+/// as an older build would have written it. Returns the result, the account
+/// data afterwards and the CPIs issued.
+fn init_over_short_campaign(f: &Fixture, short_len: usize) -> (Result<(), ProgramError>, Vec<u8>, Vec<Ix>) {
+    let state = campaign(f);
+    let mut campaign = f.accounts[1].clone();
+    campaign.data.truncate(short_len);
+    campaign.lamports = Rent::default().minimum_balance(short_len);
+
+    let mut payer = TestAccount::signer(Pubkey::new_unique());
+    let mut system = TestAccount::new(system_program::id());
+    let mut rent = TestAccount::rent();
+    let infos = [payer.info(), campaign.info(), system.info(), rent.info()];
+    let ix = Instruction::InitCampaign {
+        campaign_id: state.campaign_id,
+        manifest_hash: [9; 32],
+        merkle_root: [9; 32],
+        mint: state.mint,
+        expiry_unix: state.expiry_unix,
+        authority: state.authority,
+        max_per_recipient: 1,
+        require_recipient_sig: true,
+    };
+    common::setup(NOW);
+    let result = process_instruction(&f.program_id, &infos, &borsh::to_vec(&ix).unwrap());
+    let data = infos[1].data.borrow().to_vec();
+    (result, data, common::take_invokes())
+}
+
+#[test]
+fn init_rejects_campaigns_from_older_builds() {
+    let f = Fixture::new();
+    let new_len = f.accounts[1].data.len();
+    // The original layout (without `max_per_recipient`, `paused`,
+    // `total_claimed` and `require_recipient_sig`), the one before
+    // `total_claimed`, and lengths no build ever wrote.
+    for short_len in [new_len - 18, new_len - 9, new_len - 1, 1] {
+        let (result, data, invokes) = init_over_short_campaign(&f, short_len);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData), "len {short_len}");
+        assert_eq!(data, f.accounts[1].data[..short_len]);
+        assert!(invokes.is_empty());
+    }
+}
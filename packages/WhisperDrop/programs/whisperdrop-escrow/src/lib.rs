@@ -1,9 +1,8 @@
-\
+#![allow(unexpected_cfgs)]
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use sha2::{Digest, Sha256};
 
-declare_id!("WDEscrow111111111111111111111111111111111");
+declare_id!("WDEscrow11111111111111111111111111111111111");
 
 #[program]
 pub mod whisperdrop_escrow {
@@ -15,8 +14,10 @@ pub mod whisperdrop_escrow {
         manifest_hash: [u8; 32],
         merkle_root: [u8; 32],
         expiry_unix: i64,
+        claim_deadline_unix: i64,
     ) -> Result<()> {
         require!(expiry_unix > 0, WhisperErr::BadExpiry);
+        require!(claim_deadline_unix >= expiry_unix, WhisperErr::BadClaimDeadline);
         let c = &mut ctx.accounts.campaign;
         c.authority = ctx.accounts.authority.key();
         c.campaign_id = campaign_id;
@@ -24,6 +25,7 @@ pub mod whisperdrop_escrow {
         c.merkle_root = merkle_root;
         c.mint = ctx.accounts.mint.key();
         c.expiry_unix = expiry_unix;
+        c.claim_deadline_unix = claim_deadline_unix;
         c.bump = ctx.bumps.campaign;
         c.escrow_bump = ctx.bumps.escrow;
         Ok(())
@@ -33,6 +35,8 @@ pub mod whisperdrop_escrow {
         require!(amount > 0, WhisperErr::BadAmount);
         let c = &ctx.accounts.campaign;
         require!(ctx.accounts.authority.key() == c.authority, WhisperErr::NotAuthority);
+        // Funding closes at expiry; claims may run on until the claim deadline.
+        require!(Clock::get()?.unix_timestamp <= c.expiry_unix, WhisperErr::Expired);
 
         let cpi_accounts = Transfer {
             from: ctx.accounts.from_ata.to_account_info(),
@@ -41,42 +45,39 @@ pub mod whisperdrop_escrow {
         };
         let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
         token::transfer(cpi_ctx, amount)?;
+
+        emit!(Deposited { campaign: ctx.accounts.campaign.key(), amount });
         Ok(())
     }
 
+    /// Claim whatever is left of `allocation`.
     pub fn claim(
         ctx: Context<Claim>,
         allocation: u64,
         nonce_hex_16: [u8; 16],
         proof: Vec<[u8; 32]>,
     ) -> Result<()> {
-        let c = &ctx.accounts.campaign;
-        let now = Clock::get()?.unix_timestamp;
-        require!(now <= c.expiry_unix, WhisperErr::Expired);
-        require!(allocation > 0, WhisperErr::BadAmount);
-
-        // Verify merkle proof
-        let recipient = ctx.accounts.recipient.key();
-        let leaf = leaf_hash(&c.campaign_id, &recipient, allocation, &nonce_hex_16);
-        let root = compute_root(leaf, &proof);
-        require!(root == c.merkle_root, WhisperErr::BadProof);
-
-        // Nullifier PDA is created in the account constraints; if it exists, tx fails.
-        // Transfer tokens from escrow to recipient ATA
-        let seeds = &[
-            b"campaign",
-            &c.campaign_id,
-            &[c.bump],
-        ];
-        let signer = &[&seeds[..]];
+        let remaining = allocation.saturating_sub(ctx.accounts.nullifier.claimed_so_far);
+        pay_claim(ctx, allocation, remaining, nonce_hex_16, proof)
+    }
 
-        let cpi_accounts = Transfer {
-            from: ctx.accounts.escrow.to_account_info(),
-            to: ctx.accounts.recipient_ata.to_account_info(),
-            authority: ctx.accounts.campaign.to_account_info(),
-        };
-        let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
-        token::transfer(cpi_ctx, allocation)?;
+    /// Claim `amount` of `allocation` now; the rest stays claimable until the
+    /// cumulative total reaches `allocation`.
+    pub fn claim_partial(
+        ctx: Context<Claim>,
+        allocation: u64,
+        nonce_hex_16: [u8; 16],
+        amount: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        pay_claim(ctx, allocation, amount, nonce_hex_16, proof)
+    }
+
+    /// Close a spent nullifier once claiming is over, returning its rent to the authority.
+    /// Until the claim deadline the nullifier is what stops a second claim, so it must stay.
+    pub fn close_nullifier(ctx: Context<CloseNullifier>, _recipient: Pubkey, _nonce_hex_16: [u8; 16]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now > ctx.accounts.campaign.claim_deadline_unix, WhisperErr::NotExpired);
         Ok(())
     }
 }
@@ -93,7 +94,7 @@ pub struct InitCampaign<'info> {
         init,
         payer = authority,
         space = 8 + Campaign::SPACE,
-        seeds = [b"campaign", &campaign_id],
+        seeds = [b"campaign", campaign_id.as_ref()],
         bump
     )]
     pub campaign: Account<'info, Campaign>,
@@ -141,7 +142,51 @@ pub struct Deposit<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+fn pay_claim(
+    ctx: Context<Claim>,
+    allocation: u64,
+    amount: u64,
+    nonce_hex_16: [u8; 16],
+    proof: Vec<[u8; 32]>,
+) -> Result<()> {
+    let c = &ctx.accounts.campaign;
+    let now = Clock::get()?.unix_timestamp;
+    require!(now <= c.claim_deadline_unix, WhisperErr::Expired);
+    require!(allocation > 0, WhisperErr::BadAmount);
+
+    // Verify merkle proof; checked on every call, not just the first.
+    let recipient = ctx.accounts.recipient.key();
+    let leaf = leaf_hash(&c.campaign_id, &recipient, allocation, &nonce_hex_16);
+    require!(whisperdrop_merkle::verify(leaf, &proof, c.merkle_root), WhisperErr::BadProof);
+
+    // The nullifier PDA is per leaf (seeded with the nonce), created on the first
+    // claim, and tracks how much of that leaf's allocation has been paid out since.
+    let claimed = ctx.accounts.nullifier.claimed_so_far;
+    require!(claimed < allocation, WhisperErr::AlreadyClaimed);
+    require!(amount > 0, WhisperErr::BadAmount);
+    let claimed = claimed.checked_add(amount).filter(|&t| t <= allocation).ok_or(WhisperErr::OverClaim)?;
+    ctx.accounts.nullifier.claimed_so_far = claimed;
+
+    // Transfer tokens from escrow to recipient ATA
+    let seeds: &[&[u8]] = &[b"campaign", &c.campaign_id, &[c.bump]];
+    let signer = &[seeds];
+
+    let cpi_accounts = Transfer {
+        from: ctx.accounts.escrow.to_account_info(),
+        to: ctx.accounts.recipient_ata.to_account_info(),
+        authority: ctx.accounts.campaign.to_account_info(),
+    };
+    let cpi_ctx = CpiContext::new_with_signer(ctx.accounts.token_program.to_account_info(), cpi_accounts, signer);
+    token::transfer(cpi_ctx, amount)?;
+
+    emit!(Claimed { campaign: ctx.accounts.campaign.key(), recipient, allocation, amount });
+    Ok(())
+}
+
+// `claim` and `claim_partial` both start with (allocation, nonce_hex_16), so
+// the nullifier seeds can read the nonce from either.
 #[derive(Accounts)]
+#[instruction(allocation: u64, nonce_hex_16: [u8; 16])]
 pub struct Claim<'info> {
     pub mint: Account<'info, Mint>,
 
@@ -161,10 +206,10 @@ pub struct Claim<'info> {
     pub escrow: Account<'info, TokenAccount>,
 
     #[account(
-        init,
+        init_if_needed,
         payer = payer,
         space = 8 + Nullifier::SPACE,
-        seeds = [b"nullifier", campaign.key().as_ref(), recipient.key().as_ref()],
+        seeds = [b"nullifier", campaign.key().as_ref(), recipient.key().as_ref(), nonce_hex_16.as_ref()],
         bump
     )]
     pub nullifier: Account<'info, Nullifier>,
@@ -179,6 +224,24 @@ pub struct Claim<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+#[instruction(recipient: Pubkey, nonce_hex_16: [u8; 16])]
+pub struct CloseNullifier<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(has_one = authority)]
+    pub campaign: Account<'info, Campaign>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [b"nullifier", campaign.key().as_ref(), recipient.as_ref(), nonce_hex_16.as_ref()],
+        bump
+    )]
+    pub nullifier: Account<'info, Nullifier>,
+}
+
 #[account]
 pub struct Campaign {
     pub authority: Pubkey,
@@ -189,19 +252,34 @@ pub struct Campaign {
     pub expiry_unix: i64,
     pub bump: u8,
     pub escrow_bump: u8,
+    pub claim_deadline_unix: i64,
 }
 
 impl Campaign {
-    pub const SPACE: usize = 32 + 32 + 32 + 32 + 32 + 8 + 1 + 1;
+    pub const SPACE: usize = 32 + 32 + 32 + 32 + 32 + 8 + 1 + 1 + 8;
 }
 
 #[account]
 pub struct Nullifier {
-    pub used: bool,
+    pub claimed_so_far: u64,
 }
 
 impl Nullifier {
-    pub const SPACE: usize = 1;
+    pub const SPACE: usize = 8;
+}
+
+#[event]
+pub struct Deposited {
+    pub campaign: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct Claimed {
+    pub campaign: Pubkey,
+    pub recipient: Pubkey,
+    pub allocation: u64,
+    pub amount: u64,
 }
 
 #[error_code]
@@ -216,49 +294,22 @@ pub enum WhisperErr {
     Expired,
     #[msg("Bad merkle proof")]
     BadProof,
-}
-
-fn sha256(data: &[u8]) -> [u8; 32] {
-    let mut h = Sha256::new();
-    h.update(data);
-    let out = h.finalize();
-    let mut arr = [0u8; 32];
-    arr.copy_from_slice(&out);
-    arr
+    #[msg("Campaign not expired")]
+    NotExpired,
+    #[msg("Allocation already claimed")]
+    AlreadyClaimed,
+    #[msg("Claim exceeds allocation")]
+    OverClaim,
+    #[msg("Claim deadline before expiry")]
+    BadClaimDeadline,
 }
 
 // leaf format mirrors Step 3a:
 // "wdleaf1|<campaignIdB64?>|<recipient>|<allocation>|<nonceHex>"
-// On-chain we do a binary-friendly encoding to avoid string parsing:
-// sha256( b"wdleaf1" || campaign_id(32) || recipient(32) || allocation(le64) || nonce(16) )
-fn leaf_hash(campaign_id: &[u8; 32], recipient: &Pubkey, allocation: u64, nonce16: &[u8; 16]) -> [u8; 32] {
-    let mut buf = Vec::with_capacity(6 + 32 + 32 + 8 + 16);
-    buf.extend_from_slice(b"wdleaf1");
-    buf.extend_from_slice(campaign_id);
-    buf.extend_from_slice(recipient.as_ref());
-    buf.extend_from_slice(&allocation.to_le_bytes());
-    buf.extend_from_slice(nonce16);
-    sha256(&buf)
-}
+// On-chain we do a binary-friendly encoding to avoid string parsing; the layout and
+// the 0x00 / 0x01 domain separation live in whisperdrop-merkle, shared with escrow-lite.
+const LEAF_TAG: &[u8] = b"wdleaf1";
 
-// order-independent parent: sha256(min||max)
-fn parent(a: [u8; 32], b: [u8; 32]) -> [u8; 32] {
-    if a <= b {
-        let mut buf = [0u8; 64];
-        buf[..32].copy_from_slice(&a);
-        buf[32..].copy_from_slice(&b);
-        sha256(&buf)
-    } else {
-        let mut buf = [0u8; 64];
-        buf[..32].copy_from_slice(&b);
-        buf[32..].copy_from_slice(&a);
-        sha256(&buf)
-    }
-}
-
-fn compute_root(mut acc: [u8; 32], proof: &Vec<[u8; 32]>) -> [u8; 32] {
-    for sib in proof.iter() {
-        acc = parent(acc, *sib);
-    }
-    acc
+fn leaf_hash(campaign_id: &[u8; 32], recipient: &Pubkey, allocation: u64, nonce16: &[u8; 16]) -> [u8; 32] {
+    whisperdrop_merkle::leaf_hash(LEAF_TAG, campaign_id, recipient, allocation, nonce16)
 }
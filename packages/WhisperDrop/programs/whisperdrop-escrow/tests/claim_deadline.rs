@@ -0,0 +1,65 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::AccountDeserialize;
+use common::{Fixture, TestAccount, CAMPAIGN_ID, NOW};
+use whisperdrop_escrow::{instruction, Campaign, WhisperErr};
+
+fn claim_at(fx: &Fixture, now: i64) -> std::result::Result<(), ProgramError> {
+    let (allocation, nonce_hex_16, proof) = fx.claims[0].clone();
+    common::run_at(now, &mut fx.claim_accounts(0), instruction::Claim { allocation, nonce_hex_16, proof })
+}
+
+fn deposit_at(fx: &Fixture, now: i64) -> std::result::Result<(), ProgramError> {
+    common::run_at(now, &mut fx.deposit_accounts(), instruction::Deposit { amount: 10 })
+}
+
+fn init(fx: &Fixture, expiry_unix: i64, claim_deadline_unix: i64) -> std::result::Result<Vec<TestAccount>, ProgramError> {
+    let mut accounts = fx.init_accounts();
+    common::run(
+        &mut accounts,
+        instruction::InitCampaign {
+            campaign_id: CAMPAIGN_ID,
+            manifest_hash: [0; 32],
+            merkle_root: fx.state.merkle_root,
+            expiry_unix,
+            claim_deadline_unix,
+        },
+    )?;
+    Ok(accounts)
+}
+
+#[test]
+fn init_stores_the_claim_deadline() {
+    let fx = Fixture::new();
+    let accounts = init(&fx, NOW + 60, NOW + 120).unwrap();
+    let campaign = Campaign::try_deserialize(&mut &accounts[2].data[..]).unwrap();
+    assert_eq!(campaign.expiry_unix, NOW + 60);
+    assert_eq!(campaign.claim_deadline_unix, NOW + 120);
+
+    // No grace window is fine too.
+    init(&fx, NOW + 60, NOW + 60).unwrap();
+}
+
+#[test]
+fn init_rejects_a_deadline_before_expiry() {
+    let fx = Fixture::new();
+    let err = init(&fx, NOW + 60, NOW + 59).err().unwrap();
+    assert_eq!(err, Error::from(WhisperErr::BadClaimDeadline).into());
+}
+
+#[test]
+fn grace_window_allows_claims_but_not_deposits() {
+    let fx = Fixture::new();
+    let (expiry, deadline) = (fx.state.expiry_unix, fx.state.claim_deadline_unix);
+
+    deposit_at(&fx, expiry).unwrap();
+    claim_at(&fx, expiry).unwrap();
+
+    for now in [expiry + 1, deadline] {
+        assert_eq!(deposit_at(&fx, now).unwrap_err(), Error::from(WhisperErr::Expired).into(), "deposit at {now}");
+        claim_at(&fx, now).unwrap();
+    }
+
+    assert_eq!(claim_at(&fx, deadline + 1).unwrap_err(), Error::from(WhisperErr::Expired).into());
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::{Fixture, TestAccount, NOW};
+use whisperdrop_escrow::{instruction, WhisperErr};
+
+/// `CloseNullifier` accounts: `[authority, campaign, nullifier]`.
+fn accounts(fx: &Fixture) -> Vec<TestAccount> {
+    vec![TestAccount::signer(fx.authority), fx.campaign_account(), fx.nullifier_account(0, 100)]
+}
+
+fn close_at(fx: &Fixture, now: i64, accounts: &mut [TestAccount]) -> std::result::Result<(), ProgramError> {
+    common::run_at(now, accounts, instruction::CloseNullifier { _recipient: fx.recipient, _nonce_hex_16: fx.claims[0].1 })
+}
+
+#[test]
+fn close_before_expiry_fails() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    let err = close_at(&fx, fx.state.expiry_unix, &mut accounts).unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::NotExpired).into());
+    assert_eq!(accounts[2].owner, whisperdrop_escrow::ID);
+}
+
+#[test]
+fn close_during_claim_grace_fails() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    let err = close_at(&fx, fx.state.claim_deadline_unix, &mut accounts).unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::NotExpired).into());
+}
+
+#[test]
+fn close_after_claim_deadline_returns_rent() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    let authority_before = accounts[0].lamports;
+    let rent = accounts[2].lamports;
+    close_at(&fx, fx.state.claim_deadline_unix + 1, &mut accounts).unwrap();
+
+    assert_eq!(accounts[0].lamports, authority_before + rent);
+    assert_eq!(accounts[2].lamports, 0);
+    assert!(accounts[2].data.is_empty());
+    assert_eq!(accounts[2].owner, anchor_lang::system_program::ID);
+}
+
+#[test]
+fn only_the_authority_can_close() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    accounts[0] = TestAccount::signer(Pubkey::new_unique());
+    assert!(close_at(&fx, NOW + 3_600, &mut accounts).is_err());
+    assert!(accounts[2].lamports > 0);
+}
+
+#[test]
+fn nullifier_must_match_the_recipient() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    let err = common::run_at(
+        fx.state.claim_deadline_unix + 1,
+        &mut accounts,
+        instruction::CloseNullifier { _recipient: Pubkey::new_unique(), _nonce_hex_16: fx.claims[0].1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, Error::from(ErrorCode::ConstraintSeeds).into());
+}
+
+#[test]
+fn nullifier_must_match_the_leaf() {
+    let fx = Fixture::new();
+    let mut accounts = accounts(&fx);
+    let err = common::run_at(
+        fx.state.claim_deadline_unix + 1,
+        &mut accounts,
+        instruction::CloseNullifier { _recipient: fx.recipient, _nonce_hex_16: fx.claims[1].1 },
+    )
+    .unwrap_err();
+    assert_eq!(err, Error::from(ErrorCode::ConstraintSeeds).into());
+}
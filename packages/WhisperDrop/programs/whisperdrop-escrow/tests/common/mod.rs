@@ -0,0 +1,331 @@
+//! Host-side harness: stubs the clock/rent sysvars and records CPIs and
+//! `sol_log_data` frames per test thread, then drives the Anchor `entry`
+//! over leaked `AccountInfo`s.
+//!
+//! System `create_account` CPIs are applied to the target `AccountInfo`
+//! (lamports, zeroed data and owner), and SPL `InitializeAccount3` packs the
+//! token account, so `init` constraints can deserialize them.
+
+#![allow(dead_code)]
+
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_pack::Pack,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    system_program,
+};
+use anchor_lang::{AccountSerialize, InstructionData};
+use anchor_spl::token::spl_token;
+use whisperdrop_escrow::{Campaign, Nullifier};
+
+thread_local! {
+    static CLOCK_NOW: Cell<i64> = const { Cell::new(0) };
+    static INVOKES: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static DATA: RefCell<Vec<Vec<Vec<u8>>>> = const { RefCell::new(Vec::new()) };
+}
+
+struct HostStubs;
+
+impl SyscallStubs for HostStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        DATA.with(|d| d.borrow_mut().push(fields.iter().map(|f| f.to_vec()).collect()));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        if instruction.program_id == system_program::id() && instruction.data[..4] == [0, 0, 0, 0] {
+            // SystemInstruction::CreateAccount { lamports, space, owner }
+            let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(instruction.data[12..20].try_into().unwrap()) as usize;
+            let owner = Pubkey::try_from(&instruction.data[20..52]).unwrap();
+            let target = &instruction.accounts[1].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("created account passed");
+            **ai.lamports.borrow_mut() = lamports;
+            *ai.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            ai.assign(&owner);
+        } else if instruction.program_id == spl_token::id() && instruction.data[0] == 18 {
+            // TokenInstruction::InitializeAccount3 { owner }
+            let account = spl_token::state::Account {
+                mint: instruction.accounts[1].pubkey,
+                owner: Pubkey::try_from(&instruction.data[1..33]).unwrap(),
+                state: spl_token::state::AccountState::Initialized,
+                ..Default::default()
+            };
+            let target = &instruction.accounts[0].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("token account passed");
+            spl_token::state::Account::pack(account, &mut ai.data.borrow_mut()).unwrap();
+        }
+        INVOKES.with(|i| i.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock { unix_timestamp: CLOCK_NOW.with(Cell::get), ..Clock::default() };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// Install the stubs (once per process), set the clock and clear recorded CPIs and logs.
+pub fn setup(now: i64) {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(HostStubs));
+    });
+    CLOCK_NOW.with(|n| n.set(now));
+    INVOKES.with(|i| i.borrow_mut().clear());
+    DATA.with(|d| d.borrow_mut().clear());
+}
+
+/// Drain the CPIs recorded on this thread.
+pub fn take_invokes() -> Vec<Instruction> {
+    INVOKES.with(|i| std::mem::take(&mut *i.borrow_mut()))
+}
+
+/// Drain the `sol_log_data` frames recorded on this thread.
+pub fn take_data() -> Vec<Vec<Vec<u8>>> {
+    DATA.with(|d| std::mem::take(&mut *d.borrow_mut()))
+}
+
+/// Account storage for one `AccountInfo`; `info` borrows it for a test's duration.
+#[derive(Clone)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey) -> Self {
+        Self {
+            key,
+            owner: system_program::id(),
+            lamports: 0,
+            data: Vec::new(),
+            is_signer: false,
+            is_writable: true,
+            executable: false,
+        }
+    }
+
+    pub fn signer(key: Pubkey) -> Self {
+        Self { is_signer: true, lamports: 1_000_000_000, ..Self::new(key) }
+    }
+
+    pub fn with_data(key: Pubkey, owner: Pubkey, data: Vec<u8>) -> Self {
+        Self { owner, data, lamports: 1_000_000, ..Self::new(key) }
+    }
+
+    /// An executable program account, as `Program<'info, T>` requires.
+    pub fn program(key: Pubkey) -> Self {
+        Self { executable: true, is_writable: false, ..Self::new(key) }
+    }
+
+    /// An initialized SPL mint.
+    pub fn mint(key: Pubkey) -> Self {
+        let mint = spl_token::state::Mint { decimals: 6, is_initialized: true, ..Default::default() };
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+        Self::with_data(key, spl_token::id(), data)
+    }
+
+    /// An initialized SPL token account holding `amount` of `mint` under `authority`.
+    pub fn token(key: Pubkey, mint: Pubkey, authority: Pubkey, amount: u64) -> Self {
+        let account = spl_token::state::Account {
+            mint,
+            owner: authority,
+            amount,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        };
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account::pack(account, &mut data).unwrap();
+        Self::with_data(key, spl_token::id(), data)
+    }
+
+    /// A leaked `AccountInfo` laid out like the runtime's input buffer: the
+    /// original data length sits 4 bytes before the key and there are 8 spare
+    /// bytes before the data, which `realloc` (used by `close`) reads and writes.
+    pub fn leak_info(&self) -> AccountInfo<'static> {
+        let mut key_buf = (self.data.len() as u32).to_le_bytes().to_vec();
+        key_buf.extend_from_slice(self.key.as_ref());
+        let key_buf: &'static [u8] = Box::leak(key_buf.into_boxed_slice());
+        let key = unsafe { &*(key_buf[4..].as_ptr() as *const Pubkey) };
+
+        let mut data_buf = vec![0u8; 8];
+        data_buf.extend_from_slice(&self.data);
+        let data_buf: &'static mut [u8] = Box::leak(data_buf.into_boxed_slice());
+
+        AccountInfo::new(
+            key,
+            self.is_signer,
+            self.is_writable,
+            Box::leak(Box::new(self.lamports)),
+            &mut data_buf[8..],
+            Box::leak(Box::new(self.owner)),
+            self.executable,
+            0,
+        )
+    }
+}
+
+pub const CAMPAIGN_ID: [u8; 32] = [1; 32];
+pub const NOW: i64 = 1_000;
+pub const LEAF_TAG: &[u8] = b"wdleaf1";
+
+/// A live campaign with a funded escrow and two leaves for one recipient.
+pub struct Fixture {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub campaign: Pubkey,
+    pub escrow: Pubkey,
+    pub recipient: Pubkey,
+    /// `(allocation, nonce16, proof)` for each leaf.
+    pub claims: Vec<(u64, [u8; 16], Vec<[u8; 32]>)>,
+    pub state: Campaign,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let (campaign, bump) = Pubkey::find_program_address(&[b"campaign", &CAMPAIGN_ID], &whisperdrop_escrow::ID);
+        let (escrow, escrow_bump) =
+            Pubkey::find_program_address(&[b"escrow", campaign.as_ref()], &whisperdrop_escrow::ID);
+
+        let leaves = [(100u64, [1u8; 16]), (250, [2; 16])];
+        let hashes: Vec<[u8; 32]> = leaves
+            .iter()
+            .map(|(a, n)| whisperdrop_merkle::leaf_hash(LEAF_TAG, &CAMPAIGN_ID, &recipient, *a, n))
+            .collect();
+        let merkle_root = whisperdrop_merkle::parent(&hashes[0], &hashes[1]);
+        let claims = vec![(leaves[0].0, leaves[0].1, vec![hashes[1]]), (leaves[1].0, leaves[1].1, vec![hashes[0]])];
+
+        let state = Campaign {
+            authority,
+            mint,
+            campaign_id: CAMPAIGN_ID,
+            manifest_hash: [0; 32],
+            merkle_root,
+            expiry_unix: NOW + 60,
+            bump,
+            escrow_bump,
+            claim_deadline_unix: NOW + 120,
+        };
+        Self { authority, mint, campaign, escrow, recipient, claims, state }
+    }
+
+    pub fn campaign_account(&self) -> TestAccount {
+        let mut data = Vec::new();
+        self.state.try_serialize(&mut data).unwrap();
+        TestAccount::with_data(self.campaign, whisperdrop_escrow::ID, data)
+    }
+
+    pub fn escrow_account(&self, amount: u64) -> TestAccount {
+        TestAccount::token(self.escrow, self.mint, self.campaign, amount)
+    }
+
+    /// Nullifier PDA of leaf `leaf` in `claims`.
+    pub fn nullifier(&self, leaf: usize) -> Pubkey {
+        Pubkey::find_program_address(
+            &[b"nullifier", self.campaign.as_ref(), self.recipient.as_ref(), &self.claims[leaf].1],
+            &whisperdrop_escrow::ID,
+        )
+        .0
+    }
+
+    /// The nullifier of `leaf` left behind by earlier claims totalling `claimed_so_far`, holding its rent.
+    pub fn nullifier_account(&self, leaf: usize, claimed_so_far: u64) -> TestAccount {
+        let mut data = Vec::new();
+        Nullifier { claimed_so_far }.try_serialize(&mut data).unwrap();
+        TestAccount {
+            lamports: Rent::default().minimum_balance(data.len()),
+            ..TestAccount::with_data(self.nullifier(leaf), whisperdrop_escrow::ID, data)
+        }
+    }
+
+    /// `InitCampaign` accounts for a campaign that does not exist yet:
+    /// `[authority, mint, campaign, escrow, system_program, token_program, rent]`.
+    pub fn init_accounts(&self) -> Vec<TestAccount> {
+        let rent = Rent::default();
+        let mut rent_data = rent.lamports_per_byte_year.to_le_bytes().to_vec();
+        rent_data.extend_from_slice(&rent.exemption_threshold.to_le_bytes());
+        rent_data.push(rent.burn_percent);
+        vec![
+            TestAccount::signer(self.authority),
+            TestAccount::mint(self.mint),
+            TestAccount::new(self.campaign),
+            TestAccount::new(self.escrow),
+            TestAccount::program(system_program::id()),
+            TestAccount::program(spl_token::id()),
+            TestAccount::with_data(anchor_lang::solana_program::sysvar::rent::id(), anchor_lang::solana_program::sysvar::id(), rent_data),
+        ]
+    }
+
+    /// `Deposit` accounts: `[authority, mint, campaign, escrow, from_ata, token_program]`.
+    pub fn deposit_accounts(&self) -> Vec<TestAccount> {
+        vec![
+            TestAccount::signer(self.authority),
+            TestAccount::mint(self.mint),
+            self.campaign_account(),
+            self.escrow_account(0),
+            TestAccount::token(Pubkey::new_unique(), self.mint, self.authority, 1_000),
+            TestAccount::program(spl_token::id()),
+        ]
+    }
+
+    /// `Claim` accounts for `leaf`: `[mint, campaign, recipient, escrow, nullifier, payer, recipient_ata, system_program, token_program]`.
+    pub fn claim_accounts(&self, leaf: usize) -> Vec<TestAccount> {
+        vec![
+            TestAccount::mint(self.mint),
+            self.campaign_account(),
+            TestAccount::new(self.recipient),
+            self.escrow_account(1_000),
+            TestAccount::new(self.nullifier(leaf)),
+            TestAccount::signer(Pubkey::new_unique()),
+            TestAccount::token(Pubkey::new_unique(), self.mint, self.recipient, 0),
+            TestAccount::program(system_program::id()),
+            TestAccount::program(spl_token::id()),
+        ]
+    }
+}
+
+/// Run `ix` through the program's `entry` at unix time `now`. On success the
+/// lamports, data and owners it wrote, including into accounts it creates, land
+/// back in `accounts`; a failed run leaves them untouched, as the runtime would.
+pub fn run_at(now: i64, accounts: &mut [TestAccount], ix: impl InstructionData) -> ProgramResult {
+    setup(now);
+    let infos: &'static [AccountInfo<'static>] = accounts.iter().map(TestAccount::leak_info).collect::<Vec<_>>().leak();
+    whisperdrop_escrow::entry(&whisperdrop_escrow::ID, infos, &ix.data())?;
+    for (account, info) in accounts.iter_mut().zip(infos) {
+        account.lamports = info.lamports();
+        account.data = info.data.borrow().to_vec();
+        account.owner = *info.owner;
+    }
+    Ok(())
+}
+
+pub fn run(accounts: &mut [TestAccount], ix: impl InstructionData) -> ProgramResult {
+    run_at(NOW, accounts, ix)
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use common::Fixture;
+use whisperdrop_escrow::{instruction, Claimed, Deposited};
+
+/// The single `emit!` frame of the last run, split into discriminator and payload.
+fn single_event() -> ([u8; 8], Vec<u8>) {
+    let data = common::take_data();
+    assert_eq!(data.len(), 1, "exactly one event frame");
+    assert_eq!(data[0].len(), 1, "emit! logs a single field");
+    let frame = &data[0][0];
+    (frame[..8].try_into().unwrap(), frame[8..].to_vec())
+}
+
+#[test]
+fn deposit_emits_deposited() {
+    let fx = Fixture::new();
+    let mut accounts = fx.deposit_accounts();
+    common::run(&mut accounts, instruction::Deposit { amount: 400 }).unwrap();
+
+    let (disc, payload) = single_event();
+    assert_eq!(disc, Deposited::DISCRIMINATOR);
+    let event = Deposited::try_from_slice(&payload).unwrap();
+    assert_eq!(event.campaign, fx.campaign);
+    assert_eq!(event.amount, 400);
+}
+
+#[test]
+fn claim_emits_claimed() {
+    let fx = Fixture::new();
+    let (allocation, nonce_hex_16, proof) = fx.claims[1].clone();
+    let mut accounts = fx.claim_accounts(1);
+    common::run(&mut accounts, instruction::Claim { allocation, nonce_hex_16, proof }).unwrap();
+
+    let (disc, payload) = single_event();
+    assert_eq!(disc, Claimed::DISCRIMINATOR);
+    let event = Claimed::try_from_slice(&payload).unwrap();
+    assert_eq!(event.campaign, fx.campaign);
+    assert_eq!(event.recipient, fx.recipient);
+    assert_eq!(event.allocation, 250);
+    assert_eq!(event.amount, 250);
+}
+
+#[test]
+fn failed_claim_emits_nothing() {
+    let fx = Fixture::new();
+    let (_, nonce_hex_16, proof) = fx.claims[1].clone();
+    let mut accounts = fx.claim_accounts(1);
+    assert!(common::run(&mut accounts, instruction::Claim { allocation: 251, nonce_hex_16, proof }).is_err());
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::AccountDeserialize;
+use anchor_spl::token::spl_token;
+use common::{Fixture, TestAccount};
+use whisperdrop_escrow::{instruction, Nullifier, WhisperErr};
+
+const NULLIFIER: usize = 4;
+const RECIPIENT_ATA: usize = 6;
+
+fn claimed_so_far(accounts: &[TestAccount]) -> u64 {
+    Nullifier::try_deserialize(&mut &accounts[NULLIFIER].data[..]).unwrap().claimed_so_far
+}
+
+fn balance(accounts: &[TestAccount]) -> u64 {
+    spl_token::state::Account::unpack(&accounts[RECIPIENT_ATA].data).unwrap().amount
+}
+
+fn claim_partial(fx: &Fixture, accounts: &mut [TestAccount], amount: u64) -> std::result::Result<(), ProgramError> {
+    let (allocation, nonce_hex_16, proof) = fx.claims[1].clone();
+    common::run(accounts, instruction::ClaimPartial { allocation, nonce_hex_16, amount, proof })
+}
+
+/// The token program is stubbed, so mirror each successful transfer into the recipient ATA.
+fn credit(accounts: &mut [TestAccount], amount: u64) {
+    let mut ata = spl_token::state::Account::unpack(&accounts[RECIPIENT_ATA].data).unwrap();
+    ata.amount += amount;
+    spl_token::state::Account::pack(ata, &mut accounts[RECIPIENT_ATA].data).unwrap();
+}
+
+#[test]
+fn two_step_partial_claim() {
+    let fx = Fixture::new();
+    let mut accounts = fx.claim_accounts(1);
+
+    claim_partial(&fx, &mut accounts, 100).unwrap();
+    credit(&mut accounts, 100);
+    assert_eq!(claimed_so_far(&accounts), 100);
+
+    // `claim` pays out whatever is left of the 250 allocation.
+    let (allocation, nonce_hex_16, proof) = fx.claims[1].clone();
+    common::run(&mut accounts, instruction::Claim { allocation, nonce_hex_16, proof }).unwrap();
+    credit(&mut accounts, 150);
+    assert_eq!(claimed_so_far(&accounts), 250);
+    assert_eq!(balance(&accounts), 250);
+
+    let err = claim_partial(&fx, &mut accounts, 1).unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::AlreadyClaimed).into());
+}
+
+#[test]
+fn over_claim_is_rejected() {
+    let fx = Fixture::new();
+    let mut accounts = fx.claim_accounts(1);
+
+    let err = claim_partial(&fx, &mut accounts, 251).unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::OverClaim).into());
+
+    claim_partial(&fx, &mut accounts, 200).unwrap();
+    let err = claim_partial(&fx, &mut accounts, 51).unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::OverClaim).into());
+    assert_eq!(claimed_so_far(&accounts), 200);
+
+    claim_partial(&fx, &mut accounts, 50).unwrap();
+    assert_eq!(claimed_so_far(&accounts), 250);
+}
+
+#[test]
+fn every_partial_claim_checks_the_proof() {
+    let fx = Fixture::new();
+    let mut accounts = fx.claim_accounts(1);
+    claim_partial(&fx, &mut accounts, 100).unwrap();
+
+    let (allocation, nonce_hex_16, _) = fx.claims[1].clone();
+    let proof = vec![[0u8; 32]];
+    let err = common::run(&mut accounts, instruction::ClaimPartial { allocation, nonce_hex_16, amount: 10, proof })
+        .unwrap_err();
+    assert_eq!(err, Error::from(WhisperErr::BadProof).into());
+    assert_eq!(claimed_so_far(&accounts), 100);
+}
+
+#[test]
+fn each_leaf_has_its_own_nullifier() {
+    let fx = Fixture::new();
+
+    // Use up the 100 leaf entirely.
+    let mut small = fx.claim_accounts(0);
+    let (allocation, nonce_hex_16, proof) = fx.claims[0].clone();
+    common::run(&mut small, instruction::Claim { allocation, nonce_hex_16, proof }).unwrap();
+    assert_eq!(claimed_so_far(&small), 100);
+
+    // The 250 leaf is untouched and can be claimed in full.
+    let mut large = fx.claim_accounts(1);
+    assert_ne!(large[NULLIFIER].key, small[NULLIFIER].key);
+    claim_partial(&fx, &mut large, 250).unwrap();
+    assert_eq!(claimed_so_far(&large), 250);
+}
+
+#[test]
+fn nullifier_of_another_leaf_is_rejected() {
+    let fx = Fixture::new();
+    let mut accounts = fx.claim_accounts(0);
+    let err = claim_partial(&fx, &mut accounts, 1).unwrap_err();
+    assert_eq!(err, Error::from(ErrorCode::ConstraintSeeds).into());
+}
@@ -0,0 +1,50 @@
+//! Encode/decode throughput. Run with `cargo bench --bench codec`.
+//!
+//! Sizes: `small` is a bare message, `medium` adds aad and a signature, and
+//! `max` carries a 65535-byte body, the largest payload the memo program's
+//! `u16` length field accepts.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use styx_envelope::{decode, decode_header, decode_view, encode, Env, EnvBuilder};
+
+fn samples() -> Vec<(&'static str, Env)> {
+    let message = || EnvBuilder::message([0x11; 32]).to_hash([0x22; 32]).nonce(vec![0x33; 24]);
+    vec![
+        ("small", message().body(vec![0xab; 64]).build().unwrap()),
+        (
+            "medium",
+            message()
+                .from([0x44; 32])
+                .body(vec![0xab; 512])
+                .aad(vec![0x55; 64])
+                .sig(vec![0x66; 64])
+                .build()
+                .unwrap(),
+        ),
+        ("max", message().body(vec![0xab; u16::MAX as usize]).build().unwrap()),
+    ]
+}
+
+fn codec(c: &mut Criterion) {
+    let mut group = c.benchmark_group("codec");
+    for (name, env) in samples() {
+        let buf = encode(&env).unwrap();
+        group.throughput(Throughput::Bytes(buf.len() as u64));
+        group.bench_with_input(BenchmarkId::new("encode", name), &env, |b, env| {
+            b.iter(|| encode(black_box(env)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode", name), &buf, |b, buf| {
+            b.iter(|| decode(black_box(buf)).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("decode_view", name), &buf, |b, buf| {
+            b.iter(|| decode_view(black_box(buf)).unwrap().body.len())
+        });
+        group.bench_with_input(BenchmarkId::new("decode_header", name), &buf, |b, buf| {
+            b.iter(|| decode_header(black_box(buf)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, codec);
+criterion_main!(benches);
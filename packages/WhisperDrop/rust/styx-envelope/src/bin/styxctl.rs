@@ -0,0 +1,100 @@
+//! Operator triage for styx memos.
+//!
+//! ```text
+//! styxctl decode   <styx1:...|b64url>   pretty-print the envelope
+//! styxctl validate <styx1:...|b64url>   decode, check field rules and canonical form
+//! styxctl encode   <file.json>          print the memo for an envelope in vector-schema JSON
+//! ```
+
+use std::process::ExitCode;
+use std::{env, fs};
+
+use styx_envelope::{b64url_decode, decode, roundtrip_check, to_memo, Env, MemoError, STYX_MEMO_PREFIX};
+
+const USAGE: &str = "usage: styxctl <decode|validate> <styx1:...|b64url>\n       styxctl encode <file.json>";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Accepts a `styx1:` memo or the bare base64url payload. Returns the raw
+/// bytes too, so `validate` can check they are canonical.
+fn parse_input(arg: &str) -> Result<(Env, Vec<u8>), String> {
+    let arg = arg.trim();
+    let payload = arg.strip_prefix(STYX_MEMO_PREFIX).unwrap_or(arg);
+    // base64url has no ':', so this is a memo with some other prefix.
+    if let Some((prefix, _)) = payload.split_once(':') {
+        return Err(MemoError::UnsupportedPrefix(prefix.into()).into());
+    }
+    let bytes = b64url_decode(payload)?;
+    let env = decode(&bytes)?;
+    Ok((env, bytes))
+}
+
+fn print_env(env: &Env, encoded_len: usize) {
+    let fixed = |v: Option<&[u8; 32]>| v.map_or_else(|| "-".to_string(), |b| hex(b));
+    let var = |v: Option<&Vec<u8>>| v.map_or_else(|| "-".to_string(), |b| format!("{} bytes", b.len()));
+
+    println!("v:       {}", env.v);
+    println!("kind:    {:?}", env.kind);
+    println!("algo:    {:?}", env.algo);
+    println!("id:      {}", hex(&env.id));
+    if let Some((index, total)) = env.chunk_info() {
+        println!("chunk:   {}/{}", index, total);
+    }
+    println!("to_hash: {}", fixed(env.to_hash.as_ref()));
+    println!("from:    {}", fixed(env.from.as_ref()));
+    println!("nonce:   {}", var(env.nonce.as_ref()));
+    println!("body:    {} bytes", env.body.len());
+    println!("aad:     {}", var(env.aad.as_ref()));
+    println!("sig:     {}", if env.sig.is_some() { var(env.sig.as_ref()) } else { "none".to_string() });
+    if !env.ext.is_empty() {
+        let entries: Vec<String> = env.ext.iter().map(|(ty, v)| format!("{} ({} bytes)", ty, v.len())).collect();
+        println!("ext:     {}", entries.join(", "));
+    }
+    println!("crc:     {}", if env.crc { "yes" } else { "no" });
+    println!("size:    {} bytes encoded", encoded_len);
+}
+
+/// A vector-file entry (`{"name", "env", ...}`) or a bare envelope object.
+fn read_env_json(path: &str) -> Result<Env, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut value: serde_json::Value = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path, e))?;
+    if let Some(env) = value.get_mut("env") {
+        value = env.take();
+    }
+    serde_json::from_value(value).map_err(|e| format!("{}: {}", path, e))
+}
+
+fn run(cmd: &str, arg: &str) -> Result<(), String> {
+    match cmd {
+        "decode" => {
+            let (env, bytes) = parse_input(arg)?;
+            print_env(&env, bytes.len());
+        }
+        "validate" => {
+            let (env, bytes) = parse_input(arg)?;
+            env.validate()?;
+            roundtrip_check(&bytes)?;
+            println!("ok");
+        }
+        "encode" => println!("{}", to_memo(&read_env_json(arg)?)?),
+        _ => return Err(USAGE.into()),
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [cmd, arg] = args.as_slice() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    match run(cmd, arg) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("styxctl: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,129 @@
+//! Chainable construction of [`Env`] values.
+
+use alloc::vec::Vec;
+
+use alloc::collections::BTreeMap;
+
+use crate::{recipient_hash, Algo, EncodeError, Env, Kind, STYX_V1, STYX_V2};
+
+/// Builds an [`Env`] with `v = 1` and `algo = Pmf1` unless overridden.
+///
+/// ```
+/// use styx_envelope::EnvBuilder;
+///
+/// let env = EnvBuilder::message([7u8; 32])
+///     .to_hash([1u8; 32])
+///     .body(b"ciphertext".to_vec())
+///     .build()
+///     .unwrap();
+/// assert_eq!(env.v, 1);
+/// ```
+#[derive(Debug, Clone)]
+pub struct EnvBuilder {
+    env: Env,
+}
+
+impl EnvBuilder {
+    pub fn new(kind: Kind, id: [u8; 32]) -> Self {
+        Self {
+            env: Env {
+                v: STYX_V1,
+                kind,
+                algo: Algo::default(),
+                id,
+                chunk: None,
+                to_hash: None,
+                from: None,
+                nonce: None,
+                body: Vec::new(),
+                aad: None,
+                sig: None,
+                ext: BTreeMap::new(),
+                crc: false,
+            },
+        }
+    }
+
+    pub fn message(id: [u8; 32]) -> Self {
+        Self::new(Kind::Message, id)
+    }
+
+    pub fn reveal(id: [u8; 32]) -> Self {
+        Self::new(Kind::Reveal, id)
+    }
+
+    pub fn keybundle(id: [u8; 32]) -> Self {
+        Self::new(Kind::Keybundle, id)
+    }
+
+    /// A delivery receipt for the message whose id is `acked_id`.
+    pub fn ack(acked_id: [u8; 32]) -> Self {
+        Self::new(Kind::Ack, acked_id)
+    }
+
+    pub fn algo(mut self, algo: Algo) -> Self {
+        self.env.algo = algo;
+        self
+    }
+
+    pub fn to_hash(mut self, to_hash: [u8; 32]) -> Self {
+        self.env.to_hash = Some(to_hash);
+        self
+    }
+
+    /// Address the envelope to `pubkey` by setting `to_hash` to its
+    /// [`recipient_hash`].
+    pub fn to_recipient(self, pubkey: &[u8; 32]) -> Self {
+        self.to_hash(recipient_hash(pubkey))
+    }
+
+    pub fn from(mut self, from: [u8; 32]) -> Self {
+        self.env.from = Some(from);
+        self
+    }
+
+    pub fn nonce(mut self, nonce: Vec<u8>) -> Self {
+        self.env.nonce = Some(nonce);
+        self
+    }
+
+    pub fn body(mut self, body: Vec<u8>) -> Self {
+        self.env.body = body;
+        self
+    }
+
+    pub fn aad(mut self, aad: Vec<u8>) -> Self {
+        self.env.aad = Some(aad);
+        self
+    }
+
+    pub fn sig(mut self, sig: Vec<u8>) -> Self {
+        self.env.sig = Some(sig);
+        self
+    }
+
+    /// Add a v2 extension field; switches the envelope to `v = 2`.
+    pub fn ext(mut self, ty: u8, value: Vec<u8>) -> Self {
+        self.env.v = STYX_V2;
+        self.env.ext.insert(ty, value);
+        self
+    }
+
+    /// Mark the body as chunk `index` of `total` sharing this envelope's id.
+    pub fn chunk(mut self, index: u16, total: u16) -> Self {
+        self.env.chunk = Some((index, total));
+        self
+    }
+
+    /// Append a CRC32 integrity trailer when encoded.
+    pub fn crc(mut self, crc: bool) -> Self {
+        self.env.crc = crc;
+        self
+    }
+
+    /// Returns the envelope if it passes [`Env::validate`].
+    pub fn build(self) -> Result<Env, EncodeError> {
+        self.env.validate()?;
+        Ok(self.env)
+    }
+}
@@ -0,0 +1,101 @@
+//! Sealing message bodies to a single X25519 recipient.
+//!
+//! A sealed `Message` carries `to_hash = recipient_hash(recipient_pub)`, a 12-byte
+//! `nonce`, and a body of `ephemeral_pub[32] || ciphertext`. The content key is
+//!
+//! `sha256("STYX_ENV_KEY_V1" || x25519(ephemeral, recipient) || ephemeral_pub || recipient_pub)`
+//!
+//! and the body is ChaCha20-Poly1305 under that key with [`header_aad`] as
+//! associated data, so the ciphertext is bound to the envelope header.
+
+use alloc::vec::Vec;
+
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::{header_aad, recipient_hash, Env, Kind, SealError};
+
+/// Domain tag for the content key derivation.
+pub const STYX_ENV_KEY_DOMAIN: &[u8] = b"STYX_ENV_KEY_V1";
+
+const NONCE_LEN: usize = 12;
+
+/// `None` when the shared point is all zeros (a low-order peer key).
+fn content_key(shared: MontgomeryPoint, ephemeral_pub: &[u8; 32], recipient_pub: &[u8; 32]) -> Option<[u8; 32]> {
+    if bool::from(shared.as_bytes().ct_eq(&[0u8; 32])) {
+        return None;
+    }
+    let mut h = Sha256::new();
+    h.update(STYX_ENV_KEY_DOMAIN);
+    h.update(shared.as_bytes());
+    h.update(ephemeral_pub);
+    h.update(recipient_pub);
+    Some(h.finalize().into())
+}
+
+/// Encrypt `plaintext` to `recipient_pub` and store it in `env`.
+///
+/// Sets `to_hash`, `nonce` and `body`; every other header field must already
+/// hold its final value, since it is authenticated. `ephemeral_secret` and
+/// `nonce` must be fresh random bytes for each call.
+pub fn seal_for_recipient(
+    env: &mut Env,
+    ephemeral_secret: &[u8; 32],
+    nonce: &[u8; NONCE_LEN],
+    recipient_pub: &[u8; 32],
+    plaintext: &[u8],
+) -> Result<(), SealError> {
+    if env.kind != Kind::Message {
+        return Err(SealError::NotAMessage);
+    }
+    let ephemeral_pub = MontgomeryPoint::mul_base_clamped(*ephemeral_secret).to_bytes();
+    let shared = MontgomeryPoint(*recipient_pub).mul_clamped(*ephemeral_secret);
+    let key = content_key(shared, &ephemeral_pub, recipient_pub).ok_or(SealError::InvalidRecipientKey)?;
+
+    env.to_hash = Some(recipient_hash(recipient_pub));
+    env.nonce = Some(nonce.to_vec());
+    let aad = header_aad(env);
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&key))
+        .encrypt(Nonce::from_slice(nonce), Payload { msg: plaintext, aad: &aad })
+        .map_err(|_| SealError::Encrypt)?;
+
+    let mut body = Vec::with_capacity(32 + ciphertext.len());
+    body.extend_from_slice(&ephemeral_pub);
+    body.extend_from_slice(&ciphertext);
+    env.body = body;
+    Ok(())
+}
+
+/// Scanner helper: the plaintext of `env` if it was sealed to `my_pub`.
+///
+/// Returns `None` rather than an error for anything that is not ours (a
+/// different or missing `to_hash`, another kind, a malformed body, or a
+/// failed AEAD open), so callers can run it over every envelope in a log.
+/// The `to_hash` comparison is constant-time; the early return on mismatch
+/// reveals only that the envelope is not addressed to `my_pub`, which
+/// `to_hash` already makes public.
+pub fn try_recipient_decrypt(env: &Env, my_secret: &[u8; 32], my_pub: &[u8; 32]) -> Option<Vec<u8>> {
+    if env.kind != Kind::Message {
+        return None;
+    }
+    let to_hash = env.to_hash.as_ref()?;
+    if !bool::from(to_hash.ct_eq(&recipient_hash(my_pub))) {
+        return None;
+    }
+    let nonce = env.nonce.as_deref().filter(|n| n.len() == NONCE_LEN)?;
+    if env.body.len() < 32 {
+        return None;
+    }
+    let (ephemeral_pub, ciphertext) = env.body.split_at(32);
+    let ephemeral_pub: [u8; 32] = ephemeral_pub.try_into().ok()?;
+
+    let shared = MontgomeryPoint(ephemeral_pub).mul_clamped(*my_secret);
+    let key = content_key(shared, &ephemeral_pub, my_pub)?;
+    let aad = header_aad(env);
+    ChaCha20Poly1305::new(Key::from_slice(&key))
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad })
+        .ok()
+}
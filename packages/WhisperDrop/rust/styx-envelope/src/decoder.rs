@@ -0,0 +1,193 @@
+//! Incremental decoding of envelopes arriving in arbitrary chunks.
+
+use alloc::vec::Vec;
+
+use crate::{
+    algo_from_code, decode_prefix, kind_from_code, read_u16le, DecodeError, Env, F_AAD, F_CHUNK, F_CRC, F_FROM, F_KNOWN,
+    F_NONCE, F_SIG, F_TOHASH, MAX_VARINT_SHIFT, STYX_MAGIC, STYX_V1, STYX_V2,
+};
+
+/// Buffers pushed bytes and yields complete envelopes as they become available.
+///
+/// The header's flags and length prefixes determine where each envelope ends,
+/// so one `push` may complete several envelopes and one envelope may span
+/// several pushes. `next` returns `None` while more bytes are needed and
+/// resumes yielding after the next `push` (the iterator is not fused).
+///
+/// A malformed envelope cannot be resynchronised, so on error the internal
+/// buffer is discarded and the error is yielded once.
+#[derive(Debug, Default)]
+pub struct Decoder {
+    buf: Vec<u8>,
+    max_len: Option<usize>,
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject any envelope whose encoded size exceeds `max_len` instead of
+    /// buffering until it completes.
+    pub fn with_limit(max_len: usize) -> Self {
+        Self {
+            buf: Vec::new(),
+            max_len: Some(max_len),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Number of buffered bytes not yet returned as an envelope.
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    fn fail(&mut self, err: DecodeError) -> Option<Result<Env, DecodeError>> {
+        self.buf.clear();
+        Some(Err(err))
+    }
+}
+
+impl Iterator for Decoder {
+    type Item = Result<Env, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (len, complete) = match frame_len(&self.buf) {
+            Ok(v) => v,
+            Err(e) => return self.fail(e),
+        };
+        if matches!(self.max_len, Some(max) if len > max) {
+            return self.fail(DecodeError::ExceedsLimit);
+        }
+        if !complete || len > self.buf.len() {
+            return None;
+        }
+        match decode_prefix(&self.buf[..len]) {
+            Ok((env, _)) => {
+                self.buf.drain(..len);
+                Some(Ok(env))
+            }
+            Err(e) => self.fail(e),
+        }
+    }
+}
+
+/// Read one envelope from `r`, consuming exactly its bytes.
+///
+/// The header's flags and length prefixes are read first, so only the bytes
+/// the envelope occupies are pulled from `r`; anything after it stays unread.
+/// The buffer grows as bytes arrive, so a huge declared length costs nothing
+/// until the source actually supplies it. A source that ends mid-envelope is
+/// `DecodeError::Truncated("envelope")`.
+#[cfg(feature = "std")]
+pub fn decode_from<R: std::io::Read>(r: &mut R) -> Result<Env, crate::ReadError> {
+    use std::io::Read;
+
+    let mut buf = Vec::new();
+    loop {
+        let (len, complete) = frame_len(&buf)?;
+        if complete && len == buf.len() {
+            return Ok(decode_prefix(&buf)?.0);
+        }
+        // A multi-byte varint can leave the lower bound at `buf.len()`.
+        let need = len.saturating_sub(buf.len()).max(1) as u64;
+        let read = r.by_ref().take(need).read_to_end(&mut buf).map_err(crate::ReadError::Io)?;
+        if (read as u64) < need {
+            return Err(DecodeError::Truncated("envelope").into());
+        }
+    }
+}
+
+/// Encoded length of the envelope at the front of `buf`.
+///
+/// Returns `(len, true)` once every length prefix has arrived; `len` may then
+/// exceed `buf.len()`. Otherwise returns `(lower_bound, false)`.
+fn frame_len(buf: &[u8]) -> Result<(usize, bool), DecodeError> {
+    let magic_len = buf.len().min(4);
+    if buf[..magic_len] != STYX_MAGIC[..magic_len] {
+        return Err(DecodeError::BadMagic);
+    }
+    if buf.len() < 9 {
+        return Ok((9 + 32, false));
+    }
+    if buf[4] != STYX_V1 && buf[4] != STYX_V2 {
+        return Err(DecodeError::UnsupportedVersion(buf[4]));
+    }
+    kind_from_code(buf[5]).ok_or(DecodeError::UnknownKind(buf[5]))?;
+    let flags = read_u16le(buf, 6);
+    if flags & !F_KNOWN != 0 {
+        return Err(DecodeError::ReservedFlags);
+    }
+    algo_from_code(buf[8]).ok_or(DecodeError::UnknownAlgo(buf[8]))?;
+
+    let mut o = 9 + 32;
+    if (flags & F_CHUNK) != 0 {
+        o += 4;
+    }
+    if (flags & F_TOHASH) != 0 {
+        o += 32;
+    }
+    if (flags & F_FROM) != 0 {
+        o += 32;
+    }
+
+    let var_fields = [flags & F_NONCE != 0, true, flags & F_AAD != 0, flags & F_SIG != 0];
+    for present in var_fields {
+        if !present {
+            continue;
+        }
+        match peek_uleb128(buf, o)? {
+            Some((len, read)) => o += read + len,
+            None => return Ok((o + 1, false)),
+        }
+    }
+    if buf[4] == STYX_V2 {
+        let count = match peek_uleb128(buf, o)? {
+            Some((count, read)) => {
+                o += read;
+                count
+            }
+            None => return Ok((o + 1, false)),
+        };
+        for _ in 0..count {
+            // Type byte, then the value's length prefix.
+            match peek_uleb128(buf, o + 1)? {
+                Some((len, read)) => o += 1 + read + len,
+                None => return Ok((o + 2, false)),
+            }
+        }
+    }
+    if (flags & F_CRC) != 0 {
+        o += 4;
+    }
+    Ok((o, true))
+}
+
+/// Like `uleb128_decode`, but distinguishes "ran out of bytes" (`None`) from
+/// a malformed varint.
+fn peek_uleb128(buf: &[u8], o: usize) -> Result<Option<(usize, usize)>, DecodeError> {
+    let mut result: usize = 0;
+    let mut shift: usize = 0;
+    let mut i = o;
+    loop {
+        if i >= buf.len() {
+            return Ok(None);
+        }
+        let b = buf[i];
+        i += 1;
+        result |= ((b & 0x7f) as usize) << shift;
+        if (b & 0x80) == 0 {
+            if b == 0 && i - o > 1 {
+                return Err(DecodeError::VarintNonCanonical);
+            }
+            return Ok(Some((result, i - o)));
+        }
+        shift += 7;
+        if shift > MAX_VARINT_SHIFT {
+            return Err(DecodeError::VarintTooLarge);
+        }
+    }
+}
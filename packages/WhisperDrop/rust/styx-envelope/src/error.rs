@@ -0,0 +1,289 @@
+//! Errors returned while encoding and decoding envelopes.
+
+use core::fmt;
+
+use alloc::string::{String, ToString};
+
+/// Why a buffer is not a valid v1 envelope (or bundle of envelopes), or why
+/// a set of chunk envelopes cannot be reassembled.
+///
+/// `Display` renders the same `decode: ...` messages the crate has always
+/// produced, so logs keep reading the same.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// Shorter than the fixed header.
+    TooShort,
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownKind(u8),
+    UnknownAlgo(u8),
+    /// The named field runs past the end of the buffer.
+    Truncated(&'static str),
+    /// A length prefix is longer than five bytes (see `MAX_VARINT_SHIFT`), or
+    /// overflows `u64` in `uleb128_decode_u64`.
+    VarintTooLarge,
+    /// A length prefix with a redundant trailing zero group (e.g. `0x80 0x00`
+    /// for 0); accepting it would give one envelope two encodings.
+    VarintNonCanonical,
+    /// A length prefix points past the end of the buffer.
+    OutOfRange,
+    /// Variable-length fields exceed the caller's limit.
+    ExceedsLimit,
+    CrcMismatch,
+    TrailingBytes,
+    /// v2 extension types are not strictly ascending (out of order or repeated).
+    ExtOrder,
+    /// A flag bit this version does not define is set.
+    ReservedFlags,
+    /// A bundle declares more than `max` ([`MAX_BUNDLE_ENVELOPES`](crate::MAX_BUNDLE_ENVELOPES)) envelopes.
+    TooManyEnvelopes { count: usize, max: usize },
+    /// A bundle ends after `found` of its `declared` envelopes.
+    BundleCountMismatch { declared: usize, found: usize },
+    /// `reassemble` was given no envelopes.
+    NoChunks,
+    /// `reassemble` was given an envelope without chunk info.
+    NotAChunk,
+    /// `expected` (the chunk total) envelopes were needed, `got` were given.
+    ChunkCount { expected: u16, got: usize },
+    /// Chunks from different messages (their ids differ).
+    ChunkIdMismatch,
+    /// A chunk declares total `got` where the first declared `expected`.
+    ChunkTotalMismatch { expected: u16, got: u16 },
+    /// No chunk has this index (another index is duplicated).
+    MissingChunk(u16),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort => f.write_str("decode: too short"),
+            Self::BadMagic => f.write_str("decode: bad magic"),
+            Self::UnsupportedVersion(v) => write!(f, "decode: unsupported version {}", v),
+            Self::UnknownKind(k) => write!(f, "decode: unknown kind {}", k),
+            Self::UnknownAlgo(a) => write!(f, "decode: unknown algo {}", a),
+            Self::Truncated(field) => write!(f, "decode: truncated {}", field),
+            Self::VarintTooLarge => f.write_str("varint too large"),
+            Self::VarintNonCanonical => f.write_str("varint not canonical"),
+            Self::OutOfRange => f.write_str("varBytes out of range"),
+            Self::ExceedsLimit => f.write_str("decode: exceeds size limit"),
+            Self::CrcMismatch => f.write_str("decode: crc mismatch"),
+            Self::TrailingBytes => f.write_str("decode: trailing bytes"),
+            Self::ExtOrder => f.write_str("decode: ext types not ascending"),
+            Self::ReservedFlags => f.write_str("decode: reserved flags set"),
+            Self::TooManyEnvelopes { count, max } => write!(f, "bundle: {} envelopes exceeds {}", count, max),
+            Self::BundleCountMismatch { declared, found } => {
+                write!(f, "bundle: count mismatch (declared {}, found {})", declared, found)
+            }
+            Self::NoChunks => f.write_str("reassemble: no envelopes"),
+            Self::NotAChunk => f.write_str("reassemble: envelope is not a chunk"),
+            Self::ChunkCount { expected, got } => write!(f, "reassemble: expected {} chunks, got {}", expected, got),
+            Self::ChunkIdMismatch => f.write_str("reassemble: chunks do not share an id"),
+            Self::ChunkTotalMismatch { expected, got } => {
+                write!(f, "reassemble: chunk_total mismatch ({} vs {})", got, expected)
+            }
+            Self::MissingChunk(index) => write!(f, "reassemble: missing chunk {}", index),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+/// Why an [`Env`](crate::Env) cannot be encoded. The field-rule variants are
+/// the ones [`Env::validate`](crate::Env::validate) reports.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncodeError {
+    UnsupportedVersion(u8),
+    /// A `Message` with an empty body.
+    EmptyMessageBody,
+    /// A `Reveal` without `from`.
+    RevealWithoutFrom,
+    /// A `Keybundle` carrying a nonce.
+    KeybundleWithNonce,
+    /// An `Ack` with a non-empty body.
+    AckWithBody,
+    /// A `chunk` whose index is not below its total.
+    InvalidChunk { index: u16, total: u16 },
+    /// Extension fields on a v1 envelope.
+    ExtRequiresV2,
+    /// The encoding is `len` bytes, over the relay's `max`
+    /// ([`MAX_ENVELOPE_BYTES`](crate::MAX_ENVELOPE_BYTES)).
+    TooLarge { len: usize, max: usize },
+    /// More than `max` ([`MAX_BUNDLE_ENVELOPES`](crate::MAX_BUNDLE_ENVELOPES)) envelopes passed to `encode_bundle`.
+    TooManyEnvelopes { count: usize, max: usize },
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(_) => f.write_str("encode: only v=1 and v=2 supported"),
+            Self::EmptyMessageBody => f.write_str("validate: message body must not be empty"),
+            Self::RevealWithoutFrom => f.write_str("validate: reveal requires from"),
+            Self::KeybundleWithNonce => f.write_str("validate: keybundle must not carry a nonce"),
+            Self::AckWithBody => f.write_str("validate: ack body must be empty"),
+            Self::InvalidChunk { index, total } => {
+                write!(f, "validate: chunk index {} out of range for total {}", index, total)
+            }
+            Self::ExtRequiresV2 => f.write_str("validate: ext requires v=2"),
+            Self::TooLarge { len, max } => write!(f, "encode: envelope exceeds relay max ({} > {})", len, max),
+            Self::TooManyEnvelopes { count, max } => write!(f, "bundle: {} envelopes exceeds {}", count, max),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {}
+
+/// Why [`roundtrip_check`](crate::roundtrip_check) rejected a buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RoundtripError {
+    Decode(DecodeError),
+    /// It decodes, but `encode` refuses the result.
+    Encode(EncodeError),
+    /// It decodes, but re-encodes to `canonical_len` bytes that first differ
+    /// from the `len` input bytes at byte `at`.
+    NotCanonical { at: usize, len: usize, canonical_len: usize },
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Decode(e) => write!(f, "roundtrip: {}", e),
+            Self::Encode(e) => write!(f, "roundtrip: {}", e),
+            Self::NotCanonical { at, len, canonical_len } => write!(
+                f,
+                "roundtrip: not canonical (first difference at byte {}, {} vs {} bytes)",
+                at, len, canonical_len
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RoundtripError {}
+
+/// Why [`from_memo`](crate::from_memo) could not parse a memo string.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoError {
+    /// No `styx<N>:` prefix at all.
+    MissingPrefix,
+    /// A `styx` prefix other than `styx1` (the text before the colon).
+    UnsupportedPrefix(String),
+    /// The payload is not base64url.
+    Base64(base64::DecodeError),
+    Decode(DecodeError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for MemoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingPrefix => f.write_str("memo: missing styx1: prefix"),
+            Self::UnsupportedPrefix(prefix) => write!(f, "memo: unsupported prefix {}:", prefix),
+            Self::Base64(e) => write!(f, "b64url decode: {}", e),
+            Self::Decode(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MemoError {}
+
+#[cfg(feature = "std")]
+impl From<DecodeError> for MemoError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+/// Why [`decode_from`](crate::decode_from) could not read an envelope.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReadError {
+    /// The reader failed.
+    Io(std::io::Error),
+    /// The bytes read are not an envelope, or the reader ended mid-envelope
+    /// (`Truncated("envelope")`).
+    Decode(DecodeError),
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io: {}", e),
+            Self::Decode(e) => e.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode(e) => Some(e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<DecodeError> for ReadError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+/// Why [`seal_for_recipient`](crate::seal_for_recipient) could not seal a body.
+#[cfg(feature = "crypto")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SealError {
+    /// Only `Message` envelopes carry ciphertext.
+    NotAMessage,
+    /// A low-order recipient key (the shared secret would be all zeros).
+    InvalidRecipientKey,
+    /// ChaCha20-Poly1305 refused the plaintext.
+    Encrypt,
+}
+
+#[cfg(feature = "crypto")]
+impl fmt::Display for SealError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotAMessage => f.write_str("seal: only message envelopes carry ciphertext"),
+            Self::InvalidRecipientKey => f.write_str("seal: invalid recipient key"),
+            Self::Encrypt => f.write_str("seal: encryption failed"),
+        }
+    }
+}
+
+#[cfg(all(feature = "crypto", feature = "std"))]
+impl std::error::Error for SealError {}
+
+/// Lets `?` carry a failure out of the helpers that still report errors as
+/// `String` (base64url fields, signing, `styxctl`).
+impl From<DecodeError> for String {
+    fn from(e: DecodeError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<EncodeError> for String {
+    fn from(e: EncodeError) -> Self {
+        e.to_string()
+    }
+}
+
+impl From<RoundtripError> for String {
+    fn from(e: RoundtripError) -> Self {
+        e.to_string()
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<MemoError> for String {
+    fn from(e: MemoError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,106 @@
+//! Canonical body layout for `Kind::Keybundle` envelopes.
+
+use alloc::vec::Vec;
+
+use crate::{algo_code, algo_from_code, uleb128_decode, write_uleb128, Algo, DecodeError, Env, Kind};
+
+/// X3DH-style prekey bundle published in a keybundle envelope's body:
+///
+/// `identity_key[32] || signed_prekey[32] || prekey_sig[64] || uleb128(n) || one_time_prekeys[n][32]`
+/// `|| [uleb128(m) || supported_algos[m]]`
+///
+/// The algo list is omitted when empty, so bundles written before it existed
+/// parse unchanged (with no advertised algos).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBundle {
+    pub identity_key: [u8; 32],
+    pub signed_prekey: [u8; 32],
+    /// Signature by `identity_key` over `signed_prekey`.
+    pub prekey_sig: [u8; 64],
+    pub one_time_prekeys: Vec<[u8; 32]>,
+    /// Algo codes the owner can decrypt. Codes this crate does not know are
+    /// kept, and skipped by [`best_algo`](Self::best_algo).
+    pub supported_algos: Vec<u8>,
+}
+
+const FIXED_LEN: usize = 32 + 32 + 64;
+
+impl KeyBundle {
+    pub fn to_body(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(FIXED_LEN + 5 + 32 * self.one_time_prekeys.len());
+        out.extend_from_slice(&self.identity_key);
+        out.extend_from_slice(&self.signed_prekey);
+        out.extend_from_slice(&self.prekey_sig);
+        write_uleb128(&mut out, self.one_time_prekeys.len());
+        for key in &self.one_time_prekeys {
+            out.extend_from_slice(key);
+        }
+        if !self.supported_algos.is_empty() {
+            write_uleb128(&mut out, self.supported_algos.len());
+            out.extend_from_slice(&self.supported_algos);
+        }
+        out
+    }
+
+    /// Parse a keybundle body. Each count is checked against the remaining
+    /// bytes before anything is allocated, and nothing may follow the algo
+    /// list. An explicit empty algo list is rejected as trailing bytes,
+    /// since `to_body` omits it.
+    pub fn from_body(body: &[u8]) -> Result<Self, DecodeError> {
+        if body.len() < FIXED_LEN {
+            return Err(DecodeError::Truncated("keybundle"));
+        }
+        let (count, read) = uleb128_decode(body, FIXED_LEN)?;
+        let keys_start = FIXED_LEN + read;
+        let keys_len = match count.checked_mul(32) {
+            Some(len) if len <= body.len() - keys_start => len,
+            _ => return Err(DecodeError::OutOfRange),
+        };
+        let keys = &body[keys_start..keys_start + keys_len];
+
+        let mut supported_algos = Vec::new();
+        let mut o = keys_start + keys_len;
+        if o < body.len() {
+            let (count, read) = uleb128_decode(body, o)?;
+            o += read;
+            if count == 0 {
+                return Err(DecodeError::TrailingBytes);
+            }
+            if count > body.len() - o {
+                return Err(DecodeError::OutOfRange);
+            }
+            supported_algos.extend_from_slice(&body[o..o + count]);
+            o += count;
+            if o < body.len() {
+                return Err(DecodeError::TrailingBytes);
+            }
+        }
+
+        Ok(Self {
+            identity_key: body[..32].try_into().unwrap(),
+            signed_prekey: body[32..64].try_into().unwrap(),
+            prekey_sig: body[64..FIXED_LEN].try_into().unwrap(),
+            one_time_prekeys: keys.chunks_exact(32).map(|k| k.try_into().unwrap()).collect(),
+            supported_algos,
+        })
+    }
+
+    /// The highest-numbered algo both this bundle and the sender support.
+    ///
+    /// Codes either side lists but this crate does not know are ignored, so
+    /// a bundle advertising a future algo still negotiates a known one.
+    pub fn best_algo(&self, sender_supported: &[u8]) -> Option<Algo> {
+        self.supported_algos
+            .iter()
+            .filter(|code| sender_supported.contains(code))
+            .filter_map(|&code| algo_from_code(code))
+            .max_by_key(algo_code)
+    }
+}
+
+impl Env {
+    /// The typed body of a `Keybundle` envelope; `None` for other kinds.
+    pub fn key_bundle(&self) -> Option<Result<KeyBundle, DecodeError>> {
+        (self.kind == Kind::Keybundle).then(|| KeyBundle::from_body(&self.body))
+    }
+}
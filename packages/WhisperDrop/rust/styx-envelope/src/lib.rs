@@ -1,37 +1,356 @@
 //! Styx Envelope v1 (Rust)
 //!
 //! Canonical binary format matches @styx/memo encodeStyxEnvelope.
+//!
+//! Without the default `std` feature the crate is `no_std` + `alloc`: encoding
+//! and decoding work as usual, while the base64url and `styx1:` memo helpers
+//! (and `serde`, which needs them) are unavailable.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
 
+#[cfg(feature = "std")]
+use alloc::{format, string::String};
+#[cfg(feature = "std")]
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+#[cfg(feature = "std")]
 use base64::Engine;
 
+mod builder;
+#[cfg(feature = "crypto")]
+mod crypto;
+mod decoder;
+mod error;
+mod keybundle;
+#[cfg(feature = "serde")]
+mod serde_b64;
+#[cfg(feature = "sign")]
+mod sign;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use builder::EnvBuilder;
+#[cfg(feature = "crypto")]
+pub use crypto::{seal_for_recipient, try_recipient_decrypt, STYX_ENV_KEY_DOMAIN};
+pub use decoder::Decoder;
+#[cfg(feature = "std")]
+pub use decoder::decode_from;
+#[cfg(feature = "std")]
+pub use error::{MemoError, ReadError};
+#[cfg(feature = "crypto")]
+pub use error::SealError;
+pub use error::{DecodeError, EncodeError, RoundtripError};
+pub use keybundle::KeyBundle;
+#[cfg(feature = "sign")]
+pub use sign::{sign, verify};
+
 pub const STYX_MAGIC: [u8; 4] = [0x53, 0x54, 0x59, 0x58]; // "STYX"
 pub const STYX_V1: u8 = 1;
+/// v1 plus a trailing TLV extension section (see [`Env::ext`]).
+pub const STYX_V2: u8 = 2;
+pub const STYX_MEMO_PREFIX: &str = "styx1:";
+/// Fixed prefix every v1 envelope starts with: magic, version, kind, flags, algo, id.
+pub const STYX_HEADER_LEN: usize = 4 + 1 + 1 + 2 + 1 + 32;
+/// Largest encoded envelope the Styx relay program accepts; see [`encode_for_relay`].
+pub const MAX_ENVELOPE_BYTES: usize = 1024;
+/// Most envelopes [`encode_bundle`] writes or [`decode_bundle`] accepts.
+pub const MAX_BUNDLE_ENVELOPES: usize = 64;
+/// Shift of the last 7-bit group a v1 length prefix may carry, so prefixes
+/// are at most five bytes. Every v1 length has to fit in a transaction (or at
+/// least in memory on a 32-bit target), so anything longer is malformed and
+/// is rejected with [`DecodeError::VarintTooLarge`] before it can drive an
+/// allocation.
+pub const MAX_VARINT_SHIFT: usize = 28;
+/// Shift of the last group [`uleb128_decode_u64`] accepts: ten bytes, the
+/// full `u64` range.
+pub const MAX_VARINT_SHIFT_U64: usize = 63;
+/// Domain tag for [`derive_id`].
+pub const STYX_ENV_ID_DOMAIN: &[u8] = b"STYX_ENV_ID_V1";
+/// Domain tag for [`recipient_hash`].
+pub const STYX_TO_HASH_DOMAIN: &[u8] = b"STYX_TO_HASH_V1";
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Kind {
     Message,
     Reveal,
     Keybundle,
+    /// Delivery receipt (code 4): `id` is the acknowledged message's id and
+    /// `body` is empty.
+    Ack,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Algo {
+    /// PMF1 (code 1, the default).
+    #[default]
     Pmf1,
+    /// PMF2 (code 2): AES-256-GCM content encryption.
+    Pmf2,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+/// With the `serde` feature, byte fields serialize as base64url strings using
+/// the same field names as the shared test vectors (`toHash`, `from`, ...).
+///
+/// `Eq` and `Hash` compare every field. The encoding is canonical, so two
+/// envelopes are equal exactly when [`encode`] gives them identical bytes,
+/// which makes a `HashSet<Env>` a content-dedup set.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
 pub struct Env {
     pub v: u8,
     pub kind: Kind,
     pub algo: Algo,
+    #[cfg_attr(feature = "serde", serde(with = "serde_b64::fixed32"))]
     pub id: [u8; 32],
+    /// `(chunk_index, chunk_total)` when `body` is one piece of a message
+    /// split across envelopes sharing `id` (`F_CHUNK`). See [`reassemble`].
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub chunk: Option<(u16, u16)>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "serde_b64::opt_fixed32")
+    )]
     pub to_hash: Option<[u8; 32]>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "serde_b64::opt_fixed32")
+    )]
     pub from: Option<[u8; 32]>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "serde_b64::opt_bytes")
+    )]
     pub nonce: Option<Vec<u8>>,
+    #[cfg_attr(feature = "serde", serde(with = "serde_b64::bytes"))]
     pub body: Vec<u8>,
+    /// Application-defined associated data carried alongside the body. The
+    /// protocol's own header binding is [`header_aad`], not this field.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "serde_b64::opt_bytes")
+    )]
     pub aad: Option<Vec<u8>>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none", with = "serde_b64::opt_bytes")
+    )]
     pub sig: Option<Vec<u8>>,
+    /// v2 extension fields by type, encoded after `sig` as
+    /// `uleb128(n) || (type u8 || uleb128(len) || value)*n` in ascending type
+    /// order. Unknown types are carried verbatim. Always empty for v1.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty", with = "serde_b64::byte_map")
+    )]
+    pub ext: BTreeMap<u8, Vec<u8>>,
+    /// Append a CRC32 of the preceding bytes (`F_CRC`). Catches truncation
+    /// and corruption; it is not an authenticity check.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "std::ops::Not::not"))]
+    pub crc: bool,
+}
+
+impl Env {
+    /// A v1 envelope whose `id` is [`derive_id`] of its contents, so re-sending
+    /// the same message yields the same id. Optional fields other than `from`
+    /// start empty.
+    pub fn with_derived_id(kind: Kind, algo: Algo, body: Vec<u8>, from: Option<[u8; 32]>) -> Self {
+        let id = derive_id(&kind, &algo, &body, from.as_ref());
+        Self {
+            v: STYX_V1,
+            kind,
+            algo,
+            id,
+            chunk: None,
+            to_hash: None,
+            from,
+            nonce: None,
+            body,
+            aad: None,
+            sig: None,
+            ext: BTreeMap::new(),
+            crc: false,
+        }
+    }
+
+    /// Check per-kind field rules. Called by [`encode`].
+    ///
+    /// - `Message`: `body` must be non-empty (an empty ciphertext is never
+    ///   valid). `to_hash` is recommended for routed messages but not required,
+    ///   since the v1 spec allows unaddressed messages (`v1_message_minimal`).
+    /// - `Reveal`: `from` is required; a reveal must name its discloser.
+    /// - `Keybundle`: `nonce` is forbidden; keybundles are published in clear.
+    /// - `Ack`: `body` must be empty; the receipt is the `id` alone.
+    ///
+    /// For any kind, `chunk` must satisfy `chunk_index < chunk_total`, and
+    /// `ext` must be empty unless `v` is 2.
+    pub fn validate(&self) -> Result<(), EncodeError> {
+        if self.v == STYX_V1 && !self.ext.is_empty() {
+            return Err(EncodeError::ExtRequiresV2);
+        }
+        if let Some((index, total)) = self.chunk {
+            if index >= total {
+                return Err(EncodeError::InvalidChunk { index, total });
+            }
+        }
+        match self.kind {
+            Kind::Message => {
+                if self.body.is_empty() {
+                    return Err(EncodeError::EmptyMessageBody);
+                }
+            }
+            Kind::Reveal => {
+                if self.from.is_none() {
+                    return Err(EncodeError::RevealWithoutFrom);
+                }
+            }
+            Kind::Keybundle => {
+                if self.nonce.is_some() {
+                    return Err(EncodeError::KeybundleWithNonce);
+                }
+            }
+            Kind::Ack => {
+                if !self.body.is_empty() {
+                    return Err(EncodeError::AckWithBody);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Replace `Some(empty)` in `nonce`, `aad` and `sig` with `None`.
+    ///
+    /// An empty `Some` still sets its flag and writes a zero length, so it
+    /// encodes differently from `None` although it carries nothing. Other
+    /// implementations (the JS vector harness among them) treat empty as
+    /// absent, so normalize before signing or hashing an envelope that may
+    /// have been built that way.
+    pub fn normalize(&mut self) {
+        for field in [&mut self.nonce, &mut self.aad, &mut self.sig] {
+            if field.as_ref().is_some_and(Vec::is_empty) {
+                *field = None;
+            }
+        }
+    }
+
+    /// `(chunk_index, chunk_total)` if this envelope carries one chunk of a
+    /// larger body.
+    pub fn chunk_info(&self) -> Option<(u16, u16)> {
+        self.chunk
+    }
+
+    /// Whether `body` should be treated as ciphertext, so a UI knows to ask
+    /// for a key before rendering it.
+    ///
+    /// This is a heuristic over public fields: true for a `Message` whose
+    /// `algo` is an encrypting suite (`Pmf1`, `Pmf2`) and that carries a
+    /// `nonce`. Keybundles are published in clear and reveals disclose keys,
+    /// so both are false. Nothing here inspects the body, so a sender can
+    /// still mislabel plaintext as ciphertext or vice versa.
+    pub fn is_encrypted(&self) -> bool {
+        self.kind == Kind::Message && matches!(self.algo, Algo::Pmf1 | Algo::Pmf2) && self.nonce.is_some()
+    }
+}
+
+/// Constant-time comparisons for fields used in authentication decisions.
+///
+/// `sig` and `id` should be compared with these when the outcome gates
+/// access. Public structure (`v`, `kind`, `algo`, flags, field presence and
+/// lengths) is safe to compare with `==`.
+#[cfg(feature = "subtle")]
+impl Env {
+    /// Compare `sig` against `expected` without short-circuiting on the first
+    /// differing byte. Returns false if `sig` is absent or lengths differ.
+    pub fn ct_eq_sig(&self, expected: &[u8]) -> bool {
+        use subtle::ConstantTimeEq;
+        match &self.sig {
+            Some(sig) => sig.as_slice().ct_eq(expected).into(),
+            None => false,
+        }
+    }
+
+    /// Compare `id` against `expected` in constant time.
+    pub fn ct_eq_id(&self, expected: &[u8; 32]) -> bool {
+        use subtle::ConstantTimeEq;
+        self.id.ct_eq(expected).into()
+    }
+}
+
+/// With the `zeroize` feature, `zeroize()` scrubs `id`, `to_hash`, `from`,
+/// `nonce`, `body`, `aad` and `ext` values. `sig` is public data and left as
+/// is. To scrub on drop, hold the envelope in a `zeroize::Zeroizing<Env>`;
+/// `Env` itself has no `Drop` impl, so the feature never stops callers from
+/// moving fields out.
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for Env {
+    fn zeroize(&mut self) {
+        self.id.zeroize();
+        self.to_hash.zeroize();
+        self.from.zeroize();
+        self.nonce.zeroize();
+        self.body.zeroize();
+        self.aad.zeroize();
+        self.ext.values_mut().for_each(|v| v.zeroize());
+    }
+}
+
+/// Borrowed view of an encoded envelope; variable-length fields point into
+/// the source buffer. See [`decode_view`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvView<'a> {
+    pub v: u8,
+    pub kind: Kind,
+    pub algo: Algo,
+    pub id: [u8; 32],
+    pub chunk: Option<(u16, u16)>,
+    pub to_hash: Option<[u8; 32]>,
+    pub from: Option<[u8; 32]>,
+    pub nonce: Option<&'a [u8]>,
+    pub body: &'a [u8],
+    pub aad: Option<&'a [u8]>,
+    pub sig: Option<&'a [u8]>,
+    pub ext: BTreeMap<u8, &'a [u8]>,
+    pub crc: bool,
+}
+
+impl EnvView<'_> {
+    /// Copy the borrowed fields into an owned [`Env`].
+    pub fn to_owned(&self) -> Env {
+        Env {
+            v: self.v,
+            kind: self.kind.clone(),
+            algo: self.algo.clone(),
+            id: self.id,
+            chunk: self.chunk,
+            to_hash: self.to_hash,
+            from: self.from,
+            nonce: self.nonce.map(<[u8]>::to_vec),
+            body: self.body.to_vec(),
+            aad: self.aad.map(<[u8]>::to_vec),
+            sig: self.sig.map(<[u8]>::to_vec),
+            ext: self.ext.iter().map(|(t, v)| (*t, v.to_vec())).collect(),
+            crc: self.crc,
+        }
+    }
+}
+
+/// The fixed prefix of an envelope, as read by [`decode_header`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header {
+    pub v: u8,
+    pub kind: Kind,
+    pub algo: Algo,
+    pub id: [u8; 32],
+    /// The raw flags word; which optional fields follow the header.
+    pub flags: u16,
 }
 
 const F_TOHASH: u16 = 1 << 0;
@@ -39,12 +358,17 @@ const F_FROM: u16 = 1 << 1;
 const F_NONCE: u16 = 1 << 2;
 const F_AAD: u16 = 1 << 3;
 const F_SIG: u16 = 1 << 4;
+const F_CRC: u16 = 1 << 5;
+const F_CHUNK: u16 = 1 << 6;
+/// Every flag bit this version understands; the rest are reserved and must be zero.
+const F_KNOWN: u16 = F_TOHASH | F_FROM | F_NONCE | F_AAD | F_SIG | F_CRC | F_CHUNK;
 
 fn kind_code(k: &Kind) -> u8 {
     match k {
         Kind::Message => 1,
         Kind::Reveal => 2,
         Kind::Keybundle => 3,
+        Kind::Ack => 4,
     }
 }
 
@@ -53,6 +377,7 @@ fn kind_from_code(c: u8) -> Option<Kind> {
         1 => Some(Kind::Message),
         2 => Some(Kind::Reveal),
         3 => Some(Kind::Keybundle),
+        4 => Some(Kind::Ack),
         _ => None,
     }
 }
@@ -60,12 +385,14 @@ fn kind_from_code(c: u8) -> Option<Kind> {
 fn algo_code(a: &Algo) -> u8 {
     match a {
         Algo::Pmf1 => 1,
+        Algo::Pmf2 => 2,
     }
 }
 
 fn algo_from_code(c: u8) -> Option<Algo> {
     match c {
         1 => Some(Algo::Pmf1),
+        2 => Some(Algo::Pmf2),
         _ => None,
     }
 }
@@ -78,64 +405,288 @@ fn read_u16le(buf: &[u8], o: usize) -> u16 {
     (buf[o] as u16) | ((buf[o + 1] as u16) << 8)
 }
 
-fn uleb128_encode(mut n: usize) -> Vec<u8> {
+/// CRC-32/ISO-HDLC (the zlib/PNG polynomial), bitwise to avoid a table.
+fn crc32(data: &[u8]) -> u32 {
+    !crc32_update(0xffff_ffff, data)
+}
+
+/// Feed `data` into a running (pre-inversion) CRC-32 state.
+fn crc32_update(mut crc: u32, data: &[u8]) -> u32 {
+    for &b in data {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn read_fixed32(buf: &[u8], o: usize, field: &'static str) -> Result<[u8; 32], DecodeError> {
+    let end = o + 32;
+    if end > buf.len() {
+        return Err(DecodeError::Truncated(field));
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&buf[o..end]);
+    Ok(out)
+}
+
+fn uleb128_encode(n: usize) -> Vec<u8> {
     let mut out = Vec::new();
+    write_uleb128(&mut out, n);
+    out
+}
+
+fn uleb128_len(n: usize) -> usize {
+    let bits = usize::BITS - n.leading_zeros();
+    (bits as usize).div_ceil(7).max(1)
+}
+
+/// Write `n` into the front of `out`, returning the byte count. `out` must
+/// hold `uleb128_len(n)` bytes.
+fn uleb128_to_slice(out: &mut [u8], mut n: usize) -> usize {
+    let mut i = 0;
     loop {
         let b = (n & 0x7f) as u8;
         n >>= 7;
         if n != 0 {
-            out.push(b | 0x80);
+            out[i] = b | 0x80;
+            i += 1;
         } else {
-            out.push(b);
-            break;
+            out[i] = b;
+            return i + 1;
         }
     }
-    out
 }
 
-fn uleb128_decode(buf: &[u8], mut o: usize) -> Result<(usize, usize), String> {
+fn write_uleb128(out: &mut Vec<u8>, n: usize) {
+    let mut buf = [0u8; 10];
+    let len = uleb128_to_slice(&mut buf, n);
+    out.extend_from_slice(&buf[..len]);
+}
+
+fn uleb128_decode(buf: &[u8], mut o: usize) -> Result<(usize, usize), DecodeError> {
     let mut result: usize = 0;
     let mut shift: usize = 0;
     let start = o;
     loop {
         if o >= buf.len() {
-            return Err("varint overflow".into());
+            return Err(DecodeError::Truncated("varint"));
         }
         let b = buf[o];
         o += 1;
         result |= ((b & 0x7f) as usize) << shift;
         if (b & 0x80) == 0 {
+            // A final 0x00 after a continuation byte adds only zero bits.
+            if b == 0 && o - start > 1 {
+                return Err(DecodeError::VarintNonCanonical);
+            }
             break;
         }
         shift += 7;
-        if shift > 28 {
-            return Err("varint too large".into());
+        if shift > MAX_VARINT_SHIFT {
+            return Err(DecodeError::VarintTooLarge);
         }
     }
     Ok((result, o - start))
 }
 
-fn var_bytes_encode(v: &[u8]) -> Vec<u8> {
-    let mut out = uleb128_encode(v.len());
-    out.extend_from_slice(v);
-    out
+/// Decode a uleb128 at `buf[o..]` into a full `u64`, for formats whose
+/// lengths or counters are not bound by a transaction. Returns the value and
+/// the bytes read.
+///
+/// The same canonical-form rule as v1 applies. Anything past ten bytes, or a
+/// tenth byte with more than the top bit of the `u64`, is
+/// [`DecodeError::VarintTooLarge`].
+pub fn uleb128_decode_u64(buf: &[u8], o: usize) -> Result<(u64, usize), DecodeError> {
+    let mut result: u64 = 0;
+    let mut shift: usize = 0;
+    let mut i = o;
+    loop {
+        let b = *buf.get(i).ok_or(DecodeError::Truncated("varint"))?;
+        i += 1;
+        let group = (b & 0x7f) as u64;
+        if shift == MAX_VARINT_SHIFT_U64 && group > 1 {
+            return Err(DecodeError::VarintTooLarge);
+        }
+        result |= group << shift;
+        if (b & 0x80) == 0 {
+            if b == 0 && i - o > 1 {
+                return Err(DecodeError::VarintNonCanonical);
+            }
+            return Ok((result, i - o));
+        }
+        shift += 7;
+        if shift > MAX_VARINT_SHIFT_U64 {
+            return Err(DecodeError::VarintTooLarge);
+        }
+    }
 }
 
-fn var_bytes_decode(buf: &[u8], o: usize) -> Result<(Vec<u8>, usize), String> {
+/// Reads a length-prefixed field, charging its length against `budget`.
+/// Both the budget and the remaining buffer are checked before slicing.
+fn var_bytes_decode<'a>(buf: &'a [u8], o: usize, budget: &mut usize) -> Result<(&'a [u8], usize), DecodeError> {
     let (len, read) = uleb128_decode(buf, o)?;
+    if len > *budget {
+        return Err(DecodeError::ExceedsLimit);
+    }
     let start = o + read;
-    let end = start + len;
-    if end > buf.len() {
-        return Err("varBytes out of range".into());
+    if len > buf.len() - start {
+        return Err(DecodeError::OutOfRange);
     }
-    Ok((buf[start..end].to_vec(), read + len))
+    *budget -= len;
+    Ok((&buf[start..start + len], read + len))
 }
 
-pub fn encode(env: &Env) -> Result<Vec<u8>, String> {
-    if env.v != 1 {
-        return Err("encode: only v=1 supported".into());
+/// Associated data binding the envelope header into the body's AEAD.
+///
+/// Pass this as the AAD when encrypting or decrypting `body` (e.g. with
+/// ChaCha20-Poly1305) so a ciphertext cannot be spliced under a different
+/// header. The bytes are the envelope's own encoding of the header fields
+/// `v`, `kind`, `algo`, `id`, `chunk`, `to_hash` and `from`:
+///
+/// `magic || v || kind || flags_le16 || algo || id || chunk? || to_hash? || from?`
+///
+/// where `flags` keeps only the `chunk`/`to_hash`/`from` presence bits. `nonce`,
+/// `body`, `aad`, `sig` and `crc` are left out: they are either AEAD inputs
+/// in their own right or are attached after encryption.
+pub fn header_aad(env: &Env) -> Vec<u8> {
+    let mut flags: u16 = 0;
+    if env.to_hash.is_some() {
+        flags |= F_TOHASH;
+    }
+    if env.from.is_some() {
+        flags |= F_FROM;
+    }
+    if env.chunk.is_some() {
+        flags |= F_CHUNK;
     }
 
+    let mut out = Vec::with_capacity(STYX_HEADER_LEN + 4 + 64);
+    out.extend_from_slice(&STYX_MAGIC);
+    out.push(env.v);
+    out.push(kind_code(&env.kind));
+    out.extend_from_slice(&u16le(flags));
+    out.push(algo_code(&env.algo));
+    out.extend_from_slice(&env.id);
+    if let Some((index, total)) = env.chunk {
+        out.extend_from_slice(&u16le(index));
+        out.extend_from_slice(&u16le(total));
+    }
+    if let Some(th) = &env.to_hash {
+        out.extend_from_slice(th);
+    }
+    if let Some(fr) = &env.from {
+        out.extend_from_slice(fr);
+    }
+    out
+}
+
+/// Content-addressed envelope id:
+///
+/// `sha256("STYX_ENV_ID_V1" || kind || algo || has_from || from? || uleb128(body.len()) || body)`
+///
+/// `kind` and `algo` are their one-byte wire codes and `has_from` is 0 or 1.
+/// Length-prefixing the body keeps the preimage unambiguous.
+pub fn derive_id(kind: &Kind, algo: &Algo, body: &[u8], from: Option<&[u8; 32]>) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut h = Sha256::new();
+    h.update(STYX_ENV_ID_DOMAIN);
+    h.update([kind_code(kind), algo_code(algo)]);
+    match from {
+        Some(from) => {
+            h.update([1]);
+            h.update(from);
+        }
+        None => h.update([0]),
+    }
+    h.update(uleb128_encode(body.len()));
+    h.update(body);
+    h.finalize().into()
+}
+
+/// The `to_hash` routing hint for a recipient:
+///
+/// `sha256("STYX_TO_HASH_V1" || recipient_pubkey)`
+///
+/// Senders set `to_hash` to this and scanners compare against it, so every
+/// implementation must compute it the same way.
+pub fn recipient_hash(pubkey: &[u8; 32]) -> [u8; 32] {
+    use sha2::{Digest, Sha256};
+
+    let mut h = Sha256::new();
+    h.update(STYX_TO_HASH_DOMAIN);
+    h.update(pubkey);
+    h.finalize().into()
+}
+
+pub fn encode(env: &Env) -> Result<Vec<u8>, EncodeError> {
+    let mut out = Vec::new();
+    encode_into(env, &mut out)?;
+    Ok(out)
+}
+
+/// [`encode`], failing instead if the result would exceed
+/// [`MAX_ENVELOPE_BYTES`], so callers find out before paying for a relay
+/// transaction that would be rejected.
+pub fn encode_for_relay(env: &Env) -> Result<Vec<u8>, EncodeError> {
+    let len = encoded_len(env);
+    if len > MAX_ENVELOPE_BYTES {
+        return Err(EncodeError::TooLarge { len, max: MAX_ENVELOPE_BYTES });
+    }
+    encode(env)
+}
+
+/// Exact size of `encode(env)` without encoding, for pre-allocation and
+/// checking against relay limits. Does not validate `env`.
+pub fn encoded_len(env: &Env) -> usize {
+    let var = |v: &[u8]| uleb128_len(v.len()) + v.len();
+    STYX_HEADER_LEN
+        + env.chunk.map_or(0, |_| 4)
+        + env.to_hash.map_or(0, |_| 32)
+        + env.from.map_or(0, |_| 32)
+        + env.nonce.as_deref().map_or(0, var)
+        + var(&env.body)
+        + env.aad.as_deref().map_or(0, var)
+        + env.sig.as_deref().map_or(0, var)
+        + if env.v == STYX_V2 {
+            uleb128_len(env.ext.len()) + env.ext.values().map(|v| 1 + var(v)).sum::<usize>()
+        } else {
+            0
+        }
+        + if env.crc { 4 } else { 0 }
+}
+
+/// Like [`encode`], but clears `out` and writes into it, so a scratch buffer
+/// can be reused across calls. On error `out` is left empty.
+pub fn encode_into(env: &Env, out: &mut Vec<u8>) -> Result<(), EncodeError> {
+    out.clear();
+    check_encodable(env)?;
+    out.reserve(encoded_len(env));
+    write_canonical(env, |bytes| out.extend_from_slice(bytes));
+    Ok(())
+}
+
+/// Feed `encode(env)` into `hasher` piecewise, without building the encoding
+/// in memory. On error nothing has been fed.
+#[cfg(feature = "digest")]
+pub fn encode_to_hasher(env: &Env, hasher: &mut impl sha2::Digest) -> Result<(), EncodeError> {
+    check_encodable(env)?;
+    write_canonical(env, |bytes| hasher.update(bytes));
+    Ok(())
+}
+
+fn check_encodable(env: &Env) -> Result<(), EncodeError> {
+    if env.v != STYX_V1 && env.v != STYX_V2 {
+        return Err(EncodeError::UnsupportedVersion(env.v));
+    }
+    env.validate()
+}
+
+/// Hand the canonical encoding of an already-validated `env` to `put`, field
+/// by field. The single source of field order for every encoder.
+fn write_canonical(env: &Env, put: impl FnMut(&[u8])) {
     let mut flags: u16 = 0;
     if env.to_hash.is_some() {
         flags |= F_TOHASH;
@@ -152,116 +703,394 @@ pub fn encode(env: &Env) -> Result<Vec<u8>, String> {
     if env.sig.is_some() {
         flags |= F_SIG;
     }
+    if env.crc {
+        flags |= F_CRC;
+    }
+    if env.chunk.is_some() {
+        flags |= F_CHUNK;
+    }
 
-    let mut out = Vec::new();
-    out.extend_from_slice(&STYX_MAGIC);
-    out.push(STYX_V1);
-    out.push(kind_code(&env.kind));
-    out.extend_from_slice(&u16le(flags));
-    out.push(algo_code(&env.algo));
-    out.extend_from_slice(&env.id);
+    let mut w = CanonicalWriter {
+        put,
+        crc: env.crc.then_some(0xffff_ffff),
+    };
+    w.bytes(&STYX_MAGIC);
+    w.bytes(&[env.v, kind_code(&env.kind)]);
+    w.bytes(&u16le(flags));
+    w.bytes(&[algo_code(&env.algo)]);
+    w.bytes(&env.id);
+    if let Some((index, total)) = env.chunk {
+        w.bytes(&u16le(index));
+        w.bytes(&u16le(total));
+    }
 
     if let Some(th) = &env.to_hash {
-        out.extend_from_slice(th);
+        w.bytes(th);
     }
     if let Some(fr) = &env.from {
-        out.extend_from_slice(fr);
+        w.bytes(fr);
     }
     if let Some(nonce) = &env.nonce {
-        out.extend_from_slice(&var_bytes_encode(nonce));
+        w.var_bytes(nonce);
     }
-    out.extend_from_slice(&var_bytes_encode(&env.body));
+    w.var_bytes(&env.body);
     if let Some(aad) = &env.aad {
-        out.extend_from_slice(&var_bytes_encode(aad));
+        w.var_bytes(aad);
     }
     if let Some(sig) = &env.sig {
-        out.extend_from_slice(&var_bytes_encode(sig));
+        w.var_bytes(sig);
+    }
+    if env.v == STYX_V2 {
+        w.uleb128(env.ext.len());
+        for (ty, value) in &env.ext {
+            w.bytes(&[*ty]);
+            w.var_bytes(value);
+        }
+    }
+    if let Some(crc) = w.crc {
+        (w.put)(&(!crc).to_le_bytes());
     }
+}
 
-    Ok(out)
+/// Forwards encoded pieces to `put`, keeping the running CRC when the
+/// envelope carries one.
+struct CanonicalWriter<F> {
+    put: F,
+    crc: Option<u32>,
 }
 
-pub fn decode(buf: &[u8]) -> Result<Env, String> {
-    let min_len = 4 + 1 + 1 + 2 + 1 + 32;
-    if buf.len() < min_len {
-        return Err("decode: too short".into());
+impl<F: FnMut(&[u8])> CanonicalWriter<F> {
+    fn bytes(&mut self, bytes: &[u8]) {
+        if let Some(crc) = &mut self.crc {
+            *crc = crc32_update(*crc, bytes);
+        }
+        (self.put)(bytes);
+    }
+
+    fn uleb128(&mut self, n: usize) {
+        let mut buf = [0u8; 10];
+        let len = uleb128_to_slice(&mut buf, n);
+        self.bytes(&buf[..len]);
+    }
+
+    fn var_bytes(&mut self, v: &[u8]) {
+        self.uleb128(v.len());
+        self.bytes(v);
+    }
+}
+
+/// Decode exactly one envelope from the front of `buf`.
+///
+/// Returns the envelope and the number of bytes consumed, so callers can walk
+/// a buffer of back-to-back envelopes by slicing `&buf[consumed..]`.
+pub fn decode_prefix(buf: &[u8]) -> Result<(Env, usize), DecodeError> {
+    let (view, consumed) = view_prefix(buf, usize::MAX)?;
+    Ok((view.to_owned(), consumed))
+}
+
+/// Parse an envelope without copying its variable-length fields.
+///
+/// Applies the same checks as [`decode`], including the trailing-bytes check.
+pub fn decode_view(buf: &[u8]) -> Result<EnvView<'_>, DecodeError> {
+    let (view, consumed) = view_prefix(buf, usize::MAX)?;
+    if consumed != buf.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(view)
+}
+
+/// Cheap sanity check that `buf` starts like an envelope: at least
+/// [`STYX_HEADER_LEN`] bytes, [`STYX_MAGIC`] and version 1 or 2.
+///
+/// Nothing past the version byte is parsed, so a buffer that passes may still
+/// fail [`decode`]. Meant for relays and filters that must not pay for a full decode.
+pub fn check_header(buf: &[u8]) -> Result<(), DecodeError> {
+    if buf.len() < STYX_HEADER_LEN {
+        return Err(DecodeError::TooShort);
     }
     if buf[0..4] != STYX_MAGIC {
-        return Err("decode: bad magic".into());
+        return Err(DecodeError::BadMagic);
     }
-    let v = buf[4];
-    if v != 1 {
-        return Err(format!("decode: unsupported version {}", v));
+    if buf[4] != STYX_V1 && buf[4] != STYX_V2 {
+        return Err(DecodeError::UnsupportedVersion(buf[4]));
     }
-    let kind = kind_from_code(buf[5]).ok_or("decode: unknown kind")?;
-    let flags = read_u16le(buf, 6);
-    let algo = algo_from_code(buf[8]).ok_or("decode: unknown algo")?;
-    let mut o = 9;
+    Ok(())
+}
 
-    let mut id = [0u8; 32];
-    id.copy_from_slice(&buf[o..o + 32]);
-    o += 32;
+/// Read just the fixed [`STYX_HEADER_LEN`]-byte prefix: version, kind, algo,
+/// id and flags.
+///
+/// Unlike [`decode_view`], nothing after the id is located, so filters can
+/// drop envelopes by kind or id without walking the variable-length fields.
+/// A buffer that passes may still fail [`decode`].
+pub fn decode_header(buf: &[u8]) -> Result<Header, DecodeError> {
+    check_header(buf)?;
+    // Flags decide which fields are read, so an unknown bit could shift every
+    // later offset; refuse it rather than guess.
+    if read_u16le(buf, 6) & !F_KNOWN != 0 {
+        return Err(DecodeError::ReservedFlags);
+    }
+    Ok(Header {
+        v: buf[4],
+        kind: kind_from_code(buf[5]).ok_or(DecodeError::UnknownKind(buf[5]))?,
+        algo: algo_from_code(buf[8]).ok_or(DecodeError::UnknownAlgo(buf[8]))?,
+        id: read_fixed32(buf, 9, "id")?,
+        flags: read_u16le(buf, 6),
+    })
+}
+
+fn view_prefix(buf: &[u8], max_len: usize) -> Result<(EnvView<'_>, usize), DecodeError> {
+    let Header { v, kind, algo, id, flags } = decode_header(buf)?;
+    let mut o = STYX_HEADER_LEN;
+
+    let mut chunk: Option<(u16, u16)> = None;
+    if (flags & F_CHUNK) != 0 {
+        if buf.len() - o < 4 {
+            return Err(DecodeError::Truncated("chunk"));
+        }
+        chunk = Some((read_u16le(buf, o), read_u16le(buf, o + 2)));
+        o += 4;
+    }
 
     let mut to_hash: Option<[u8; 32]> = None;
     let mut from: Option<[u8; 32]> = None;
-    let mut nonce: Option<Vec<u8>> = None;
-    let mut aad: Option<Vec<u8>> = None;
-    let mut sig: Option<Vec<u8>> = None;
+    let mut nonce: Option<&[u8]> = None;
+    let mut aad: Option<&[u8]> = None;
+    let mut sig: Option<&[u8]> = None;
+    let mut budget = max_len;
 
     if (flags & F_TOHASH) != 0 {
-        let mut th = [0u8; 32];
-        th.copy_from_slice(&buf[o..o + 32]);
+        to_hash = Some(read_fixed32(buf, o, "to_hash")?);
         o += 32;
-        to_hash = Some(th);
     }
     if (flags & F_FROM) != 0 {
-        let mut fr = [0u8; 32];
-        fr.copy_from_slice(&buf[o..o + 32]);
+        from = Some(read_fixed32(buf, o, "from")?);
         o += 32;
-        from = Some(fr);
     }
     if (flags & F_NONCE) != 0 {
-        let (v, read) = var_bytes_decode(buf, o)?;
+        let (v, read) = var_bytes_decode(buf, o, &mut budget)?;
         nonce = Some(v);
         o += read;
     }
 
-    let (body, read_body) = var_bytes_decode(buf, o)?;
+    let (body, read_body) = var_bytes_decode(buf, o, &mut budget)?;
     o += read_body;
 
     if (flags & F_AAD) != 0 {
-        let (v, read) = var_bytes_decode(buf, o)?;
+        let (v, read) = var_bytes_decode(buf, o, &mut budget)?;
         aad = Some(v);
         o += read;
     }
     if (flags & F_SIG) != 0 {
-        let (v, read) = var_bytes_decode(buf, o)?;
+        let (v, read) = var_bytes_decode(buf, o, &mut budget)?;
         sig = Some(v);
         o += read;
     }
-
-    if o != buf.len() {
-        return Err("decode: trailing bytes".into());
+    let mut ext = BTreeMap::new();
+    if v == STYX_V2 {
+        let (count, read) = uleb128_decode(buf, o)?;
+        o += read;
+        // Each entry takes at least two bytes; reject impossible counts up front.
+        if count > (buf.len() - o) / 2 {
+            return Err(DecodeError::OutOfRange);
+        }
+        for _ in 0..count {
+            if o == buf.len() {
+                return Err(DecodeError::Truncated("ext"));
+            }
+            let ty = buf[o];
+            if matches!(ext.last_key_value(), Some((&last, _)) if ty <= last) {
+                return Err(DecodeError::ExtOrder);
+            }
+            let (value, read) = var_bytes_decode(buf, o + 1, &mut budget)?;
+            ext.insert(ty, value);
+            o += 1 + read;
+        }
+    }
+    let crc = (flags & F_CRC) != 0;
+    if crc {
+        if buf.len() - o < 4 {
+            return Err(DecodeError::Truncated("crc"));
+        }
+        let stored = u32::from_le_bytes([buf[o], buf[o + 1], buf[o + 2], buf[o + 3]]);
+        if stored != crc32(&buf[..o]) {
+            return Err(DecodeError::CrcMismatch);
+        }
+        o += 4;
     }
 
-    Ok(Env {
+    let view = EnvView {
         v,
         kind,
         algo,
         id,
+        chunk,
         to_hash,
         from,
         nonce,
         body,
         aad,
         sig,
-    })
+        ext,
+        crc,
+    };
+    Ok((view, o))
+}
+
+pub fn decode(buf: &[u8]) -> Result<Env, DecodeError> {
+    decode_with_limit(buf, usize::MAX)
+}
+
+/// Like [`decode`], but rejects envelopes whose nonce, body, aad, sig and ext
+/// values together exceed `max_len` bytes. Each length prefix is checked against the
+/// remaining allowance before anything is allocated.
+pub fn decode_with_limit(buf: &[u8], max_len: usize) -> Result<Env, DecodeError> {
+    let (view, consumed) = view_prefix(buf, max_len)?;
+    if consumed != buf.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(view.to_owned())
+}
+
+/// Concatenate envelopes as `uleb128(count) || env_0 || env_1 || ...`.
+///
+/// Envelopes are self-delimiting, so no per-item length is needed. At most
+/// [`MAX_BUNDLE_ENVELOPES`] fit in one bundle.
+pub fn encode_bundle(envs: &[Env]) -> Result<Vec<u8>, EncodeError> {
+    if envs.len() > MAX_BUNDLE_ENVELOPES {
+        return Err(EncodeError::TooManyEnvelopes { count: envs.len(), max: MAX_BUNDLE_ENVELOPES });
+    }
+    let mut out = uleb128_encode(envs.len());
+    for env in envs {
+        out.extend_from_slice(&encode(env)?);
+    }
+    Ok(out)
+}
+
+/// Read back an [`encode_bundle`] buffer. The count is checked against
+/// [`MAX_BUNDLE_ENVELOPES`] before anything is allocated, and the buffer must
+/// hold exactly that many envelopes.
+pub fn decode_bundle(buf: &[u8]) -> Result<Vec<Env>, DecodeError> {
+    let (count, mut o) = uleb128_decode(buf, 0)?;
+    if count > MAX_BUNDLE_ENVELOPES {
+        return Err(DecodeError::TooManyEnvelopes { count, max: MAX_BUNDLE_ENVELOPES });
+    }
+    let mut envs = Vec::with_capacity(count);
+    for i in 0..count {
+        if o == buf.len() {
+            return Err(DecodeError::BundleCountMismatch { declared: count, found: i });
+        }
+        let (env, used) = decode_prefix(&buf[o..])?;
+        envs.push(env);
+        o += used;
+    }
+    if o != buf.len() {
+        return Err(DecodeError::TrailingBytes);
+    }
+    Ok(envs)
 }
 
+/// Rebuild a chunked body from its envelopes, given in any order.
+///
+/// Every envelope must carry [`Env::chunk_info`], share one `id` and one
+/// `chunk_total`, and together they must cover indices `0..chunk_total`
+/// exactly once. Bodies are concatenated in index order.
+pub fn reassemble(envs: &[Env]) -> Result<Vec<u8>, DecodeError> {
+    let first = envs.first().ok_or(DecodeError::NoChunks)?;
+    let (_, total) = first.chunk_info().ok_or(DecodeError::NotAChunk)?;
+    if envs.len() != total as usize {
+        return Err(DecodeError::ChunkCount { expected: total, got: envs.len() });
+    }
+
+    let mut ordered: Vec<(u16, &Env)> = Vec::with_capacity(envs.len());
+    for env in envs {
+        let (index, t) = env.chunk_info().ok_or(DecodeError::NotAChunk)?;
+        if env.id != first.id {
+            return Err(DecodeError::ChunkIdMismatch);
+        }
+        if t != total {
+            return Err(DecodeError::ChunkTotalMismatch { expected: total, got: t });
+        }
+        ordered.push((index, env));
+    }
+    ordered.sort_unstable_by_key(|(index, _)| *index);
+
+    let mut out = Vec::with_capacity(envs.iter().map(|e| e.body.len()).sum());
+    for (expected, (index, env)) in ordered.into_iter().enumerate() {
+        if index as usize != expected {
+            return Err(DecodeError::MissingChunk(expected as u16));
+        }
+        out.extend_from_slice(&env.body);
+    }
+    Ok(out)
+}
+
+/// Canonicalization oracle for fuzzers and property tests.
+///
+/// Succeeds when `buf` decodes and `encode` of the result reproduces `buf`
+/// byte for byte. That is the contract: each envelope has exactly one
+/// encoding, so any input that decodes but re-encodes differently (an
+/// overlong length prefix, for instance) is non-canonical and reported here.
+/// Inputs that fail to decode, or decode to an envelope `encode` refuses
+/// (see [`Env::validate`]), are errors too.
+pub fn roundtrip_check(buf: &[u8]) -> Result<(), RoundtripError> {
+    let env = decode(buf).map_err(RoundtripError::Decode)?;
+    let canonical = encode(&env).map_err(RoundtripError::Encode)?;
+    if canonical != buf {
+        let at = canonical
+            .iter()
+            .zip(buf)
+            .position(|(a, b)| a != b)
+            .unwrap_or(canonical.len().min(buf.len()));
+        return Err(RoundtripError::NotCanonical { at, len: buf.len(), canonical_len: canonical.len() });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "std")]
 pub fn b64url_encode(bytes: &[u8]) -> String {
     URL_SAFE_NO_PAD.encode(bytes)
 }
 
+#[cfg(feature = "std")]
 pub fn b64url_decode(s: &str) -> Result<Vec<u8>, String> {
     URL_SAFE_NO_PAD.decode(s.as_bytes()).map_err(|e| format!("b64url decode: {}", e))
 }
+
+/// Decode a base64url string that must contain exactly `N` bytes.
+#[cfg(feature = "std")]
+pub fn b64url_decode_n<const N: usize>(s: &str) -> Result<[u8; N], String> {
+    let v = b64url_decode(s)?;
+    v.as_slice()
+        .try_into()
+        .map_err(|_| format!("b64url decode: expected {} bytes, got {}", N, v.len()))
+}
+
+/// Decode a base64url `id`/`to_hash`/`from` field.
+#[cfg(feature = "std")]
+pub fn b64url_decode_32(s: &str) -> Result<[u8; 32], String> {
+    b64url_decode_n::<32>(s)
+}
+
+/// Render an envelope as a memo string: `styx1:<b64url(encode(env))>`.
+#[cfg(feature = "std")]
+pub fn to_memo(env: &Env) -> Result<String, EncodeError> {
+    Ok(format!("{}{}", STYX_MEMO_PREFIX, b64url_encode(&encode(env)?)))
+}
+
+/// Parse a `styx1:` memo string. Surrounding whitespace is ignored.
+#[cfg(feature = "std")]
+pub fn from_memo(s: &str) -> Result<Env, MemoError> {
+    let s = s.trim();
+    let payload = match s.strip_prefix(STYX_MEMO_PREFIX) {
+        Some(p) => p,
+        None => {
+            return match s.split_once(':') {
+                Some((prefix, _)) if prefix.starts_with("styx") => Err(MemoError::UnsupportedPrefix(prefix.into())),
+                _ => Err(MemoError::MissingPrefix),
+            };
+        }
+    };
+    let bytes = URL_SAFE_NO_PAD.decode(payload.as_bytes()).map_err(MemoError::Base64)?;
+    Ok(decode(&bytes)?)
+}
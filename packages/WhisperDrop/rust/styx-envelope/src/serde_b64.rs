@@ -0,0 +1,86 @@
+//! serde adapters encoding envelope byte fields as base64url (no padding),
+//! matching the layout of `styx-envelope-v1.json`.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::{b64url_decode, b64url_decode_32, b64url_encode};
+
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&b64url_encode(v))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(d)?;
+        b64url_decode(&s).map_err(D::Error::custom)
+    }
+}
+
+pub mod opt_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(v) => s.serialize_some(&b64url_encode(v)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Vec<u8>>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(s) => b64url_decode(&s).map(Some).map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `ext`: a map from type to base64url value (JSON keys are the decimal type).
+pub mod byte_map {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    pub fn serialize<S: Serializer>(v: &BTreeMap<u8, Vec<u8>>, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_map(v.iter().map(|(ty, value)| (ty, b64url_encode(value))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<BTreeMap<u8, Vec<u8>>, D::Error> {
+        BTreeMap::<u8, String>::deserialize(d)?
+            .into_iter()
+            .map(|(ty, s)| b64url_decode(&s).map(|value| (ty, value)).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+pub mod fixed32 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &[u8; 32], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&b64url_encode(v))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<[u8; 32], D::Error> {
+        let s = String::deserialize(d)?;
+        b64url_decode_32(&s).map_err(D::Error::custom)
+    }
+}
+
+pub mod opt_fixed32 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Option<[u8; 32]>, s: S) -> Result<S::Ok, S::Error> {
+        match v {
+            Some(v) => s.serialize_some(&b64url_encode(v)),
+            None => s.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<[u8; 32]>, D::Error> {
+        match Option::<String>::deserialize(d)? {
+            Some(s) => b64url_decode_32(&s).map(Some).map_err(D::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
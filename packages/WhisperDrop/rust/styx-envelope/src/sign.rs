@@ -0,0 +1,60 @@
+//! Ed25519 signatures over the canonical envelope encoding.
+//!
+//! The signed message is `encode(env)` with `sig` cleared, so every field
+//! (including `from`) is covered. `from` must hold the signer's Ed25519
+//! public key; an envelope without `from` can be neither signed nor verified.
+//!
+//! Signer and verifier must agree on those bytes, so call
+//! [`Env::normalize`](crate::Env::normalize) before signing: an empty
+//! `Some(nonce)` or `Some(aad)` encodes differently from `None`.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+
+use crate::{encode, Env};
+
+fn signing_bytes(env: &Env) -> Result<Vec<u8>, String> {
+    let mut unsigned = env.clone();
+    unsigned.sig = None;
+    Ok(encode(&unsigned)?)
+}
+
+/// Sign `env` with an Ed25519 secret key and attach the 64-byte signature.
+///
+/// Errors if `from` is absent or does not match the public key of `secret`.
+pub fn sign(env: &mut Env, secret: &[u8; 32]) -> Result<(), String> {
+    let key = SigningKey::from_bytes(secret);
+    match &env.from {
+        None => return Err("sign: from is required".into()),
+        Some(from) if *from != key.verifying_key().to_bytes() => {
+            return Err("sign: from does not match signing key".into());
+        }
+        Some(_) => {}
+    }
+    let msg = signing_bytes(env)?;
+    env.sig = Some(key.sign(&msg).to_bytes().to_vec());
+    Ok(())
+}
+
+/// Verify the envelope signature against `pubkey`.
+///
+/// Returns `Ok(false)` if `sig` is missing or malformed, if `from` differs
+/// from `pubkey`, or if the signature does not check out.
+pub fn verify(env: &Env, pubkey: &[u8; 32]) -> Result<bool, String> {
+    let from = env.from.as_ref().ok_or("verify: from is required")?;
+    if from != pubkey {
+        return Ok(false);
+    }
+    let Some(sig) = &env.sig else {
+        return Ok(false);
+    };
+    let Ok(sig) = Signature::from_slice(sig) else {
+        return Ok(false);
+    };
+    let key = VerifyingKey::from_bytes(pubkey).map_err(|e| format!("verify: bad pubkey: {}", e))?;
+    let msg = signing_bytes(env)?;
+    Ok(key.verify(&msg, &sig).is_ok())
+}
@@ -0,0 +1,30 @@
+//! `#[wasm_bindgen]` wrappers so browsers encode and decode with this crate
+//! instead of a parallel JS codec.
+//!
+//! Envelopes cross the boundary as plain JS objects in the serde shape: the
+//! shared test-vector field names (`toHash`, `from`, ...) with byte fields as
+//! base64url strings. The cdylib for `wasm-pack` lives in the `wasm/` crate.
+
+use js_sys::{Uint8Array, JSON};
+use wasm_bindgen::prelude::*;
+
+use crate::{decode, encode, Env};
+
+/// Encode a JS envelope object to its canonical bytes.
+#[wasm_bindgen(js_name = encodeEnvelope)]
+pub fn encode_envelope(js_env: JsValue) -> Result<Uint8Array, JsError> {
+    let json: String = JSON::stringify(&js_env)
+        .map_err(|_| JsError::new("encodeEnvelope: value is not JSON-serializable"))?
+        .into();
+    let env: Env = serde_json::from_str(&json).map_err(|e| JsError::new(&format!("encodeEnvelope: {}", e)))?;
+    let bytes = encode(&env)?;
+    Ok(Uint8Array::from(bytes.as_slice()))
+}
+
+/// Decode canonical bytes to a JS envelope object.
+#[wasm_bindgen(js_name = decodeEnvelope)]
+pub fn decode_envelope(bytes: &[u8]) -> Result<JsValue, JsError> {
+    let env = decode(bytes)?;
+    let json = serde_json::to_string(&env).map_err(|e| JsError::new(&format!("decodeEnvelope: {}", e)))?;
+    JSON::parse(&json).map_err(|_| JsError::new("decodeEnvelope: JSON.parse failed"))
+}
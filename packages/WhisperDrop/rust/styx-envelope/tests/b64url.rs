@@ -0,0 +1,21 @@
+#![cfg(feature = "std")]
+
+use styx_envelope::{b64url_decode_32, b64url_decode_n, b64url_encode};
+
+#[test]
+fn decode_32_returns_array() {
+    let bytes = [7u8; 32];
+    assert_eq!(b64url_decode_32(&b64url_encode(&bytes)).unwrap(), bytes);
+}
+
+#[test]
+fn decode_n_reports_actual_length() {
+    let s = b64url_encode(&[1u8; 31]);
+    assert_eq!(b64url_decode_32(&s).unwrap_err(), "b64url decode: expected 32 bytes, got 31");
+    assert_eq!(b64url_decode_n::<31>(&s).unwrap(), [1u8; 31]);
+}
+
+#[test]
+fn decode_n_rejects_invalid_base64() {
+    assert!(b64url_decode_n::<4>("!!!").is_err());
+}
@@ -0,0 +1,59 @@
+use styx_envelope::{decode, encode, Algo, EncodeError, EnvBuilder, Kind};
+
+#[test]
+fn builder_defaults_version_and_algo() {
+    let env = EnvBuilder::message([1; 32])
+        .to_hash([2; 32])
+        .nonce(vec![3; 24])
+        .body(b"ciphertext".to_vec())
+        .build()
+        .unwrap();
+    assert_eq!(env.v, 1);
+    assert_eq!(env.kind, Kind::Message);
+    assert_eq!(env.algo, Algo::Pmf1);
+    assert_eq!(env.from, None);
+    assert_eq!(env.aad, None);
+    assert_eq!(env.sig, None);
+    assert_eq!(decode(&encode(&env).unwrap()).unwrap(), env);
+}
+
+#[test]
+fn builder_rejects_empty_message_body() {
+    let err = EnvBuilder::message([1; 32]).build().unwrap_err();
+    assert_eq!(err, EncodeError::EmptyMessageBody);
+}
+
+#[test]
+fn builder_allows_empty_body_for_other_kinds() {
+    let env = EnvBuilder::reveal([1; 32]).from([9; 32]).build().unwrap();
+    assert_eq!(env.kind, Kind::Reveal);
+    assert!(env.body.is_empty());
+}
+
+#[test]
+fn encode_into_reuses_buffer_and_clears_on_error() {
+    let env = EnvBuilder::message([1; 32]).body(b"body".to_vec()).build().unwrap();
+    let mut out = vec![0xAA; 500];
+    styx_envelope::encode_into(&env, &mut out).unwrap();
+    assert_eq!(out, encode(&env).unwrap());
+
+    let mut bad = env;
+    bad.body.clear();
+    assert_eq!(styx_envelope::encode_into(&bad, &mut out), Err(EncodeError::EmptyMessageBody));
+    assert!(out.is_empty());
+}
+
+#[test]
+fn encoded_len_matches_encode_across_varint_boundaries() {
+    for len in [0usize, 1, 127, 128, 16_383, 16_384, 70_000] {
+        let env = EnvBuilder::keybundle([1; 32])
+            .from([2; 32])
+            .body(vec![3; len])
+            .aad(vec![4; len / 2])
+            .sig(vec![5; 64])
+            .crc(len % 2 == 0)
+            .build()
+            .unwrap();
+        assert_eq!(styx_envelope::encoded_len(&env), encode(&env).unwrap().len(), "body {len}");
+    }
+}
@@ -0,0 +1,53 @@
+use styx_envelope::{decode_bundle, encode, encode_bundle, DecodeError, EncodeError, Env, EnvBuilder, MAX_BUNDLE_ENVELOPES};
+
+fn envs(n: u8) -> Vec<Env> {
+    (0..n).map(|i| EnvBuilder::message([i; 32]).body(vec![i; 1 + i as usize]).crc(i % 2 == 0).build().unwrap()).collect()
+}
+
+#[test]
+fn bundle_roundtrips() {
+    let envs = envs(3);
+    let buf = encode_bundle(&envs).unwrap();
+    assert_eq!(buf[0], 3);
+    assert_eq!(buf[1..], envs.iter().flat_map(|e| encode(e).unwrap()).collect::<Vec<u8>>());
+    assert_eq!(decode_bundle(&buf).unwrap(), envs);
+}
+
+#[test]
+fn empty_bundle_roundtrips() {
+    assert_eq!(encode_bundle(&[]).unwrap(), [0]);
+    assert!(decode_bundle(&[0]).unwrap().is_empty());
+}
+
+#[test]
+fn count_mismatch_and_trailing_bytes_fail() {
+    let mut buf = encode_bundle(&envs(2)).unwrap();
+    buf[0] = 3;
+    let err = decode_bundle(&buf).unwrap_err();
+    assert_eq!(err, DecodeError::BundleCountMismatch { declared: 3, found: 2 });
+    assert_eq!(err.to_string(), "bundle: count mismatch (declared 3, found 2)");
+
+    buf[0] = 1;
+    assert_eq!(decode_bundle(&buf).unwrap_err(), DecodeError::TrailingBytes);
+}
+
+#[test]
+fn count_is_capped() {
+    let too_many = vec![envs(1).remove(0); MAX_BUNDLE_ENVELOPES + 1];
+    let err = encode_bundle(&too_many).unwrap_err();
+    assert_eq!(err, EncodeError::TooManyEnvelopes { count: 65, max: 64 });
+    assert_eq!(err.to_string(), "bundle: 65 envelopes exceeds 64");
+
+    // A huge declared count is rejected before any allocation.
+    assert_eq!(
+        decode_bundle(&[0xff, 0xff, 0xff, 0x7f]).unwrap_err(),
+        DecodeError::TooManyEnvelopes { count: 268435455, max: 64 }
+    );
+}
+
+#[test]
+fn corrupt_member_fails() {
+    let mut buf = encode_bundle(&envs(2)).unwrap();
+    buf[1] = b'X';
+    assert_eq!(decode_bundle(&buf).unwrap_err(), DecodeError::BadMagic);
+}
@@ -0,0 +1,98 @@
+use styx_envelope::{
+    decode, encode, encoded_len, header_aad, reassemble, DecodeError, EncodeError, Env, Decoder, EnvBuilder,
+};
+
+fn chunks(id: u8, parts: &[&[u8]]) -> Vec<Env> {
+    let total = parts.len() as u16;
+    parts
+        .iter()
+        .enumerate()
+        .map(|(i, body)| EnvBuilder::message([id; 32]).chunk(i as u16, total).body(body.to_vec()).build().unwrap())
+        .collect()
+}
+
+#[test]
+fn chunk_field_follows_id() {
+    let env = EnvBuilder::message([7; 32]).chunk(2, 5).body(b"x".to_vec()).build().unwrap();
+    let buf = encode(&env).unwrap();
+    assert_eq!(buf[6] & (1 << 6), 1 << 6);
+    assert_eq!(buf[41..45], [2, 0, 5, 0]);
+    assert_eq!(encoded_len(&env), buf.len());
+
+    let decoded = decode(&buf).unwrap();
+    assert_eq!(decoded.chunk_info(), Some((2, 5)));
+    assert_eq!(decoded, env);
+}
+
+#[test]
+fn unchunked_envelope_has_no_chunk_info() {
+    let env = EnvBuilder::message([7; 32]).body(b"x".to_vec()).build().unwrap();
+    assert_eq!(decode(&encode(&env).unwrap()).unwrap().chunk_info(), None);
+}
+
+#[test]
+fn truncated_chunk_field_fails() {
+    let env = EnvBuilder::message([7; 32]).chunk(0, 1).body(b"x".to_vec()).build().unwrap();
+    let buf = encode(&env).unwrap();
+    assert_eq!(decode(&buf[..43]), Err(DecodeError::Truncated("chunk")));
+}
+
+#[test]
+fn index_must_be_below_total() {
+    let err = EnvBuilder::message([7; 32]).chunk(3, 3).body(b"x".to_vec()).build().unwrap_err();
+    assert_eq!(err, EncodeError::InvalidChunk { index: 3, total: 3 });
+}
+
+#[test]
+fn header_aad_binds_chunk_position() {
+    let parts = chunks(1, &[b"a", b"b"]);
+    assert_ne!(header_aad(&parts[0]), header_aad(&parts[1]));
+}
+
+#[test]
+fn stream_decoder_frames_chunked_envelopes() {
+    let parts = chunks(1, &[b"ab", b"cd"]);
+    let mut dec = Decoder::new();
+    for env in &parts {
+        dec.push(&encode(env).unwrap());
+    }
+    assert_eq!(dec.next(), Some(Ok(parts[0].clone())));
+    assert_eq!(dec.next(), Some(Ok(parts[1].clone())));
+    assert_eq!(dec.next(), None);
+}
+
+#[test]
+fn reassemble_orders_by_index() {
+    let mut parts = chunks(1, &[b"hello ", b"chunked ", b"world"]);
+    parts.reverse();
+    assert_eq!(reassemble(&parts).unwrap(), b"hello chunked world");
+}
+
+#[test]
+fn reassemble_rejects_gaps_and_duplicates() {
+    let parts = chunks(1, &[b"a", b"b", b"c"]);
+    let err = reassemble(&parts[..2]).unwrap_err();
+    assert_eq!(err, DecodeError::ChunkCount { expected: 3, got: 2 });
+    assert_eq!(err.to_string(), "reassemble: expected 3 chunks, got 2");
+
+    let dup = vec![parts[0].clone(), parts[1].clone(), parts[1].clone()];
+    assert_eq!(reassemble(&dup).unwrap_err(), DecodeError::MissingChunk(2));
+}
+
+#[test]
+fn reassemble_rejects_mixed_messages() {
+    let mut parts = chunks(1, &[b"a", b"b"]);
+    parts[1].id = [2; 32];
+    assert_eq!(reassemble(&parts).unwrap_err(), DecodeError::ChunkIdMismatch);
+
+    let mut parts = chunks(1, &[b"a", b"b"]);
+    parts[1].chunk = Some((1, 3));
+    let err = reassemble(&parts).unwrap_err();
+    assert_eq!(err, DecodeError::ChunkTotalMismatch { expected: 2, got: 3 });
+    assert_eq!(err.to_string(), "reassemble: chunk_total mismatch (3 vs 2)");
+
+    let mut parts = chunks(1, &[b"a", b"b"]);
+    parts[1].chunk = None;
+    assert_eq!(reassemble(&parts).unwrap_err(), DecodeError::NotAChunk);
+    assert_eq!(reassemble(&[]).unwrap_err(), DecodeError::NoChunks);
+}
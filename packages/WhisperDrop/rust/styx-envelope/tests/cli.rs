@@ -0,0 +1,64 @@
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::process::Command;
+
+fn styxctl(args: &[&str]) -> (bool, String, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_styxctl")).args(args).output().expect("run styxctl");
+    (out.status.success(), String::from_utf8(out.stdout).unwrap(), String::from_utf8(out.stderr).unwrap())
+}
+
+fn vectors() -> Vec<serde_json::Value> {
+    serde_json::from_str(&fs::read_to_string("tests/styx-envelope-v1.json").unwrap()).unwrap()
+}
+
+#[test]
+fn decode_prints_fields_for_memo_and_bare_payload() {
+    let v = &vectors()[1];
+    for input in [v["memo"].as_str().unwrap(), v["encoded_b64url"].as_str().unwrap()] {
+        let (ok, out, _) = styxctl(&["decode", input]);
+        assert!(ok);
+        assert!(out.contains("kind:    Reveal"), "{out}");
+        assert!(out.contains("nonce:   24 bytes"), "{out}");
+        assert!(out.contains("sig:     64 bytes"), "{out}");
+    }
+}
+
+#[test]
+fn validate_accepts_vectors_and_reports_errors() {
+    for v in vectors() {
+        let (ok, out, _) = styxctl(&["validate", v["memo"].as_str().unwrap()]);
+        assert!(ok, "{}", v["name"]);
+        assert_eq!(out.trim(), "ok");
+    }
+    let (ok, _, err) = styxctl(&["validate", "styx1:AAAA"]);
+    assert!(!ok);
+    assert_eq!(err.trim(), "styxctl: decode: too short");
+}
+
+#[test]
+fn encode_reads_a_vector_entry() {
+    let v = &vectors()[0];
+    let path = std::env::temp_dir().join(format!("styxctl-encode-{}.json", std::process::id()));
+    fs::write(&path, v.to_string()).unwrap();
+    let (ok, out, _) = styxctl(&["encode", path.to_str().unwrap()]);
+    fs::remove_file(&path).unwrap();
+    assert!(ok);
+    assert_eq!(out.trim(), v["memo"].as_str().unwrap());
+}
+
+#[test]
+fn unknown_command_prints_usage() {
+    let (ok, _, err) = styxctl(&["inspect"]);
+    assert!(!ok);
+    assert!(err.starts_with("usage: styxctl"));
+}
+
+#[test]
+fn foreign_memo_prefix_is_named() {
+    let v = &vectors()[0];
+    let memo = v["memo"].as_str().unwrap().replacen("styx1:", "styx2:", 1);
+    let (ok, _, err) = styxctl(&["decode", &memo]);
+    assert!(!ok);
+    assert_eq!(err.trim(), "styxctl: memo: unsupported prefix styx2:");
+}
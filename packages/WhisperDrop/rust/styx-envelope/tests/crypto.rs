@@ -0,0 +1,80 @@
+#![cfg(feature = "crypto")]
+
+use curve25519_dalek::montgomery::MontgomeryPoint;
+use styx_envelope::{
+    decode, encode, recipient_hash, seal_for_recipient, try_recipient_decrypt, Env, EnvBuilder, SealError,
+};
+
+const ALICE: [u8; 32] = [0x11; 32];
+const BOB: [u8; 32] = [0x22; 32];
+
+fn public(secret: &[u8; 32]) -> [u8; 32] {
+    MontgomeryPoint::mul_base_clamped(*secret).to_bytes()
+}
+
+fn sealed_to(recipient_pub: &[u8; 32], plaintext: &[u8]) -> Env {
+    let mut env = EnvBuilder::message([9; 32]).body(vec![0]).build().unwrap();
+    seal_for_recipient(&mut env, &[0x33; 32], &[0x44; 12], recipient_pub, plaintext).unwrap();
+    env
+}
+
+#[test]
+fn recipient_decrypts_after_roundtrip() {
+    let env = sealed_to(&public(&ALICE), b"hello alice");
+    assert_eq!(env.to_hash, Some(recipient_hash(&public(&ALICE))));
+    let decoded = decode(&encode(&env).unwrap()).unwrap();
+    assert_eq!(try_recipient_decrypt(&decoded, &ALICE, &public(&ALICE)).unwrap(), b"hello alice");
+}
+
+#[test]
+fn other_recipient_gets_none() {
+    let env = sealed_to(&public(&ALICE), b"hello alice");
+    assert_eq!(try_recipient_decrypt(&env, &BOB, &public(&BOB)), None);
+    // Claiming Alice's public key without her secret derives the wrong key.
+    assert_eq!(try_recipient_decrypt(&env, &BOB, &public(&ALICE)), None);
+}
+
+#[test]
+fn header_is_authenticated() {
+    let mut env = sealed_to(&public(&ALICE), b"hello alice");
+    env.id = [10; 32];
+    assert_eq!(try_recipient_decrypt(&env, &ALICE, &public(&ALICE)), None);
+}
+
+#[test]
+fn malformed_envelopes_are_skipped() {
+    let good = sealed_to(&public(&ALICE), b"hello alice");
+
+    let mut env = good.clone();
+    env.body[40] ^= 1;
+    assert_eq!(try_recipient_decrypt(&env, &ALICE, &public(&ALICE)), None);
+
+    let mut env = good.clone();
+    env.nonce = Some(vec![0x44; 24]);
+    assert_eq!(try_recipient_decrypt(&env, &ALICE, &public(&ALICE)), None);
+
+    let mut env = good.clone();
+    env.body.truncate(31);
+    assert_eq!(try_recipient_decrypt(&env, &ALICE, &public(&ALICE)), None);
+
+    let mut env = good;
+    env.to_hash = None;
+    assert_eq!(try_recipient_decrypt(&env, &ALICE, &public(&ALICE)), None);
+}
+
+#[test]
+fn low_order_recipient_key_is_rejected() {
+    let mut env = EnvBuilder::message([9; 32]).body(vec![0]).build().unwrap();
+    let err = seal_for_recipient(&mut env, &[0x33; 32], &[0x44; 12], &[0; 32], b"x").unwrap_err();
+    assert_eq!(err, SealError::InvalidRecipientKey);
+    assert_eq!(err.to_string(), "seal: invalid recipient key");
+}
+
+#[test]
+fn only_messages_are_sealed() {
+    let mut env = EnvBuilder::reveal([9; 32]).from([1; 32]).build().unwrap();
+    assert_eq!(
+        seal_for_recipient(&mut env, &[0x33; 32], &[0x44; 12], &public(&ALICE), b"x"),
+        Err(SealError::NotAMessage)
+    );
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "subtle")]
+
+use styx_envelope::EnvBuilder;
+
+#[test]
+fn ct_eq_sig_compares_full_value() {
+    let env = EnvBuilder::message([1; 32]).body(b"b".to_vec()).sig(vec![9; 64]).build().unwrap();
+    assert!(env.ct_eq_sig(&[9; 64]));
+
+    let mut last_differs = [9u8; 64];
+    last_differs[63] = 8;
+    assert!(!env.ct_eq_sig(&last_differs));
+    assert!(!env.ct_eq_sig(&[9; 63]));
+}
+
+#[test]
+fn ct_eq_sig_is_false_without_sig() {
+    let env = EnvBuilder::message([1; 32]).body(b"b".to_vec()).build().unwrap();
+    assert!(!env.ct_eq_sig(&[]));
+}
+
+#[test]
+fn ct_eq_id_matches_only_identical_ids() {
+    let env = EnvBuilder::message([3; 32]).body(b"b".to_vec()).build().unwrap();
+    assert!(env.ct_eq_id(&[3; 32]));
+    assert!(!env.ct_eq_id(&[4; 32]));
+}
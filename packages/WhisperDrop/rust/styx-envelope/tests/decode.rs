@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use styx_envelope::{
+    check_header, decode, decode_header, decode_prefix, decode_view, decode_with_limit, encode, Algo, DecodeError,
+    Env, Header, Kind, STYX_HEADER_LEN,
+};
+
+fn sample(id_byte: u8, body: &[u8]) -> Env {
+    Env {
+        v: 1,
+        kind: Kind::Message,
+        algo: Algo::Pmf1,
+        id: [id_byte; 32],
+        chunk: None,
+        to_hash: Some([0x11; 32]),
+        from: None,
+        nonce: Some(vec![0x22; 24]),
+        body: body.to_vec(),
+        aad: None,
+        sig: None,
+        ext: BTreeMap::new(),
+        crc: false,
+    }
+}
+
+#[test]
+fn decode_prefix_walks_concatenated_envelopes() {
+    let a = sample(1, b"first");
+    let b = sample(2, b"second body");
+    let mut buf = encode(&a).unwrap();
+    buf.extend_from_slice(&encode(&b).unwrap());
+
+    let (got_a, used_a) = decode_prefix(&buf).expect("first");
+    assert_eq!(got_a, a);
+    let (got_b, used_b) = decode_prefix(&buf[used_a..]).expect("second");
+    assert_eq!(got_b, b);
+    assert_eq!(used_a + used_b, buf.len());
+
+    assert_eq!(decode(&buf).unwrap_err(), DecodeError::TrailingBytes);
+}
+
+#[test]
+fn decode_prefix_rejects_truncated_body() {
+    let buf = encode(&sample(3, b"truncated body")).unwrap();
+    assert!(decode_prefix(&buf[..buf.len() - 1]).is_err());
+}
+
+// Header (magic, v, kind, flags, algo) plus id.
+const FIXED_PREFIX: usize = 4 + 1 + 1 + 2 + 1 + 32;
+
+#[test]
+fn decode_rejects_truncated_to_hash() {
+    let mut env = sample(4, b"x");
+    env.nonce = None;
+    let buf = encode(&env).unwrap();
+    let err = decode(&buf[..FIXED_PREFIX + 31]).unwrap_err();
+    assert_eq!(err, DecodeError::Truncated("to_hash"));
+}
+
+#[test]
+fn decode_rejects_truncated_from() {
+    let mut env = sample(5, b"x");
+    env.to_hash = None;
+    env.nonce = None;
+    env.from = Some([0x33; 32]);
+    let buf = encode(&env).unwrap();
+    let err = decode(&buf[..FIXED_PREFIX + 31]).unwrap_err();
+    assert_eq!(err, DecodeError::Truncated("from"));
+}
+
+#[test]
+fn decode_rejects_truncated_var_fields() {
+    let mut env = sample(6, b"body");
+    env.aad = Some(b"aad".to_vec());
+    env.sig = Some(vec![0x44; 64]);
+    let buf = encode(&env).unwrap();
+    for cut in FIXED_PREFIX..buf.len() {
+        assert!(decode(&buf[..cut]).is_err(), "cut at {cut} should fail");
+    }
+}
+
+#[test]
+fn decode_with_limit_caps_variable_fields() {
+    let env = sample(7, &[0xab; 100]);
+    let buf = encode(&env).unwrap();
+    // nonce (24) + body (100)
+    assert_eq!(decode_with_limit(&buf, 124).unwrap(), env);
+    assert_eq!(decode_with_limit(&buf, 123).unwrap_err(), DecodeError::ExceedsLimit);
+}
+
+#[test]
+fn decode_with_limit_rejects_oversized_length_prefix_before_allocating() {
+    let mut buf = encode(&sample(8, b"x")).unwrap();
+    // Replace the 1-byte body length with a varint claiming ~256MB.
+    let body_len_at = buf.len() - 2;
+    buf.truncate(body_len_at);
+    buf.extend_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    assert_eq!(decode_with_limit(&buf, 1024).unwrap_err(), DecodeError::ExceedsLimit);
+    assert_eq!(decode(&buf).unwrap_err(), DecodeError::OutOfRange);
+}
+
+#[test]
+fn decode_view_borrows_from_buffer() {
+    let mut env = sample(9, b"view body");
+    env.aad = Some(b"ctx".to_vec());
+    let buf = encode(&env).unwrap();
+
+    let view = decode_view(&buf).unwrap();
+    assert_eq!(view.id, env.id);
+    assert_eq!(view.kind, Kind::Message);
+    assert_eq!(view.body, b"view body");
+    assert_eq!(view.aad, Some(&b"ctx"[..]));
+    assert!(buf.as_ptr_range().contains(&view.body.as_ptr()));
+    assert_eq!(view.to_owned(), env);
+}
+
+#[test]
+fn decode_view_matches_decode_errors() {
+    let buf = encode(&sample(10, b"x")).unwrap();
+    let mut trailing = buf.clone();
+    trailing.push(0);
+    assert_eq!(decode_view(&trailing).unwrap_err(), decode(&trailing).unwrap_err());
+    assert_eq!(decode_view(&buf[..20]).unwrap_err(), decode(&buf[..20]).unwrap_err());
+}
+
+#[test]
+fn crc_detects_flipped_body_byte() {
+    let mut env = sample(11, b"checked body");
+    env.crc = true;
+    let mut buf = encode(&env).unwrap();
+    assert_eq!(decode(&buf).unwrap(), env);
+
+    let body_at = buf.len() - 4 - 1;
+    buf[body_at] ^= 0x01;
+    assert_eq!(decode(&buf).unwrap_err(), DecodeError::CrcMismatch);
+}
+
+#[test]
+fn crc_trailer_must_be_present() {
+    let mut env = sample(12, b"checked body");
+    env.crc = true;
+    let buf = encode(&env).unwrap();
+    assert_eq!(decode(&buf[..buf.len() - 2]).unwrap_err(), DecodeError::Truncated("crc"));
+}
+
+#[test]
+fn check_header_reads_only_the_prefix() {
+    let buf = encode(&sample(1, b"body")).unwrap();
+    assert_eq!(check_header(&buf), Ok(()));
+    // Garbage after the fixed header is not inspected.
+    assert_eq!(check_header(&buf[..STYX_HEADER_LEN]), Ok(()));
+    assert!(decode(&buf[..STYX_HEADER_LEN]).is_err());
+
+    assert_eq!(check_header(&buf[..STYX_HEADER_LEN - 1]).unwrap_err(), DecodeError::TooShort);
+    let mut bad = buf.clone();
+    bad[0] = b'X';
+    assert_eq!(check_header(&bad).unwrap_err(), DecodeError::BadMagic);
+    let mut bad = buf;
+    bad[4] = 3;
+    assert_eq!(check_header(&bad).unwrap_err(), DecodeError::UnsupportedVersion(3));
+}
+
+#[test]
+fn decode_header_reads_only_the_prefix() {
+    let mut env = sample(7, b"body");
+    env.kind = Kind::Reveal;
+    env.from = Some([0x33; 32]);
+    let buf = encode(&env).unwrap();
+    let header = Header { v: 1, kind: Kind::Reveal, algo: Algo::Pmf1, id: [7; 32], flags: 0b111 };
+    assert_eq!(decode_header(&buf), Ok(header.clone()));
+    // The body and everything after it are never looked at.
+    assert_eq!(decode_header(&buf[..STYX_HEADER_LEN]), Ok(header));
+
+    assert_eq!(decode_header(&buf[..STYX_HEADER_LEN - 1]).unwrap_err(), DecodeError::TooShort);
+    let mut bad = buf;
+    bad[8] = 9;
+    assert_eq!(decode_header(&bad).unwrap_err(), DecodeError::UnknownAlgo(9));
+}
+
+#[test]
+fn reserved_flag_bits_are_rejected() {
+    let buf = encode(&sample(1, b"body")).unwrap();
+    for bit in 7..16 {
+        let mut bad = buf.clone();
+        let flags = u16::from_le_bytes([bad[6], bad[7]]) | 1 << bit;
+        bad[6..8].copy_from_slice(&flags.to_le_bytes());
+        assert_eq!(decode(&bad).unwrap_err(), DecodeError::ReservedFlags, "bit {bit}");
+        assert_eq!(decode_header(&bad).unwrap_err(), DecodeError::ReservedFlags, "bit {bit}");
+    }
+    assert_eq!(DecodeError::ReservedFlags.to_string(), "decode: reserved flags set");
+}
+
+#[test]
+fn decode_error_displays_legacy_messages() {
+    assert_eq!(DecodeError::BadMagic.to_string(), "decode: bad magic");
+    assert_eq!(DecodeError::Truncated("to_hash").to_string(), "decode: truncated to_hash");
+    assert_eq!(DecodeError::UnsupportedVersion(2).to_string(), "decode: unsupported version 2");
+    assert_eq!(DecodeError::OutOfRange.to_string(), "varBytes out of range");
+}
+
+#[test]
+fn unknown_codes_are_reported() {
+    let buf = encode(&sample(1, b"body")).unwrap();
+    let mut bad = buf.clone();
+    bad[5] = 9;
+    assert_eq!(decode(&bad).unwrap_err(), DecodeError::UnknownKind(9));
+    let mut bad = buf;
+    bad[8] = 7;
+    assert_eq!(decode(&bad).unwrap_err(), DecodeError::UnknownAlgo(7));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn errors_work_with_question_mark_into_box_dyn_error() {
+    fn parse(buf: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        Ok(encode(&decode(buf)?)?)
+    }
+    let buf = encode(&sample(1, b"body")).unwrap();
+    assert_eq!(parse(&buf).unwrap(), buf);
+    assert_eq!(parse(&buf[1..]).unwrap_err().to_string(), "decode: bad magic");
+}
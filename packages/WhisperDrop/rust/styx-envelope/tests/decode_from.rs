@@ -0,0 +1,105 @@
+#![cfg(feature = "std")]
+
+use std::io::{Cursor, Read};
+
+use styx_envelope::{decode_from, encode, DecodeError, Env, EnvBuilder, ReadError};
+
+fn sample(i: u8) -> Env {
+    EnvBuilder::message([i; 32])
+        .to_hash([0x11; 32])
+        .nonce(vec![0x22; 24])
+        .body(vec![i; 300])
+        .aad(b"ctx".to_vec())
+        .sig(vec![0x44; 64])
+        .crc(true)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn stops_at_the_envelope_boundary() {
+    let env = sample(1);
+    let mut bytes = encode(&env).unwrap();
+    let len = bytes.len();
+    bytes.extend_from_slice(b"trailing junk");
+
+    let mut cursor = Cursor::new(bytes);
+    assert_eq!(decode_from(&mut cursor).unwrap(), env);
+    assert_eq!(cursor.position() as usize, len);
+    let mut rest = Vec::new();
+    cursor.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, b"trailing junk");
+}
+
+#[test]
+fn reads_consecutive_envelopes() {
+    let (a, b) = (sample(1), sample(2));
+    let mut bytes = encode(&a).unwrap();
+    bytes.extend_from_slice(&encode(&b).unwrap());
+
+    let mut cursor = Cursor::new(bytes);
+    assert_eq!(decode_from(&mut cursor).unwrap(), a);
+    assert_eq!(decode_from(&mut cursor).unwrap(), b);
+}
+
+/// Hands out one byte per `read`, like a slow socket.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, out: &mut [u8]) -> std::io::Result<usize> {
+        if self.0.is_empty() || out.is_empty() {
+            return Ok(0);
+        }
+        out[0] = self.0[0];
+        self.0 = &self.0[1..];
+        Ok(1)
+    }
+}
+
+#[test]
+fn handles_short_reads() {
+    let env = sample(3);
+    let bytes = encode(&env).unwrap();
+    assert_eq!(decode_from(&mut Trickle(&bytes)).unwrap(), env);
+}
+
+#[test]
+fn truncated_source_is_an_error() {
+    let bytes = encode(&sample(4)).unwrap();
+    for cut in [0, 10, bytes.len() - 1] {
+        let err = decode_from(&mut Cursor::new(&bytes[..cut])).unwrap_err();
+        assert!(matches!(err, ReadError::Decode(DecodeError::Truncated("envelope"))), "cut at {cut}: {err}");
+    }
+}
+
+#[test]
+fn huge_declared_length_does_not_preallocate() {
+    let mut bytes = encode(&EnvBuilder::message([5; 32]).body(b"x".to_vec()).build().unwrap()).unwrap();
+    // Replace the 1-byte body length with a varint claiming ~256MB.
+    bytes.truncate(bytes.len() - 2);
+    bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    let err = decode_from(&mut Cursor::new(bytes)).unwrap_err();
+    assert!(matches!(err, ReadError::Decode(DecodeError::Truncated("envelope"))));
+}
+
+#[test]
+fn bad_magic_is_reported() {
+    let mut bytes = encode(&sample(6)).unwrap();
+    bytes[0] = b'X';
+    let err = decode_from(&mut Cursor::new(bytes)).unwrap_err();
+    assert!(matches!(err, ReadError::Decode(DecodeError::BadMagic)));
+    assert_eq!(err.to_string(), "decode: bad magic");
+}
+
+#[test]
+fn reader_failures_are_io_errors() {
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "gone"))
+        }
+    }
+    let err = decode_from(&mut Broken).unwrap_err();
+    assert!(matches!(&err, ReadError::Io(e) if e.kind() == std::io::ErrorKind::ConnectionReset));
+    assert_eq!(err.to_string(), "io: gone");
+}
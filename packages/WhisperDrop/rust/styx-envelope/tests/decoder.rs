@@ -0,0 +1,73 @@
+use styx_envelope::{encode, DecodeError, Decoder, EnvBuilder};
+
+fn envs() -> Vec<styx_envelope::Env> {
+    (0..3u8)
+        .map(|i| {
+            EnvBuilder::message([i; 32])
+                .to_hash([i + 10; 32])
+                .nonce(vec![i; 24])
+                .body(vec![i; 200 + i as usize])
+                .aad(b"ctx".to_vec())
+                .build()
+                .unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn yields_envelopes_split_across_pushes() {
+    let envs = envs();
+    let stream: Vec<u8> = envs.iter().flat_map(|e| encode(e).unwrap()).collect();
+
+    for chunk in [1, 7, 64, stream.len()] {
+        let mut dec = Decoder::new();
+        let mut out = Vec::new();
+        for piece in stream.chunks(chunk) {
+            dec.push(piece);
+            for env in dec.by_ref() {
+                out.push(env.unwrap());
+            }
+        }
+        assert_eq!(out, envs, "chunk size {chunk}");
+        assert_eq!(dec.buffered(), 0);
+    }
+}
+
+#[test]
+fn waits_for_more_bytes() {
+    let bytes = encode(&envs()[0]).unwrap();
+    let mut dec = Decoder::new();
+    dec.push(&bytes[..bytes.len() - 1]);
+    assert!(dec.next().is_none());
+    dec.push(&bytes[bytes.len() - 1..]);
+    assert!(dec.next().unwrap().is_ok());
+    assert!(dec.next().is_none());
+}
+
+#[test]
+fn malformed_input_errors_once_and_resets() {
+    let mut dec = Decoder::new();
+    dec.push(b"STYQ");
+    assert_eq!(dec.next().unwrap().unwrap_err(), DecodeError::BadMagic);
+    assert_eq!(dec.buffered(), 0);
+    assert!(dec.next().is_none());
+}
+
+#[test]
+fn reserved_flags_are_rejected_before_the_frame_arrives() {
+    let mut bytes = encode(&envs()[0]).unwrap();
+    bytes[7] |= 0x80;
+    let mut dec = Decoder::new();
+    dec.push(&bytes[..9]);
+    assert_eq!(dec.next().unwrap().unwrap_err(), DecodeError::ReservedFlags);
+}
+
+#[test]
+fn limit_rejects_oversized_frames_early() {
+    let bytes = encode(&envs()[0]).unwrap();
+    assert!(bytes.len() > 256);
+    let mut dec = Decoder::with_limit(256);
+    // Enough to read the 200-byte body's length prefix, but not the body.
+    dec.push(&bytes[..100]);
+    assert_eq!(dec.next().unwrap().unwrap_err(), DecodeError::ExceedsLimit);
+}
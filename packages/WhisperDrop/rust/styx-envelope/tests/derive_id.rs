@@ -0,0 +1,32 @@
+use styx_envelope::{derive_id, encode, Algo, Env, Kind};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn derive_id_vectors() {
+    let id = derive_id(&Kind::Message, &Algo::Pmf1, b"hello", Some(&[0x33; 32]));
+    assert_eq!(hex(&id), "cba2702f08300b1c8b911400c7110021bf0adbe9e6728d7f30360a633d6eef2f");
+
+    let id = derive_id(&Kind::Keybundle, &Algo::Pmf1, b"", None);
+    assert_eq!(hex(&id), "ed1408980fea55ef78650cf3dc578a61d8a7605e4143f6bdfae8b24fe699a55e");
+}
+
+#[test]
+fn every_input_changes_the_id() {
+    let base = derive_id(&Kind::Message, &Algo::Pmf1, b"hello", Some(&[0x33; 32]));
+    assert_ne!(derive_id(&Kind::Reveal, &Algo::Pmf1, b"hello", Some(&[0x33; 32])), base);
+    assert_ne!(derive_id(&Kind::Message, &Algo::Pmf2, b"hello", Some(&[0x33; 32])), base);
+    assert_ne!(derive_id(&Kind::Message, &Algo::Pmf1, b"hellO", Some(&[0x33; 32])), base);
+    assert_ne!(derive_id(&Kind::Message, &Algo::Pmf1, b"hello", Some(&[0x34; 32])), base);
+    assert_ne!(derive_id(&Kind::Message, &Algo::Pmf1, b"hello", None), base);
+}
+
+#[test]
+fn with_derived_id_is_stable_across_resends() {
+    let a = Env::with_derived_id(Kind::Message, Algo::Pmf1, b"hello".to_vec(), Some([0x33; 32]));
+    let b = Env::with_derived_id(Kind::Message, Algo::Pmf1, b"hello".to_vec(), Some([0x33; 32]));
+    assert_eq!(a.id, derive_id(&Kind::Message, &Algo::Pmf1, b"hello", Some(&[0x33; 32])));
+    assert_eq!(encode(&a).unwrap(), encode(&b).unwrap());
+}
@@ -0,0 +1,75 @@
+#![cfg(feature = "digest")]
+
+use std::collections::BTreeMap;
+
+use sha2::digest::{consts::U32, FixedOutput, HashMarker, Output, OutputSizeUser, Update};
+use sha2::{Digest, Sha256};
+use styx_envelope::{encode, encode_to_hasher, EncodeError, Env, EnvBuilder, STYX_V2};
+
+/// A `Digest` that records what it is fed instead of hashing it.
+#[derive(Default)]
+struct Recorder(Vec<u8>);
+
+impl Update for Recorder {
+    fn update(&mut self, data: &[u8]) {
+        self.0.extend_from_slice(data);
+    }
+}
+
+impl OutputSizeUser for Recorder {
+    type OutputSize = U32;
+}
+
+impl FixedOutput for Recorder {
+    fn finalize_into(self, _out: &mut Output<Self>) {}
+}
+
+impl HashMarker for Recorder {}
+
+fn fed(env: &Env) -> Vec<u8> {
+    let mut h = Sha256::new();
+    encode_to_hasher(env, &mut h).unwrap();
+    h.finalize().to_vec()
+}
+
+fn cases() -> Vec<Env> {
+    let full = EnvBuilder::message([0x11; 32])
+        .to_hash([0x22; 32])
+        .from([0x33; 32])
+        .nonce(vec![0x44; 24])
+        .body(vec![0x55; 300])
+        .aad(b"app".to_vec())
+        .sig(vec![0x66; 64])
+        .crc(true)
+        .build()
+        .unwrap();
+    let mut v2 = EnvBuilder::message([0x77; 32]).body(b"hi".to_vec()).crc(true).build().unwrap();
+    v2.v = STYX_V2;
+    v2.ext = BTreeMap::from([(1, b"one".to_vec()), (9, vec![0; 200])]);
+    vec![EnvBuilder::message([0; 32]).body(b"x".to_vec()).build().unwrap(), full, v2]
+}
+
+#[test]
+fn digest_matches_hash_of_encode() {
+    for env in cases() {
+        assert_eq!(fed(&env), Sha256::digest(encode(&env).unwrap()).to_vec());
+    }
+}
+
+#[test]
+fn fed_bytes_equal_encode() {
+    for env in cases() {
+        let mut rec = Recorder::default();
+        encode_to_hasher(&env, &mut rec).unwrap();
+        assert_eq!(rec.0, encode(&env).unwrap());
+    }
+}
+
+#[test]
+fn invalid_envelope_feeds_nothing() {
+    let mut env = cases().remove(0);
+    env.v = 9;
+    let mut h = Sha256::new();
+    assert_eq!(encode_to_hasher(&env, &mut h), Err(EncodeError::UnsupportedVersion(9)));
+    assert_eq!(h.finalize(), Sha256::new().finalize());
+}
@@ -0,0 +1,51 @@
+use styx_envelope::{encode, header_aad, Algo, EnvBuilder};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Pinned so other implementations (the TS client) can check byte equality.
+#[test]
+fn header_aad_vector() {
+    let env = EnvBuilder::message([0x11; 32])
+        .algo(Algo::Pmf2)
+        .to_hash([0x22; 32])
+        .from([0x33; 32])
+        .nonce(vec![0x44; 24])
+        .body(b"ciphertext".to_vec())
+        .aad(b"app".to_vec())
+        .sig(vec![0x55; 64])
+        .crc(true)
+        .build()
+        .unwrap();
+
+    let expected = [
+        "5354595801010300",
+        "02",
+        &"11".repeat(32),
+        &"22".repeat(32),
+        &"33".repeat(32),
+    ]
+    .concat();
+    assert_eq!(hex(&header_aad(&env)), expected);
+}
+
+#[test]
+fn header_aad_is_the_encoded_header_for_plain_envelopes() {
+    let env = EnvBuilder::message([1; 32]).to_hash([2; 32]).body(b"x".to_vec()).build().unwrap();
+    let aad = header_aad(&env);
+    assert_eq!(aad.len(), 41 + 32);
+    assert_eq!(aad, encode(&env).unwrap()[..aad.len()]);
+}
+
+#[test]
+fn header_aad_ignores_body_sig_and_nonce_but_not_routing() {
+    let base = EnvBuilder::message([1; 32]).to_hash([2; 32]).body(b"x".to_vec());
+    let aad = header_aad(&base.clone().build().unwrap());
+
+    let resealed = base.clone().body(b"other".to_vec()).nonce(vec![9; 24]).sig(vec![1; 64]).build().unwrap();
+    assert_eq!(header_aad(&resealed), aad);
+
+    let rerouted = base.to_hash([3; 32]).build().unwrap();
+    assert_ne!(header_aad(&rerouted), aad);
+}
@@ -0,0 +1,25 @@
+use styx_envelope::{Algo, EnvBuilder};
+
+#[test]
+fn message_with_nonce_is_encrypted() {
+    for algo in [Algo::Pmf1, Algo::Pmf2] {
+        let env =
+            EnvBuilder::message([1; 32]).algo(algo.clone()).nonce(vec![0; 24]).body(b"ct".to_vec()).build().unwrap();
+        assert!(env.is_encrypted(), "{algo:?}");
+    }
+}
+
+#[test]
+fn message_without_nonce_is_not_encrypted() {
+    let env = EnvBuilder::message([1; 32]).body(b"hello".to_vec()).build().unwrap();
+    assert!(!env.is_encrypted());
+}
+
+#[test]
+fn keybundles_and_reveals_are_not_encrypted() {
+    let bundle = EnvBuilder::keybundle([2; 32]).body(b"keys".to_vec()).build().unwrap();
+    assert!(!bundle.is_encrypted());
+
+    let reveal = EnvBuilder::reveal([3; 32]).from([4; 32]).nonce(vec![0; 24]).body(b"key".to_vec()).build().unwrap();
+    assert!(!reveal.is_encrypted());
+}
@@ -0,0 +1,89 @@
+use styx_envelope::{decode, encode, Algo, DecodeError, EnvBuilder, KeyBundle};
+
+fn bundle(one_time: usize) -> KeyBundle {
+    KeyBundle {
+        identity_key: [1; 32],
+        signed_prekey: [2; 32],
+        prekey_sig: [3; 64],
+        one_time_prekeys: (0..one_time).map(|i| [4 + i as u8; 32]).collect(),
+        supported_algos: Vec::new(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Pinned so other implementations can check byte equality.
+#[test]
+fn body_vector() {
+    let expected = [
+        "01".repeat(32),
+        "02".repeat(32),
+        "03".repeat(64),
+        "02".into(),
+        "04".repeat(32),
+        "05".repeat(32),
+    ]
+    .concat();
+    assert_eq!(hex(&bundle(2).to_body()), expected);
+}
+
+#[test]
+fn algo_list_follows_prekeys() {
+    let mut b = bundle(1);
+    b.supported_algos = vec![1, 2, 9];
+    let body = b.to_body();
+    assert_eq!(body[body.len() - 4..], [3, 1, 2, 9]);
+    assert_eq!(KeyBundle::from_body(&body), Ok(b));
+}
+
+#[test]
+fn bundles_without_algo_list_still_parse() {
+    let body = bundle(2).to_body();
+    assert_eq!(body.len(), 128 + 1 + 64);
+    assert!(KeyBundle::from_body(&body).unwrap().supported_algos.is_empty());
+}
+
+#[test]
+fn best_algo_picks_highest_common_known_code() {
+    let mut b = bundle(0);
+    b.supported_algos = vec![1, 2, 200];
+    assert_eq!(b.best_algo(&[1, 2]), Some(Algo::Pmf2));
+    assert_eq!(b.best_algo(&[1]), Some(Algo::Pmf1));
+    // 200 is common but unknown, so it is skipped rather than an error.
+    assert_eq!(b.best_algo(&[1, 200]), Some(Algo::Pmf1));
+    assert_eq!(b.best_algo(&[200]), None);
+    assert_eq!(bundle(0).best_algo(&[1, 2]), None);
+}
+
+#[test]
+fn roundtrips_through_a_keybundle_envelope() {
+    for n in [0, 1, 5] {
+        let env = EnvBuilder::keybundle([9; 32]).body(bundle(n).to_body()).build().unwrap();
+        let decoded = decode(&encode(&env).unwrap()).unwrap();
+        assert_eq!(decoded.key_bundle(), Some(Ok(bundle(n))));
+    }
+}
+
+#[test]
+fn other_kinds_have_no_key_bundle() {
+    let env = EnvBuilder::message([9; 32]).body(bundle(1).to_body()).build().unwrap();
+    assert_eq!(env.key_bundle(), None);
+}
+
+#[test]
+fn malformed_bodies_are_rejected() {
+    let body = bundle(2).to_body();
+    assert_eq!(KeyBundle::from_body(&body[..127]), Err(DecodeError::Truncated("keybundle")));
+    assert_eq!(KeyBundle::from_body(&body[..128]), Err(DecodeError::Truncated("varint")));
+    assert_eq!(KeyBundle::from_body(&body[..body.len() - 1]), Err(DecodeError::OutOfRange));
+    assert_eq!(KeyBundle::from_body(&[body.as_slice(), &[0]].concat()), Err(DecodeError::TrailingBytes));
+    assert_eq!(KeyBundle::from_body(&[body.as_slice(), &[2, 1]].concat()), Err(DecodeError::OutOfRange));
+    assert_eq!(KeyBundle::from_body(&[body.as_slice(), &[1, 1, 0]].concat()), Err(DecodeError::TrailingBytes));
+
+    // A huge declared count fails before allocating.
+    let mut huge = body[..128].to_vec();
+    huge.extend_from_slice(&[0xff, 0xff, 0xff, 0x7f]);
+    assert_eq!(KeyBundle::from_body(&huge), Err(DecodeError::OutOfRange));
+}
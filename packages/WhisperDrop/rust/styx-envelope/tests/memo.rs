@@ -0,0 +1,29 @@
+#![cfg(feature = "std")]
+
+use styx_envelope::{from_memo, to_memo, DecodeError, EnvBuilder, MemoError};
+
+#[test]
+fn memo_roundtrip_tolerates_whitespace() {
+    let env = EnvBuilder::message([5; 32]).body(b"hi".to_vec()).build().unwrap();
+    let memo = to_memo(&env).unwrap();
+    assert!(memo.starts_with("styx1:"));
+    assert_eq!(from_memo(&format!("  {}\n", memo)).unwrap(), env);
+}
+
+#[test]
+fn memo_rejects_missing_or_foreign_prefix() {
+    let env = EnvBuilder::message([5; 32]).body(b"hi".to_vec()).build().unwrap();
+    let memo = to_memo(&env).unwrap();
+    let payload = memo.trim_start_matches("styx1:");
+
+    assert_eq!(from_memo(payload), Err(MemoError::MissingPrefix));
+    let err = from_memo(&format!("styx2:{}", payload)).unwrap_err();
+    assert_eq!(err, MemoError::UnsupportedPrefix("styx2".into()));
+    assert_eq!(err.to_string(), "memo: unsupported prefix styx2:");
+}
+
+#[test]
+fn memo_payload_errors_are_typed() {
+    assert!(matches!(from_memo("styx1:not base64!"), Err(MemoError::Base64(_))));
+    assert_eq!(from_memo("styx1:AAAA"), Err(MemoError::Decode(DecodeError::TooShort)));
+}
@@ -0,0 +1,30 @@
+use styx_envelope::{encode, EnvBuilder};
+
+#[test]
+fn empty_optionals_encode_like_none_after_normalize() {
+    let clean = EnvBuilder::message([3; 32]).from([4; 32]).body(b"ct".to_vec()).build().unwrap();
+
+    let mut empty = clean.clone();
+    empty.nonce = Some(Vec::new());
+    empty.aad = Some(Vec::new());
+    empty.sig = Some(Vec::new());
+    assert_ne!(encode(&empty).unwrap(), encode(&clean).unwrap());
+
+    empty.normalize();
+    assert_eq!(empty, clean);
+    assert_eq!(encode(&empty).unwrap(), encode(&clean).unwrap());
+}
+
+#[test]
+fn non_empty_optionals_are_kept() {
+    let mut env = EnvBuilder::message([3; 32])
+        .nonce(vec![1; 24])
+        .aad(b"ctx".to_vec())
+        .sig(vec![2; 64])
+        .body(b"ct".to_vec())
+        .build()
+        .unwrap();
+    let before = env.clone();
+    env.normalize();
+    assert_eq!(env, before);
+}
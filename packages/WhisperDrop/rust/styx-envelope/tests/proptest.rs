@@ -0,0 +1,73 @@
+//! Randomized encode/decode coverage on top of the fixed vectors.
+
+use proptest::collection::{btree_map, vec};
+use proptest::prelude::*;
+use styx_envelope::{decode, decode_prefix, decode_view, encode, encoded_len, Algo, Env, Kind};
+
+fn bytes(max: usize) -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..max)
+}
+
+fn chunk() -> impl Strategy<Value = Option<(u16, u16)>> {
+    proptest::option::of((1..=u16::MAX).prop_flat_map(|total| (0..total, Just(total))))
+}
+
+/// Any envelope `Env::validate` accepts.
+fn arb_env() -> impl Strategy<Value = Env> {
+    (
+        prop_oneof![Just(Kind::Message), Just(Kind::Reveal), Just(Kind::Keybundle), Just(Kind::Ack)],
+        prop_oneof![Just(Algo::Pmf1), Just(Algo::Pmf2)],
+        any::<[u8; 32]>(),
+        chunk(),
+        proptest::option::of(any::<[u8; 32]>()),
+        proptest::option::of(any::<[u8; 32]>()),
+        proptest::option::of(bytes(64)),
+        bytes(512),
+        (proptest::option::of(bytes(64)), proptest::option::of(bytes(128)), any::<bool>()),
+        // `Some` makes a v2 envelope, possibly with an empty ext section.
+        proptest::option::of(btree_map(any::<u8>(), bytes(32), 0..4)),
+    )
+        .prop_map(|(kind, algo, id, chunk, to_hash, from, nonce, body, (aad, sig, crc), ext)| {
+            let v = if ext.is_some() { 2 } else { 1 };
+            let ext = ext.unwrap_or_default();
+            let mut env = Env { v, kind, algo, id, chunk, to_hash, from, nonce, body, aad, sig, ext, crc };
+            match env.kind {
+                Kind::Message if env.body.is_empty() => env.body.push(0),
+                Kind::Reveal if env.from.is_none() => env.from = Some([0; 32]),
+                Kind::Keybundle => env.nonce = None,
+                Kind::Ack => env.body.clear(),
+                _ => {}
+            }
+            env
+        })
+}
+
+proptest! {
+    #[test]
+    fn decode_inverts_encode(env in arb_env()) {
+        let buf = encode(&env).unwrap();
+        prop_assert_eq!(buf.len(), encoded_len(&env));
+        let decoded = decode(&buf).unwrap();
+        prop_assert_eq!(&decoded, &env);
+        prop_assert_eq!(encode(&decoded).unwrap(), buf);
+    }
+
+    #[test]
+    fn decode_never_panics(buf in bytes(256)) {
+        let _ = decode(&buf);
+        let _ = decode_prefix(&buf);
+        let _ = decode_view(&buf);
+    }
+
+    #[test]
+    fn decode_never_panics_on_mutated_envelopes(env in arb_env(), at in any::<prop::sample::Index>(), byte in any::<u8>(), cut in any::<prop::sample::Index>()) {
+        let buf = encode(&env).unwrap();
+        // Every length is fixed by the header and prefixes, so no strict prefix decodes.
+        prop_assert!(decode(&buf[..cut.index(buf.len())]).is_err());
+
+        let mut mutated = buf;
+        let i = at.index(mutated.len());
+        mutated[i] = byte;
+        let _ = decode(&mutated);
+    }
+}
@@ -0,0 +1,24 @@
+use styx_envelope::{recipient_hash, EnvBuilder};
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[test]
+fn recipient_hash_vectors() {
+    assert_eq!(
+        hex(&recipient_hash(&[7; 32])),
+        "030288dd35ada4793a71e9b1a1f503347542963d4b08a2881eec92ce9ed1784a"
+    );
+    let pubkey: [u8; 32] = core::array::from_fn(|i| i as u8);
+    assert_eq!(
+        hex(&recipient_hash(&pubkey)),
+        "2e827e38a17d3009a6e6862829e05dadff93ce8eddb8335833492d9c97ae6a0d"
+    );
+}
+
+#[test]
+fn to_recipient_sets_to_hash() {
+    let env = EnvBuilder::message([1; 32]).to_recipient(&[7; 32]).body(b"x".to_vec()).build().unwrap();
+    assert_eq!(env.to_hash, Some(recipient_hash(&[7; 32])));
+}
@@ -0,0 +1,32 @@
+use styx_envelope::{encode, encode_for_relay, encoded_len, Algo, EncodeError, Env, Kind, MAX_ENVELOPE_BYTES};
+
+/// A message whose encoding is exactly `len` bytes.
+fn sized(len: usize) -> Env {
+    let mut env = Env::with_derived_id(Kind::Message, Algo::Pmf1, Vec::new(), None);
+    while encoded_len(&env) < len {
+        env.body.push(0xab);
+    }
+    assert_eq!(encoded_len(&env), len);
+    env
+}
+
+#[test]
+fn envelope_at_the_limit_encodes() {
+    let env = sized(MAX_ENVELOPE_BYTES);
+    assert_eq!(encode_for_relay(&env).unwrap(), encode(&env).unwrap());
+}
+
+#[test]
+fn envelope_one_byte_over_the_limit_is_rejected() {
+    let env = sized(MAX_ENVELOPE_BYTES + 1);
+    let err = encode_for_relay(&env).unwrap_err();
+    assert_eq!(err, EncodeError::TooLarge { len: MAX_ENVELOPE_BYTES + 1, max: MAX_ENVELOPE_BYTES });
+    assert_eq!(err.to_string(), format!("encode: envelope exceeds relay max ({} > {})", MAX_ENVELOPE_BYTES + 1, MAX_ENVELOPE_BYTES));
+    assert!(encode(&env).is_ok());
+}
+
+#[test]
+fn invalid_envelope_reports_the_encode_error() {
+    let env = Env::with_derived_id(Kind::Message, Algo::Pmf1, Vec::new(), None);
+    assert_eq!(encode_for_relay(&env).unwrap_err(), encode(&env).unwrap_err());
+}
@@ -0,0 +1,56 @@
+use std::collections::HashSet;
+
+use styx_envelope::{decode, encode, roundtrip_check, DecodeError, EncodeError, EnvBuilder, RoundtripError};
+
+fn canonical() -> Vec<u8> {
+    let env = EnvBuilder::message([3; 32]).to_hash([4; 32]).nonce(vec![5; 24]).body(b"ciphertext".to_vec()).crc(true);
+    encode(&env.build().unwrap()).unwrap()
+}
+
+#[test]
+fn canonical_bytes_pass() {
+    assert_eq!(roundtrip_check(&canonical()), Ok(()));
+}
+
+#[test]
+fn undecodable_bytes_fail() {
+    let buf = canonical();
+    assert_eq!(roundtrip_check(&buf[1..]), Err(RoundtripError::Decode(DecodeError::BadMagic)));
+    let err = roundtrip_check(&[buf.as_slice(), &[0]].concat()).unwrap_err();
+    assert_eq!(err, RoundtripError::Decode(DecodeError::TrailingBytes));
+    assert_eq!(err.to_string(), "roundtrip: decode: trailing bytes");
+}
+
+#[test]
+fn overlong_length_prefix_is_rejected() {
+    // Message with only a body: header (41 bytes), then uleb128(len) || body.
+    let env = EnvBuilder::message([3; 32]).body(b"hi".to_vec()).build().unwrap();
+    let buf = encode(&env).unwrap();
+    assert_eq!(buf[41], 2);
+
+    // 0x82 0x00 also means 2, one byte longer.
+    let overlong = [&buf[..41], &[0x82, 0x00], &buf[42..]].concat();
+    assert_eq!(roundtrip_check(&overlong), Err(RoundtripError::Decode(DecodeError::VarintNonCanonical)));
+}
+
+#[test]
+fn decodable_but_invalid_envelope_fails() {
+    // A keybundle with a nonce decodes, but encode refuses it.
+    let env = EnvBuilder::keybundle([3; 32]).body(b"k".to_vec()).build().unwrap();
+    let mut buf = encode(&env).unwrap();
+    buf[6] |= 1 << 2; // F_NONCE
+    buf.insert(41, 0); // empty nonce
+    let err = roundtrip_check(&buf).unwrap_err();
+    assert_eq!(err, RoundtripError::Encode(EncodeError::KeybundleWithNonce));
+    assert_eq!(err.to_string(), "roundtrip: validate: keybundle must not carry a nonce");
+}
+
+#[test]
+fn hash_set_dedups_by_content() {
+    let env = decode(&canonical()).unwrap();
+    let mut other = env.clone();
+    other.crc = false;
+    let set: HashSet<_> = [env.clone(), decode(&canonical()).unwrap(), other.clone()].into_iter().collect();
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(&env) && set.contains(&other));
+}
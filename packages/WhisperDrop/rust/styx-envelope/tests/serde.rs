@@ -0,0 +1,36 @@
+#![cfg(feature = "serde")]
+
+use std::fs;
+
+use serde::Deserialize;
+use styx_envelope::{b64url_encode, encode, Env};
+
+#[derive(Debug, Deserialize)]
+struct Vector {
+    name: String,
+    env: Env,
+    encoded_b64url: String,
+}
+
+#[test]
+fn env_deserializes_from_vector_json() {
+    let text = fs::read_to_string("tests/styx-envelope-v1.json").expect("read vectors");
+    let vectors: Vec<Vector> = serde_json::from_str(&text).expect("parse json");
+
+    for v in vectors {
+        let encoded = encode(&v.env).expect("encode");
+        assert_eq!(b64url_encode(&encoded), v.encoded_b64url, "{}: encoded mismatch", v.name);
+    }
+}
+
+#[test]
+fn env_json_roundtrip_matches_vector_schema() {
+    let text = fs::read_to_string("tests/styx-envelope-v1.json").expect("read vectors");
+    let raw: Vec<serde_json::Value> = serde_json::from_str(&text).expect("parse json");
+
+    for v in raw {
+        let env: Env = serde_json::from_value(v["env"].clone()).expect("env");
+        let back = serde_json::to_value(&env).expect("serialize");
+        assert_eq!(back, v["env"], "{}: json mismatch", v["name"]);
+    }
+}
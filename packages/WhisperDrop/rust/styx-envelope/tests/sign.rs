@@ -0,0 +1,48 @@
+#![cfg(feature = "sign")]
+
+use ed25519_dalek::SigningKey;
+use styx_envelope::{decode, encode, sign, verify, EnvBuilder};
+
+const SECRET: [u8; 32] = [42; 32];
+
+fn signer_pub() -> [u8; 32] {
+    SigningKey::from_bytes(&SECRET).verifying_key().to_bytes()
+}
+
+#[test]
+fn sign_then_verify_roundtrips_through_encoding() {
+    let pubkey = signer_pub();
+    let mut env = EnvBuilder::message([1; 32])
+        .to_hash([2; 32])
+        .from(pubkey)
+        .body(b"hello".to_vec())
+        .build()
+        .unwrap();
+    sign(&mut env, &SECRET).unwrap();
+    assert_eq!(env.sig.as_ref().map(Vec::len), Some(64));
+
+    let decoded = decode(&encode(&env).unwrap()).unwrap();
+    assert_eq!(verify(&decoded, &pubkey), Ok(true));
+}
+
+#[test]
+fn verify_fails_on_tampered_body() {
+    let pubkey = signer_pub();
+    let mut env = EnvBuilder::message([1; 32]).from(pubkey).body(b"hello".to_vec()).build().unwrap();
+    sign(&mut env, &SECRET).unwrap();
+    env.body[0] ^= 1;
+    assert_eq!(verify(&env, &pubkey), Ok(false));
+}
+
+#[test]
+fn from_is_required() {
+    let mut env = EnvBuilder::message([1; 32]).body(b"hello".to_vec()).build().unwrap();
+    assert!(sign(&mut env, &SECRET).is_err());
+    assert!(verify(&env, &signer_pub()).is_err());
+}
+
+#[test]
+fn sign_rejects_mismatched_from() {
+    let mut env = EnvBuilder::message([1; 32]).from([9; 32]).body(b"hello".to_vec()).build().unwrap();
+    assert_eq!(sign(&mut env, &SECRET), Err("sign: from does not match signing key".into()));
+}
@@ -0,0 +1,97 @@
+#![cfg(feature = "std")]
+
+use std::collections::BTreeMap;
+use std::fs;
+
+use styx_envelope::{
+    b64url_decode, b64url_decode_32, b64url_encode, decode, encode, encoded_len, DecodeError, Decoder, EncodeError,
+    EnvBuilder, STYX_V2,
+};
+
+fn vector() -> serde_json::Value {
+    let text = fs::read_to_string("tests/styx-envelope-v2.json").expect("read vectors");
+    let vectors: Vec<serde_json::Value> = serde_json::from_str(&text).expect("parse json");
+    vectors[0].clone()
+}
+
+#[test]
+fn v2_vector_with_two_ext_entries() {
+    let v = vector();
+    let e = &v["env"];
+    let b64 = |k: &str| b64url_decode(e[k].as_str().unwrap()).unwrap();
+    let env = EnvBuilder::message(b64url_decode_32(e["id"].as_str().unwrap()).unwrap())
+        .to_hash(b64url_decode_32(e["toHash"].as_str().unwrap()).unwrap())
+        .body(b64("body"))
+        .ext(7, vec![1, 2, 3])
+        .ext(1, b"text/plain".to_vec())
+        .build()
+        .unwrap();
+    assert_eq!(env.v, STYX_V2);
+
+    let buf = encode(&env).unwrap();
+    assert_eq!(b64url_encode(&buf), v["encoded_b64url"].as_str().unwrap());
+    assert_eq!(encoded_len(&env), buf.len());
+    let decoded = decode(&buf).unwrap();
+    assert_eq!(decoded.ext, BTreeMap::from([(1, b"text/plain".to_vec()), (7, vec![1, 2, 3])]));
+    assert_eq!(decoded, env);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn v2_vector_env_json_roundtrips() {
+    let v = vector();
+    let env: styx_envelope::Env = serde_json::from_value(v["env"].clone()).unwrap();
+    assert_eq!(b64url_encode(&encode(&env).unwrap()), v["encoded_b64url"].as_str().unwrap());
+    assert_eq!(serde_json::to_value(&env).unwrap(), v["env"]);
+}
+
+#[test]
+fn v1_decodes_with_empty_ext() {
+    let env = EnvBuilder::message([1; 32]).body(b"x".to_vec()).build().unwrap();
+    let decoded = decode(&encode(&env).unwrap()).unwrap();
+    assert_eq!(decoded.v, 1);
+    assert!(decoded.ext.is_empty());
+}
+
+#[test]
+fn v1_cannot_carry_ext() {
+    let mut env = EnvBuilder::message([1; 32]).body(b"x".to_vec()).ext(1, vec![1]).build().unwrap();
+    env.v = 1;
+    assert_eq!(encode(&env), Err(EncodeError::ExtRequiresV2));
+}
+
+#[test]
+fn ext_is_covered_by_crc_and_framed_by_decoder() {
+    let env = EnvBuilder::message([1; 32]).body(b"x".to_vec()).ext(200, vec![9; 40]).crc(true).build().unwrap();
+    let buf = encode(&env).unwrap();
+
+    let mut corrupt = buf.clone();
+    let n = corrupt.len();
+    corrupt[n - 5] ^= 1;
+    assert_eq!(decode(&corrupt), Err(DecodeError::CrcMismatch));
+
+    let mut dec = Decoder::new();
+    for b in &buf {
+        dec.push(&[*b]);
+    }
+    assert_eq!(dec.next(), Some(Ok(env)));
+}
+
+#[test]
+fn ext_types_must_ascend() {
+    let env = EnvBuilder::message([1; 32]).body(b"x".to_vec()).ext(1, vec![]).ext(2, vec![]).build().unwrap();
+    let mut buf = encode(&env).unwrap();
+    let n = buf.len();
+    // Entries are `01 00 02 00`; make the second type repeat the first.
+    buf[n - 2] = 1;
+    assert_eq!(decode(&buf), Err(DecodeError::ExtOrder));
+}
+
+#[test]
+fn truncated_ext_section_fails() {
+    let env = EnvBuilder::message([1; 32]).body(b"x".to_vec()).ext(1, vec![5; 4]).ext(2, vec![]).build().unwrap();
+    let buf = encode(&env).unwrap();
+    for cut in 1..8 {
+        assert!(decode(&buf[..buf.len() - cut]).is_err(), "cut {cut}");
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+use styx_envelope::{encode, Algo, EncodeError, Env, Kind};
+
+fn env(kind: Kind) -> Env {
+    Env {
+        v: 1,
+        kind,
+        algo: Algo::Pmf1,
+        id: [1; 32],
+        chunk: None,
+        to_hash: None,
+        from: None,
+        nonce: None,
+        body: b"body".to_vec(),
+        aad: None,
+        sig: None,
+        ext: BTreeMap::new(),
+        crc: false,
+    }
+}
+
+#[test]
+fn message_requires_body() {
+    let mut e = env(Kind::Message);
+    assert!(e.validate().is_ok());
+    e.body.clear();
+    assert_eq!(encode(&e).unwrap_err(), EncodeError::EmptyMessageBody);
+}
+
+#[test]
+fn reveal_requires_from() {
+    let mut e = env(Kind::Reveal);
+    assert_eq!(encode(&e).unwrap_err(), EncodeError::RevealWithoutFrom);
+    e.from = Some([2; 32]);
+    assert!(encode(&e).is_ok());
+}
+
+#[test]
+fn keybundle_forbids_nonce() {
+    let mut e = env(Kind::Keybundle);
+    assert!(encode(&e).is_ok());
+    e.nonce = Some(vec![0; 24]);
+    assert_eq!(encode(&e).unwrap_err(), EncodeError::KeybundleWithNonce);
+}
+
+#[test]
+fn ack_requires_empty_body() {
+    let mut e = env(Kind::Ack);
+    assert_eq!(encode(&e).unwrap_err(), EncodeError::AckWithBody);
+    e.body.clear();
+    assert!(encode(&e).is_ok());
+}
@@ -0,0 +1,86 @@
+use styx_envelope::{decode, encode, uleb128_decode_u64, DecodeError, Decoder, EnvBuilder, MAX_VARINT_SHIFT};
+
+const HEADER: usize = 41;
+
+/// Keybundle (an empty body is allowed) with only a body: header || uleb128(len) || body.
+fn keybundle(body_len: usize) -> Vec<u8> {
+    let env = EnvBuilder::keybundle([1; 32]).body(vec![7; body_len]).build().unwrap();
+    encode(&env).unwrap()
+}
+
+#[test]
+fn single_zero_byte_is_valid() {
+    let buf = keybundle(0);
+    assert_eq!(&buf[HEADER..], [0x00]);
+    assert!(decode(&buf).unwrap().body.is_empty());
+}
+
+#[test]
+fn overlong_zero_is_rejected() {
+    let buf = [&keybundle(0)[..HEADER], &[0x80, 0x00]].concat();
+    assert_eq!(decode(&buf).unwrap_err(), DecodeError::VarintNonCanonical);
+
+    let mut dec = Decoder::new();
+    dec.push(&buf);
+    assert_eq!(dec.next().unwrap().unwrap_err(), DecodeError::VarintNonCanonical);
+}
+
+#[test]
+fn overlong_nonzero_is_rejected() {
+    // 5 encoded as 0x85 0x80 0x00.
+    let buf = [&keybundle(0)[..HEADER], &[0x85, 0x80, 0x00], &[7; 5]].concat();
+    assert_eq!(decode(&buf).unwrap_err(), DecodeError::VarintNonCanonical);
+}
+
+#[test]
+fn multi_byte_length_is_valid() {
+    let buf = keybundle(200);
+    assert_eq!(&buf[HEADER..HEADER + 2], [0xC8, 0x01]);
+    assert_eq!(decode(&buf).unwrap().body, vec![7; 200]);
+
+    let mut dec = Decoder::new();
+    dec.push(&buf);
+    assert_eq!(dec.next().unwrap().unwrap().body.len(), 200);
+}
+
+#[test]
+fn v1_length_stops_at_max_shift() {
+    // Five bytes reach shift 28 and parse; the length then overruns the buffer.
+    let five = [&keybundle(0)[..HEADER], &[0xff, 0xff, 0xff, 0xff, 0x0f]].concat();
+    assert_eq!(MAX_VARINT_SHIFT, 28);
+    assert_ne!(decode(&five).unwrap_err(), DecodeError::VarintTooLarge);
+
+    // A sixth byte would start at shift 35.
+    let six = [&keybundle(0)[..HEADER], &[0x80, 0x80, 0x80, 0x80, 0x80, 0x01]].concat();
+    assert_eq!(decode(&six).unwrap_err(), DecodeError::VarintTooLarge);
+
+    let mut dec = Decoder::new();
+    dec.push(&six);
+    assert_eq!(dec.next().unwrap().unwrap_err(), DecodeError::VarintTooLarge);
+}
+
+#[test]
+fn u64_variant_covers_full_range() {
+    let max = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01];
+    assert_eq!(uleb128_decode_u64(&max, 0), Ok((u64::MAX, 10)));
+
+    // Past the v1 ceiling: 2^35 needs a sixth byte.
+    let six = [0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+    assert_eq!(uleb128_decode_u64(&six, 0), Ok((1 << 35, 6)));
+
+    assert_eq!(uleb128_decode_u64(&[0xC8, 0x01, 0xAA], 0), Ok((200, 2)));
+    assert_eq!(uleb128_decode_u64(&[0xAA, 0x05], 1), Ok((5, 1)));
+}
+
+#[test]
+fn u64_variant_rejects_overflow_and_overlong() {
+    // Tenth group holds only bit 63.
+    let overflow = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x02];
+    assert_eq!(uleb128_decode_u64(&overflow, 0), Err(DecodeError::VarintTooLarge));
+
+    let eleven = [0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x81, 0x00];
+    assert_eq!(uleb128_decode_u64(&eleven, 0), Err(DecodeError::VarintTooLarge));
+
+    assert_eq!(uleb128_decode_u64(&[0x85, 0x00], 0), Err(DecodeError::VarintNonCanonical));
+    assert_eq!(uleb128_decode_u64(&[0x80, 0x80], 0), Err(DecodeError::Truncated("varint")));
+}
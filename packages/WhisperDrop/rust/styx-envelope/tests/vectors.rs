@@ -1,7 +1,12 @@
+#![cfg(feature = "std")]
+
+use std::collections::BTreeMap;
 use std::fs;
 use serde::Deserialize;
 
-use styx_envelope::{b64url_decode, b64url_encode, decode, encode, Algo, Env, Kind};
+use styx_envelope::{
+    b64url_decode, b64url_decode_32, b64url_encode, decode, encode, encode_into, encoded_len, from_memo, to_memo, Algo, Env, Kind,
+};
 
 #[derive(Debug, Deserialize)]
 struct Vector {
@@ -17,17 +22,19 @@ struct EnvJson {
     kind: String,
     algo: String,
     id: String,
-   #[serde(default)]
-    toHash: Option<String>,
-   #[serde(default)]
+    #[serde(default, rename = "toHash")]
+    to_hash: Option<String>,
+    #[serde(default)]
     from: Option<String>,
-   #[serde(default)]
+    #[serde(default)]
     nonce: Option<String>,
     body: String,
-   #[serde(default)]
+    #[serde(default)]
     aad: Option<String>,
-   #[serde(default)]
+    #[serde(default)]
     sig: Option<String>,
+    #[serde(default)]
+    crc: bool,
 }
 
 fn kind_from_str(s: &str) -> Kind {
@@ -35,6 +42,7 @@ fn kind_from_str(s: &str) -> Kind {
         "message" => Kind::Message,
         "reveal" => Kind::Reveal,
         "keybundle" => Kind::Keybundle,
+        "ack" => Kind::Ack,
         _ => panic!("unknown kind {s}"),
     }
 }
@@ -42,16 +50,13 @@ fn kind_from_str(s: &str) -> Kind {
 fn algo_from_str(s: &str) -> Algo {
     match s {
         "pmf1" => Algo::Pmf1,
+        "pmf2" => Algo::Pmf2,
         _ => panic!("unknown algo {s}"),
     }
 }
 
 fn decode32(s: &str) -> [u8; 32] {
-    let v = b64url_decode(s).expect("b64url");
-    assert_eq!(v.len(), 32, "expected 32 bytes");
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&v);
-    out
+    b64url_decode_32(s).expect("b64url")
 }
 
 #[test]
@@ -59,6 +64,8 @@ fn vectors_match_spec() {
     let text = fs::read_to_string("tests/styx-envelope-v1.json").expect("read vectors");
     let vectors: Vec<Vector> = serde_json::from_str(&text).expect("parse json");
 
+    // Reused across vectors: encode_into must not leak bytes from the previous one.
+    let mut scratch = Vec::new();
     for v in vectors {
         let envj = &v.env;
         let env = Env {
@@ -66,18 +73,26 @@ fn vectors_match_spec() {
             kind: kind_from_str(&envj.kind),
             algo: algo_from_str(&envj.algo),
             id: decode32(&envj.id),
-            to_hash: envj.toHash.as_deref().filter(|s| !s.is_empty()).map(decode32),
+            chunk: None,
+            to_hash: envj.to_hash.as_deref().filter(|s| !s.is_empty()).map(decode32),
             from: envj.from.as_deref().filter(|s| !s.is_empty()).map(decode32),
             nonce: envj.nonce.as_deref().filter(|s| !s.is_empty()).map(|s| b64url_decode(s).unwrap()),
             body: b64url_decode(&envj.body).unwrap(),
             aad: envj.aad.as_deref().filter(|s| !s.is_empty()).map(|s| b64url_decode(s).unwrap()),
             sig: envj.sig.as_deref().filter(|s| !s.is_empty()).map(|s| b64url_decode(s).unwrap()),
+            ext: BTreeMap::new(),
+            crc: envj.crc,
         };
 
         let encoded = encode(&env).expect("encode");
+        encode_into(&env, &mut scratch).expect("encode_into");
+        assert_eq!(scratch, encoded, "{}: encode_into mismatch", v.name);
+        assert_eq!(encoded_len(&env), encoded.len(), "{}: encoded_len", v.name);
         let enc_b64 = b64url_encode(&encoded);
         assert_eq!(enc_b64, v.encoded_b64url, "{}: encoded mismatch", v.name);
         assert_eq!(format!("styx1:{}", v.encoded_b64url), v.memo, "{}: memo mismatch", v.name);
+        assert_eq!(to_memo(&env).expect("to_memo"), v.memo, "{}: to_memo", v.name);
+        assert_eq!(from_memo(&v.memo).expect("from_memo"), env, "{}: from_memo", v.name);
 
         let decoded = decode(&encoded).expect("decode");
         assert_eq!(decoded.v, 1, "{}: version", v.name);
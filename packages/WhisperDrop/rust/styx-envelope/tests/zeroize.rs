@@ -0,0 +1,39 @@
+#![cfg(feature = "zeroize")]
+
+use styx_envelope::EnvBuilder;
+use zeroize::{Zeroize, Zeroizing};
+
+#[test]
+fn zeroize_clears_secret_fields() {
+    let mut env = EnvBuilder::message([1; 32])
+        .to_hash([2; 32])
+        .from([3; 32])
+        .nonce(vec![4; 24])
+        .body(b"plaintext".to_vec())
+        .aad(b"ctx".to_vec())
+        .sig(vec![5; 64])
+        .build()
+        .unwrap();
+    env.zeroize();
+
+    assert_eq!(env.id, [0; 32]);
+    assert_eq!(env.to_hash, None);
+    assert_eq!(env.from, None);
+    assert_eq!(env.nonce, None);
+    assert!(env.body.is_empty());
+    assert_eq!(env.aad, None);
+    assert_eq!(env.sig, Some(vec![5; 64]));
+}
+
+#[test]
+fn zeroizing_wrapper_scrubs_and_fields_still_move_out() {
+    let env = EnvBuilder::message([1; 32]).body(b"plaintext".to_vec()).build().unwrap();
+    // Only compiles while `Env` has no `Drop` impl.
+    let body = env.clone().body;
+    assert_eq!(body, b"plaintext");
+
+    let mut held = Zeroizing::new(env);
+    assert_eq!(held.body, b"plaintext");
+    held.zeroize();
+    assert!(held.body.is_empty());
+}
@@ -0,0 +1,7 @@
+//! wasm-pack entry point; see `styx_envelope::wasm`.
+//!
+//! ```sh
+//! wasm-pack build --target web
+//! ```
+
+pub use styx_envelope::wasm::{decode_envelope, encode_envelope};
@@ -0,0 +1,34 @@
+//! WhisperDrop claim merkle tree, shared by `whisperdrop-escrow` and
+//! `whisperdrop-escrow-lite` so both verifiers hash exactly the same way.
+//!
+//! - leaf = `sha256(0x00 || tag || campaign_id[32] || recipient[32] || allocation_le64 || nonce16)`
+//! - parent = `sha256(0x01 || min(a, b) || max(a, b))`
+//!
+//! The 0x00/0x01 prefixes keep an internal node from being presented as a
+//! leaf. Parents sort their children, so proofs carry no left/right bits.
+//! Each program keeps its own leaf `tag`; changing it changes every root.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+pub const LEAF_PREFIX: u8 = 0x00;
+pub const NODE_PREFIX: u8 = 0x01;
+
+pub fn leaf_hash(tag: &[u8], campaign_id: &[u8; 32], recipient: &Pubkey, allocation: u64, nonce16: &[u8; 16]) -> [u8; 32] {
+    hashv(&[&[LEAF_PREFIX], tag, campaign_id, recipient.as_ref(), &allocation.to_le_bytes(), nonce16]).to_bytes()
+}
+
+/// Order-independent parent of two nodes.
+pub fn parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (lo, hi) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[&[NODE_PREFIX], lo, hi]).to_bytes()
+}
+
+/// Fold `proof` into `leaf`, bottom sibling first.
+pub fn compute_root(leaf: [u8; 32], proof: &[[u8; 32]]) -> [u8; 32] {
+    proof.iter().fold(leaf, |acc, sibling| parent(&acc, sibling))
+}
+
+/// An empty proof is valid only for a single-leaf tree (leaf == root).
+pub fn verify(leaf: [u8; 32], proof: &[[u8; 32]], root: [u8; 32]) -> bool {
+    compute_root(leaf, proof) == root
+}
@@ -0,0 +1,64 @@
+use solana_program::{hash::hash, pubkey::Pubkey};
+use whisperdrop_merkle::{compute_root, leaf_hash, parent, verify};
+
+// The escrow-lite vector: tag "wd:claim:v1", campaign_id [1; 32],
+// leaf A = ([2; 32], 100, [3; 16]), leaf B = ([4; 32], 200, [5; 16]).
+const LEAF_A: &str = "63d3d1c95118068b8cba676f0591fb37b41fa4cd3675912719b8be1ba66e100e";
+const LEAF_B: &str = "e16b9ea8c40879fa7c746d90eab766c83695acd1959a2579c4a4573864d43fd3";
+const ROOT: &str = "798b2d6f41036e8a276bec67c9f07a56aefc2404eb76e036fc5d44856620528a";
+
+fn hex32(s: &str) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+    }
+    out
+}
+
+fn vector_leaf(recipient: u8, allocation: u64, nonce: u8) -> [u8; 32] {
+    leaf_hash(b"wd:claim:v1", &[1; 32], &Pubkey::new_from_array([recipient; 32]), allocation, &[nonce; 16])
+}
+
+#[test]
+fn vector_leaves_and_root() {
+    let (a, b) = (vector_leaf(2, 100, 3), vector_leaf(4, 200, 5));
+    assert_eq!(a, hex32(LEAF_A));
+    assert_eq!(b, hex32(LEAF_B));
+    assert_eq!(parent(&a, &b), hex32(ROOT));
+    assert!(verify(a, &[b], hex32(ROOT)));
+    assert!(verify(b, &[a], hex32(ROOT)));
+}
+
+#[test]
+fn leaf_layout_is_prefixed_and_tagged() {
+    let recipient = Pubkey::new_unique();
+    let mut buf = vec![0x00];
+    buf.extend_from_slice(b"wdleaf1");
+    buf.extend_from_slice(&[9; 32]);
+    buf.extend_from_slice(recipient.as_ref());
+    buf.extend_from_slice(&7u64.to_le_bytes());
+    buf.extend_from_slice(&[8; 16]);
+    assert_eq!(leaf_hash(b"wdleaf1", &[9; 32], &recipient, 7, &[8; 16]), hash(&buf).to_bytes());
+    assert_ne!(leaf_hash(b"wd:claim:v1", &[9; 32], &recipient, 7, &[8; 16]), hash(&buf).to_bytes());
+}
+
+#[test]
+fn parent_is_order_independent_and_not_a_leaf_hash() {
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    assert_eq!(parent(&a, &b), parent(&b, &a));
+    let unprefixed = hash(&[a, b].concat()).to_bytes();
+    assert_ne!(parent(&a, &b), unprefixed);
+}
+
+#[test]
+fn deeper_proofs_fold_bottom_up() {
+    let leaves: Vec<[u8; 32]> = (0..4u8).map(|i| vector_leaf(i, i as u64, i)).collect();
+    let (l, r) = (parent(&leaves[0], &leaves[1]), parent(&leaves[2], &leaves[3]));
+    let root = parent(&l, &r);
+
+    assert_eq!(compute_root(leaves[2], &[leaves[3], l]), root);
+    assert!(verify(leaves[0], &[leaves[1], r], root));
+    assert!(!verify(leaves[0], &[r, leaves[1]], root));
+    assert!(!verify(leaves[0], &[], root));
+    assert!(verify(root, &[], root));
+}
@@ -52,6 +52,12 @@ export function buildRelayInstruction(args: {
   relay: RelayV1Args;
 }): TransactionInstruction {
   const data = encodeRelayV1(args.relay);
+  // Every relay passes the operator's rate-limit config and the payer's counter.
+  const [rateConfig] = PublicKey.findProgramAddressSync([Buffer.from("ratecfg")], args.programId);
+  const [counter] = PublicKey.findProgramAddressSync(
+    [Buffer.from("ratelimit"), args.payer.toBuffer()],
+    args.programId
+  );
 
   return new TransactionInstruction({
     programId: args.programId,
@@ -59,6 +65,8 @@ export function buildRelayInstruction(args: {
       { pubkey: args.payer, isSigner: true, isWritable: true },
       { pubkey: args.treasury, isSigner: false, isWritable: true },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
+      { pubkey: rateConfig, isSigner: false, isWritable: false },
+      { pubkey: counter, isSigner: false, isWritable: true },
     ],
     data,
   });
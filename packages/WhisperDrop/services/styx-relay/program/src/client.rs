@@ -0,0 +1,115 @@
+//! Off-chain helper that relays one envelope with `RelayIx::Relay`
+//! (`client` feature).
+
+use std::fmt;
+use std::time::Duration;
+
+use borsh::to_vec;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_rpc_client_api::client_error::Error as ClientError;
+use solana_sdk::{
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use styx_envelope::{encode, EncodeError, Env};
+
+use crate::{RelayIx, MAX_ENVELOPE_BYTES, SEED_RATE_CONFIG, SEED_RATE_LIMIT};
+
+/// Times `submit_envelope` sends before giving up, each with a fresh blockhash.
+pub const SUBMIT_ATTEMPTS: u32 = 3;
+/// Wait before retry `n` is `n * RETRY_BACKOFF`.
+pub const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+#[derive(Debug)]
+pub enum SubmitError {
+    Encode(EncodeError),
+    /// The encoded envelope is longer than the relay's `MAX_ENVELOPE_BYTES`.
+    TooLarge(usize),
+    Rpc(Box<ClientError>),
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SubmitError::Encode(e) => write!(f, "encode: {e}"),
+            SubmitError::TooLarge(len) => write!(f, "envelope is {len} bytes, relay max is {MAX_ENVELOPE_BYTES}"),
+            SubmitError::Rpc(e) => write!(f, "rpc: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/// `RelayIx::Relay` carrying `encode(env)`, with the accounts the program expects.
+pub fn relay_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    treasury: &Pubkey,
+    fee_lamports: u64,
+    env: &Env,
+) -> Result<Instruction, SubmitError> {
+    let envelope = encode(env).map_err(SubmitError::Encode)?;
+    if envelope.len() > MAX_ENVELOPE_BYTES {
+        return Err(SubmitError::TooLarge(envelope.len()));
+    }
+    let data = to_vec(&RelayIx::Relay { fee_lamports, envelope }).expect("borsh into Vec");
+    let (rate_config, _) = Pubkey::find_program_address(&[SEED_RATE_CONFIG], program_id);
+    let (counter, _) = Pubkey::find_program_address(&[SEED_RATE_LIMIT, payer.as_ref()], program_id);
+    Ok(Instruction::new_with_bytes(
+        *program_id,
+        &data,
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(*treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(rate_config, false),
+            AccountMeta::new(counter, false),
+        ],
+    ))
+}
+
+/// Relay `env` through the program at `program_id`, paying `fee` lamports to
+/// `treasury`, and wait for confirmation.
+///
+/// Transport failures and expired blockhashes are retried up to
+/// [`SUBMIT_ATTEMPTS`] times with a fresh blockhash. A transaction the
+/// program rejects is returned at once; resending it would fail the same way.
+pub async fn submit_envelope(
+    rpc: &RpcClient,
+    program_id: &Pubkey,
+    payer: &Keypair,
+    treasury: &Pubkey,
+    fee: u64,
+    env: &Env,
+) -> Result<Signature, SubmitError> {
+    let ix = relay_instruction(program_id, &payer.pubkey(), treasury, fee, env)?;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let result = match rpc.get_latest_blockhash().await {
+            Ok(blockhash) => {
+                let tx = Transaction::new_signed_with_payer(
+                    std::slice::from_ref(&ix),
+                    Some(&payer.pubkey()),
+                    &[payer],
+                    blockhash,
+                );
+                rpc.send_and_confirm_transaction(&tx).await
+            }
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(signature) => return Ok(signature),
+            Err(e) if e.get_transaction_error().is_some() || attempt >= SUBMIT_ATTEMPTS => {
+                return Err(SubmitError::Rpc(Box::new(e)));
+            }
+            Err(_) => tokio::time::sleep(RETRY_BACKOFF * attempt).await,
+        }
+    }
+}
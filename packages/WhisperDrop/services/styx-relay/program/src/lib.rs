@@ -1,28 +1,193 @@
+#![allow(unexpected_cfgs)]
+
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
     account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    clock::Clock,
     entrypoint,
     entrypoint::ProgramResult,
     msg,
-    program::invoke,
+    program::{invoke, invoke_signed},
     program_error::ProgramError,
+    program_utils::limited_deserialize,
     pubkey::Pubkey,
+    rent::Rent,
     system_instruction,
+    sysvar::Sysvar,
 };
 
+#[cfg(feature = "client")]
+pub mod client;
+
 /// Upper bound to keep transactions affordable and reduce log spam.
 /// (Logs are still public and should contain encrypted bytes.)
-pub const MAX_ENVELOPE_BYTES: usize = 1024;
+/// Defined in `styx_envelope` so `encode_for_relay` checks the same limit.
+pub use styx_envelope::MAX_ENVELOPE_BYTES;
+
+/// Envelopes per `RelayBatch`, and their combined size. A transaction is at
+/// most 1232 bytes, so the byte ceiling is what usually binds.
+pub const MAX_BATCH_ENVELOPES: usize = 8;
+pub const MAX_BATCH_BYTES: usize = MAX_ENVELOPE_BYTES;
+
+/// Seed prefix of the per-topic ring buffer PDA: `["ring", topic]`.
+pub const SEED_RING: &[u8] = b"ring";
+/// Envelopes a ring keeps before the oldest is overwritten.
+pub const RING_SLOTS: usize = 8;
+/// Ring header: `topic[32] | head u32 | filled u32 | seq u64` (little-endian).
+/// `head` is the slot the next envelope goes to, `filled` how many slots hold
+/// data, `seq` the number of envelopes ever stored.
+pub const RING_HEADER_LEN: usize = 32 + 4 + 4 + 8;
+/// One slot: `len u16 | bytes[MAX_ENVELOPE_BYTES]`.
+pub const RING_SLOT_LEN: usize = 2 + MAX_ENVELOPE_BYTES;
+// Slot lengths are stored as `u16`.
+const _: () = assert!(MAX_ENVELOPE_BYTES <= u16::MAX as usize);
+pub const RING_ACCOUNT_LEN: usize = RING_HEADER_LEN + RING_SLOTS * RING_SLOT_LEN;
+
+/// First field of the `sol_log_data` frame carrying a `RelayWithTopic` topic;
+/// the second field is the 32-byte topic. Envelope frames have a single field.
+pub const TOPIC_FRAME_TAG: &[u8] = b"STYX_TOPIC";
+
+/// First field of the `RelayWithTimestamp` frame; then `slot` (u64 LE) and
+/// `unix_timestamp` (i64 LE) from the `Clock` sysvar.
+pub const TIME_FRAME_TAG: &[u8] = b"STYX_TIME";
+
+/// First field of the frame logged right after every envelope frame; the
+/// second field is `sha256(envelope)`, a ready dedup key for indexers.
+pub const HASH_FRAME_TAG: &[u8] = b"STYX_HASH";
+
+/// Seed prefix of a treasury's fee config PDA: `["config", treasury]`.
+pub const SEED_CONFIG: &[u8] = b"config";
+
+/// Seed prefix of a payer's rate-limit counter PDA: `["ratelimit", payer]`.
+pub const SEED_RATE_LIMIT: &[u8] = b"ratelimit";
+/// Seed of the operator's rate-limit config PDA: `["ratecfg"]`. There is one
+/// per deployment, so a payer cannot pick a looser cap by picking a treasury.
+pub const SEED_RATE_CONFIG: &[u8] = b"ratecfg";
+
+/// Operator-tunable fee floor for envelopes relayed to `treasury`.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RelayConfig {
+    pub treasury: Pubkey,
+    pub min_fee_per_byte: u64,
+}
+
+/// Operator cap on every relay: at most `max_per_window` envelopes per payer in
+/// any `window_slots`-slot window. `treasury` is the operator allowed to change it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    pub treasury: Pubkey,
+    pub window_slots: u64,
+    pub max_per_window: u32,
+}
+
+/// Per-payer counter: envelopes relayed since `window_start` (a slot).
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    pub window_start: u64,
+    pub count: u32,
+}
+
+pub const RATE_LIMIT_LEN: usize = 8 + 4;
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
 pub enum RelayIx {
     /// Relay an encrypted envelope and pay a lamports fee to a treasury.
+    /// Every relay variant counts its envelopes against the payer's rate limit
+    /// once the operator has set one with `SetRateLimit`; until then the
+    /// rate-limit accounts are checked but left alone.
     ///
     /// Accounts:
     /// 0. [signer, writable] payer
     /// 1. [writable] treasury
     /// 2. [] system program
+    /// 3. [] rate-limit config PDA `["ratecfg"]`
+    /// 4. [writable] rate-limit PDA `["ratelimit", payer]`
     Relay { fee_lamports: u64, envelope: Vec<u8> },
+    /// Like `Relay`, and also keep the envelope in the topic's ring buffer so
+    /// late-joining clients can page back through recent messages. The payer
+    /// funds the ring's rent the first time a topic is used.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] payer
+    /// 1. [writable] treasury
+    /// 2. [writable] ring PDA `["ring", topic]`
+    /// 3. [] system program
+    /// 4. [] rate-limit config PDA `["ratecfg"]`
+    /// 5. [writable] rate-limit PDA `["ratelimit", payer]`
+    RelayStore { topic: [u8; 32], fee_lamports: u64, envelope: Vec<u8> },
+    /// Create or update the treasury's fee config.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] treasury
+    /// 1. [writable] config PDA `["config", treasury]`
+    /// 2. [] system program
+    SetMinFee { min_fee_per_byte: u64 },
+    /// Like `Relay`, but reject fees below the treasury's
+    /// `min_fee_per_byte * envelope.len()`.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] payer
+    /// 1. [writable] treasury
+    /// 2. [] config PDA `["config", treasury]`
+    /// 3. [] system program
+    /// 4. [] rate-limit config PDA `["ratecfg"]`
+    /// 5. [writable] rate-limit PDA `["ratelimit", payer]`
+    RelayWithMinFee { fee_lamports: u64, envelope: Vec<u8> },
+    /// Like `Relay`, plus a public routing hint (e.g. a salted hash of the
+    /// recipient tag) logged as a `[TOPIC_FRAME_TAG, topic]` frame just before
+    /// the envelope, so scanners can filter before attempting decryption.
+    ///
+    /// Accounts: as `Relay`.
+    RelayWithTopic { fee_lamports: u64, topic: [u8; 32], envelope: Vec<u8> },
+    /// Relay several envelopes for one fee transfer. Each is logged as its own
+    /// frame, as `Relay` would; if any envelope is invalid nothing is charged
+    /// or logged.
+    ///
+    /// Accounts: as `Relay`.
+    RelayBatch { fee_lamports: u64, envelopes: Vec<Vec<u8>> },
+    /// Like `Relay`, but send `fee_lamports * burn_bps / 10_000` (rounded
+    /// down) to a burn/dev account and the rest to the treasury.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] payer
+    /// 1. [writable] treasury
+    /// 2. [writable] burn
+    /// 3. [] system program
+    /// 4. [] rate-limit config PDA `["ratecfg"]`
+    /// 5. [writable] rate-limit PDA `["ratelimit", payer]`
+    RelayWithSplit { fee_lamports: u64, burn_bps: u16, envelope: Vec<u8> },
+    /// Create or update the operator's rate-limit config. The first call must
+    /// be signed by the program's upgrade authority, which becomes the
+    /// config's `treasury`; later calls must be signed by that treasury.
+    /// `window_slots` must be non-zero.
+    ///
+    /// Relays past the cap fail with `RateLimited`. A payer's window opens at
+    /// its first relay after the previous one expired, and the payer funds its
+    /// counter's rent on first use.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] treasury
+    /// 1. [writable] rate-limit config PDA `["ratecfg"]`
+    /// 2. [] system program
+    /// 3. [] this program's ProgramData account (first call only)
+    SetRateLimit { window_slots: u64, max_per_window: u32 },
+    /// Same as `Relay`, with the rate-limit accounts ahead of the system
+    /// program. Kept for callers from before every relay was counted.
+    ///
+    /// Accounts:
+    /// 0. [signer, writable] payer
+    /// 1. [writable] treasury
+    /// 2. [] rate-limit config PDA `["ratecfg"]`
+    /// 3. [writable] rate-limit PDA `["ratelimit", payer]`
+    /// 4. [] system program
+    RelayRateLimited { fee_lamports: u64, envelope: Vec<u8> },
+    /// Like `Relay`, plus a `[TIME_FRAME_TAG, slot, unix_timestamp]` frame
+    /// just before the envelope, so indexers can date it without fetching
+    /// the block. The clock is read via syscall; no sysvar account is needed.
+    ///
+    /// Accounts: as `Relay`.
+    RelayWithTimestamp { fee_lamports: u64, envelope: Vec<u8> },
 }
 
 #[repr(u32)]
@@ -30,6 +195,15 @@ pub enum RelayError {
     EnvelopeTooLarge = 1,
     MissingSignature = 2,
     InvalidSystemProgram = 3,
+    InvalidRing = 4,
+    FeeTooLow = 5,
+    InvalidConfig = 6,
+    NotAnEnvelope = 7,
+    BatchTooLarge = 8,
+    InvalidSplit = 9,
+    RateLimited = 10,
+    InvalidRateLimit = 11,
+    NotOperator = 12,
 }
 
 impl From<RelayError> for ProgramError {
@@ -41,7 +215,7 @@ impl From<RelayError> for ProgramError {
 entrypoint!(process_instruction);
 
 pub fn process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     data: &[u8],
 ) -> ProgramResult {
@@ -51,15 +225,147 @@ pub fn process_instruction(
         RelayIx::Relay {
             fee_lamports,
             envelope,
-        } => relay(accounts, fee_lamports, envelope),
+        } => relay(program_id, accounts, fee_lamports, envelope),
+        RelayIx::RelayStore {
+            topic,
+            fee_lamports,
+            envelope,
+        } => relay_store(program_id, accounts, topic, fee_lamports, envelope),
+        RelayIx::SetMinFee { min_fee_per_byte } => set_min_fee(program_id, accounts, min_fee_per_byte),
+        RelayIx::RelayWithMinFee {
+            fee_lamports,
+            envelope,
+        } => relay_with_min_fee(program_id, accounts, fee_lamports, envelope),
+        RelayIx::RelayWithTopic {
+            fee_lamports,
+            topic,
+            envelope,
+        } => relay_with_topic(program_id, accounts, fee_lamports, topic, envelope),
+        RelayIx::RelayBatch {
+            fee_lamports,
+            envelopes,
+        } => relay_batch(program_id, accounts, fee_lamports, envelopes),
+        RelayIx::RelayWithSplit {
+            fee_lamports,
+            burn_bps,
+            envelope,
+        } => relay_with_split(program_id, accounts, fee_lamports, burn_bps, envelope),
+        RelayIx::SetRateLimit {
+            window_slots,
+            max_per_window,
+        } => set_rate_limit(program_id, accounts, window_slots, max_per_window),
+        RelayIx::RelayRateLimited {
+            fee_lamports,
+            envelope,
+        } => relay_rate_limited(program_id, accounts, fee_lamports, envelope),
+        RelayIx::RelayWithTimestamp {
+            fee_lamports,
+            envelope,
+        } => relay_with_timestamp(program_id, accounts, fee_lamports, envelope),
+    }
+}
+
+fn relay(program_id: &Pubkey, accounts: &[AccountInfo], fee_lamports: u64, envelope: Vec<u8>) -> ProgramResult {
+    check_envelope(&envelope)?;
+    count_relays(program_id, accounts, 1)?;
+    pay_fee(accounts, fee_lamports)?;
+    emit_envelope(&envelope);
+    Ok(())
+}
+
+fn relay_with_topic(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_lamports: u64,
+    topic: [u8; 32],
+    envelope: Vec<u8>,
+) -> ProgramResult {
+    check_envelope(&envelope)?;
+    count_relays(program_id, accounts, 1)?;
+    pay_fee(accounts, fee_lamports)?;
+    // Ahead of the envelope so scanners can skip it without attempting a decrypt.
+    solana_program::log::sol_log_data(&[TOPIC_FRAME_TAG, &topic]);
+    emit_envelope(&envelope);
+    Ok(())
+}
+
+fn relay_with_timestamp(program_id: &Pubkey, accounts: &[AccountInfo], fee_lamports: u64, envelope: Vec<u8>) -> ProgramResult {
+    check_envelope(&envelope)?;
+    let clock = Clock::get()?;
+    count_relays(program_id, accounts, 1)?;
+    pay_fee(accounts, fee_lamports)?;
+    solana_program::log::sol_log_data(&[
+        TIME_FRAME_TAG,
+        &clock.slot.to_le_bytes(),
+        &clock.unix_timestamp.to_le_bytes(),
+    ]);
+    emit_envelope(&envelope);
+    Ok(())
+}
+
+fn relay_batch(program_id: &Pubkey, accounts: &[AccountInfo], fee_lamports: u64, envelopes: Vec<Vec<u8>>) -> ProgramResult {
+    let total: usize = envelopes.iter().map(Vec::len).sum();
+    if envelopes.is_empty() || envelopes.len() > MAX_BATCH_ENVELOPES || total > MAX_BATCH_BYTES {
+        msg!("batch of {} envelopes, {} bytes", envelopes.len(), total);
+        return Err(RelayError::BatchTooLarge.into());
+    }
+    for envelope in &envelopes {
+        check_envelope(envelope)?;
+    }
+
+    // Cannot truncate: at most MAX_BATCH_ENVELOPES.
+    count_relays(program_id, accounts, envelopes.len() as u32)?;
+    pay_fee(accounts, fee_lamports)?;
+    for envelope in &envelopes {
+        emit_envelope(envelope);
+    }
+    Ok(())
+}
+
+fn relay_with_split(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_lamports: u64,
+    burn_bps: u16,
+    envelope: Vec<u8>,
+) -> ProgramResult {
+    if burn_bps > 10_000 {
+        return Err(RelayError::InvalidSplit.into());
     }
+    check_envelope(&envelope)?;
+
+    let mut it = accounts.iter();
+    let payer = next_account_info(&mut it)?;
+    let treasury = next_account_info(&mut it)?;
+    let burn = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+    let rate_config = next_account_info(&mut it)?;
+    let counter = next_account_info(&mut it)?;
+    check_rate_limit(program_id, payer, rate_config, counter, system_program, 1)?;
+
+    // Cannot overflow: burn <= fee_lamports.
+    let burn_lamports = (fee_lamports as u128 * burn_bps as u128 / 10_000) as u64;
+    pay_fee(&[payer.clone(), burn.clone(), system_program.clone()], burn_lamports)?;
+    pay_fee(&[payer.clone(), treasury.clone(), system_program.clone()], fee_lamports - burn_lamports)?;
+
+    emit_envelope(&envelope);
+    Ok(())
 }
 
-fn relay(accounts: &[AccountInfo], fee_lamports: u64, envelope: Vec<u8>) -> ProgramResult {
+fn check_envelope(envelope: &[u8]) -> ProgramResult {
     if envelope.len() > MAX_ENVELOPE_BYTES {
         return Err(RelayError::EnvelopeTooLarge.into());
     }
+    // Header only (magic, version, length); a full decode is not worth the compute.
+    if let Err(e) = styx_envelope::check_header(envelope) {
+        msg!("not an envelope: {}", e);
+        return Err(RelayError::NotAnEnvelope.into());
+    }
+    Ok(())
+}
 
+/// Accounts: `[payer (signer), treasury, system program]`.
+fn pay_fee(accounts: &[AccountInfo], fee_lamports: u64) -> ProgramResult {
     let mut it = accounts.iter();
     let payer = next_account_info(&mut it)?;
     let treasury = next_account_info(&mut it)?;
@@ -77,11 +383,344 @@ fn relay(accounts: &[AccountInfo], fee_lamports: u64, envelope: Vec<u8>) -> Prog
         let transfer_ix = system_instruction::transfer(payer.key, treasury.key, fee_lamports);
         invoke(&transfer_ix, &[payer.clone(), treasury.clone(), system_program.clone()])?;
     }
+    Ok(())
+}
 
+fn emit_envelope(envelope: &[u8]) {
     // Emit the (encrypted) envelope in logs for discovery.
     // Indexers/inbox scanners can parse this program's log frames.
     msg!("STYX_RELAY_V1");
-    solana_program::log::sol_log_data(&[&envelope]);
+    solana_program::log::sol_log_data(&[envelope]);
+    // Separate tagged frame so parsers expecting single-field envelope frames skip it.
+    let digest = solana_program::hash::hash(envelope);
+    solana_program::log::sol_log_data(&[HASH_FRAME_TAG, digest.as_ref()]);
+}
 
+fn relay_store(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    topic: [u8; 32],
+    fee_lamports: u64,
+    envelope: Vec<u8>,
+) -> ProgramResult {
+    if envelope.len() > MAX_ENVELOPE_BYTES {
+        return Err(RelayError::EnvelopeTooLarge.into());
+    }
+
+    let mut it = accounts.iter();
+    let payer = next_account_info(&mut it)?;
+    let treasury = next_account_info(&mut it)?;
+    let ring = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+    let rate_config = next_account_info(&mut it)?;
+    let counter = next_account_info(&mut it)?;
+
+    let (ring_pda, bump) = Pubkey::find_program_address(&[SEED_RING, &topic], program_id);
+    if *ring.key != ring_pda {
+        return Err(RelayError::InvalidRing.into());
+    }
+
+    relay(
+        program_id,
+        &[payer.clone(), treasury.clone(), system_program.clone(), rate_config.clone(), counter.clone()],
+        fee_lamports,
+        envelope.clone(),
+    )?;
+
+    if ring.data_is_empty() {
+        create_pda_account(program_id, payer, ring, system_program, RING_ACCOUNT_LEN, &[SEED_RING, &topic, &[bump]])?;
+        ring.data.borrow_mut()[..32].copy_from_slice(&topic);
+    } else if ring.owner != program_id || ring.data_len() != RING_ACCOUNT_LEN {
+        return Err(RelayError::InvalidRing.into());
+    }
+
+    let mut data = ring.data.borrow_mut();
+    let head = u32::from_le_bytes(data[32..36].try_into().unwrap()) as usize;
+    let filled = u32::from_le_bytes(data[36..40].try_into().unwrap()) as usize;
+    let seq = u64::from_le_bytes(data[40..48].try_into().unwrap());
+    if head >= RING_SLOTS {
+        return Err(RelayError::InvalidRing.into());
+    }
+
+    let slot = &mut data[RING_HEADER_LEN + head * RING_SLOT_LEN..][..RING_SLOT_LEN];
+    slot.fill(0);
+    slot[..2].copy_from_slice(&(envelope.len() as u16).to_le_bytes());
+    slot[2..2 + envelope.len()].copy_from_slice(&envelope);
+
+    let next_head = (head + 1) % RING_SLOTS;
+    let filled = (filled + 1).min(RING_SLOTS);
+    data[32..36].copy_from_slice(&(next_head as u32).to_le_bytes());
+    data[36..40].copy_from_slice(&(filled as u32).to_le_bytes());
+    data[40..48].copy_from_slice(&(seq + 1).to_le_bytes());
+
+    Ok(())
+}
+
+fn set_min_fee(program_id: &Pubkey, accounts: &[AccountInfo], min_fee_per_byte: u64) -> ProgramResult {
+    let mut it = accounts.iter();
+    let treasury = next_account_info(&mut it)?;
+    let config = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+
+    if !treasury.is_signer {
+        return Err(RelayError::MissingSignature.into());
+    }
+
+    let (config_pda, bump) = Pubkey::find_program_address(&[SEED_CONFIG, treasury.key.as_ref()], program_id);
+    if *config.key != config_pda {
+        return Err(RelayError::InvalidConfig.into());
+    }
+
+    let state = RelayConfig { treasury: *treasury.key, min_fee_per_byte };
+    if !config.data_is_empty() && config.owner != program_id {
+        return Err(RelayError::InvalidConfig.into());
+    }
+    store_pda(
+        program_id,
+        treasury,
+        config,
+        system_program,
+        &[SEED_CONFIG, treasury.key.as_ref(), &[bump]],
+        &borsh::to_vec(&state)?,
+    )
+}
+
+/// Write `bytes` to a program-owned PDA, first creating it funded by `payer`
+/// if it does not exist yet. Callers check the PDA address and, for an
+/// existing account, its owner.
+fn store_pda<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    pda: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    signer_seeds: &[&[u8]],
+    bytes: &[u8],
+) -> ProgramResult {
+    if pda.data_is_empty() {
+        if *system_program.key != solana_program::system_program::id() {
+            return Err(RelayError::InvalidSystemProgram.into());
+        }
+        create_pda_account(program_id, payer, pda, system_program, bytes.len(), signer_seeds)?;
+    }
+    pda.data.borrow_mut()[..bytes.len()].copy_from_slice(bytes);
     Ok(())
 }
+
+/// Create `target` as a `space`-byte account owned by this program. The PDA
+/// addresses are predictable, so anyone can send lamports there first and make
+/// `create_account` fail; a pre-funded account is topped up to rent-exempt,
+/// then allocated and assigned instead.
+fn create_pda_account<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    target: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let lamports = Rent::get()?.minimum_balance(space);
+    if target.lamports() == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(payer.key, target.key, lamports, space as u64, program_id),
+            &[payer.clone(), target.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    let top_up = lamports.saturating_sub(target.lamports());
+    if top_up > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, target.key, top_up),
+            &[payer.clone(), target.clone(), system_program.clone()],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, program_id),
+        &[target.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
+
+fn set_rate_limit(program_id: &Pubkey, accounts: &[AccountInfo], window_slots: u64, max_per_window: u32) -> ProgramResult {
+    let mut it = accounts.iter();
+    let treasury = next_account_info(&mut it)?;
+    let config = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+
+    if !treasury.is_signer {
+        return Err(RelayError::MissingSignature.into());
+    }
+    if window_slots == 0 {
+        return Err(RelayError::InvalidRateLimit.into());
+    }
+
+    let (config_pda, bump) = Pubkey::find_program_address(&[SEED_RATE_CONFIG], program_id);
+    if *config.key != config_pda {
+        return Err(RelayError::InvalidRateLimit.into());
+    }
+    let operator = if config.data_is_empty() {
+        upgrade_authority(program_id, next_account_info(&mut it)?)?
+    } else if config.owner != program_id {
+        return Err(RelayError::InvalidRateLimit.into());
+    } else {
+        let current = RateLimitConfig::try_from_slice(&config.data.borrow())
+            .map_err(|_| ProgramError::from(RelayError::InvalidRateLimit))?;
+        Some(current.treasury)
+    };
+    if operator != Some(*treasury.key) {
+        msg!("{} is not the relay operator", treasury.key);
+        return Err(RelayError::NotOperator.into());
+    }
+
+    let state = RateLimitConfig { treasury: *treasury.key, window_slots, max_per_window };
+    store_pda(
+        program_id,
+        treasury,
+        config,
+        system_program,
+        &[SEED_RATE_CONFIG, &[bump]],
+        &borsh::to_vec(&state)?,
+    )
+}
+
+/// Upgrade authority recorded in this program's ProgramData account.
+fn upgrade_authority(program_id: &Pubkey, program_data: &AccountInfo) -> Result<Option<Pubkey>, ProgramError> {
+    let (expected, _bump) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    if *program_data.key != expected || *program_data.owner != bpf_loader_upgradeable::id() {
+        return Err(RelayError::NotOperator.into());
+    }
+    let limit = UpgradeableLoaderState::size_of_programdata_metadata() as u64;
+    match limited_deserialize(&program_data.data.borrow(), limit) {
+        Ok(UpgradeableLoaderState::ProgramData { upgrade_authority_address, .. }) => Ok(upgrade_authority_address),
+        _ => Err(RelayError::NotOperator.into()),
+    }
+}
+
+/// `check_rate_limit` for the `Relay` account layout.
+fn count_relays(program_id: &Pubkey, accounts: &[AccountInfo], envelopes: u32) -> ProgramResult {
+    let mut it = accounts.iter();
+    let payer = next_account_info(&mut it)?;
+    let _treasury = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+    let config = next_account_info(&mut it)?;
+    let counter = next_account_info(&mut it)?;
+    check_rate_limit(program_id, payer, config, counter, system_program, envelopes)
+}
+
+/// Count `envelopes` against the payer's counter, failing with `RateLimited`
+/// past the operator's cap. Does nothing until the operator config exists.
+fn check_rate_limit<'a>(
+    program_id: &Pubkey,
+    payer: &AccountInfo<'a>,
+    config: &AccountInfo<'a>,
+    counter: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    envelopes: u32,
+) -> ProgramResult {
+    let (config_pda, _bump) = Pubkey::find_program_address(&[SEED_RATE_CONFIG], program_id);
+    if *config.key != config_pda {
+        return Err(RelayError::InvalidRateLimit.into());
+    }
+    if config.data_is_empty() {
+        return Ok(());
+    }
+    if config.owner != program_id {
+        return Err(RelayError::InvalidRateLimit.into());
+    }
+    let limits = RateLimitConfig::try_from_slice(&config.data.borrow())
+        .map_err(|_| ProgramError::from(RelayError::InvalidRateLimit))?;
+
+    if !payer.is_signer {
+        return Err(RelayError::MissingSignature.into());
+    }
+    let (counter_pda, bump) = Pubkey::find_program_address(&[SEED_RATE_LIMIT, payer.key.as_ref()], program_id);
+    if *counter.key != counter_pda {
+        return Err(RelayError::InvalidRateLimit.into());
+    }
+    let slot = Clock::get()?.slot;
+    let mut state = if counter.data_is_empty() {
+        RateLimit { window_start: slot, count: 0 }
+    } else if counter.owner != program_id || counter.data_len() != RATE_LIMIT_LEN {
+        return Err(RelayError::InvalidRateLimit.into());
+    } else {
+        RateLimit::try_from_slice(&counter.data.borrow())?
+    };
+
+    if slot.saturating_sub(state.window_start) >= limits.window_slots {
+        state = RateLimit { window_start: slot, count: 0 };
+    }
+    if state.count.saturating_add(envelopes) > limits.max_per_window {
+        msg!("rate limited: {} envelopes since slot {}", state.count, state.window_start);
+        return Err(RelayError::RateLimited.into());
+    }
+    state.count += envelopes;
+    store_pda(
+        program_id,
+        payer,
+        counter,
+        system_program,
+        &[SEED_RATE_LIMIT, payer.key.as_ref(), &[bump]],
+        &borsh::to_vec(&state)?,
+    )
+}
+
+fn relay_rate_limited(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_lamports: u64,
+    envelope: Vec<u8>,
+) -> ProgramResult {
+    let mut it = accounts.iter();
+    let payer = next_account_info(&mut it)?;
+    let treasury = next_account_info(&mut it)?;
+    let config = next_account_info(&mut it)?;
+    let counter = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+
+    relay(
+        program_id,
+        &[payer.clone(), treasury.clone(), system_program.clone(), config.clone(), counter.clone()],
+        fee_lamports,
+        envelope,
+    )
+}
+
+fn relay_with_min_fee(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    fee_lamports: u64,
+    envelope: Vec<u8>,
+) -> ProgramResult {
+    let mut it = accounts.iter();
+    let payer = next_account_info(&mut it)?;
+    let treasury = next_account_info(&mut it)?;
+    let config = next_account_info(&mut it)?;
+    let system_program = next_account_info(&mut it)?;
+    let rate_config = next_account_info(&mut it)?;
+    let counter = next_account_info(&mut it)?;
+
+    let (config_pda, _bump) = Pubkey::find_program_address(&[SEED_CONFIG, treasury.key.as_ref()], program_id);
+    if *config.key != config_pda || config.owner != program_id {
+        return Err(RelayError::InvalidConfig.into());
+    }
+    let state = RelayConfig::try_from_slice(&config.data.borrow())
+        .map_err(|_| ProgramError::from(RelayError::InvalidConfig))?;
+
+    let min_fee = state.min_fee_per_byte.saturating_mul(envelope.len() as u64);
+    if fee_lamports < min_fee {
+        msg!("fee {} below minimum {}", fee_lamports, min_fee);
+        return Err(RelayError::FeeTooLow.into());
+    }
+
+    relay(
+        program_id,
+        &[payer.clone(), treasury.clone(), system_program.clone(), rate_config.clone(), counter.clone()],
+        fee_lamports,
+        envelope,
+    )
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use styx_relay_program::{RelayIx, MAX_BATCH_ENVELOPES, MAX_ENVELOPE_BYTES};
+
+fn batch(envelopes: Vec<Vec<u8>>) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    common::run(&program_id, &mut common::relay_accounts(&program_id), &RelayIx::RelayBatch { fee_lamports: 10_000, envelopes })
+}
+
+#[test]
+fn batch_pays_once_and_logs_each_envelope() {
+    let envelopes: Vec<Vec<u8>> = (1..=3).map(|i| common::envelope(i, 20)).collect();
+    batch(envelopes.clone()).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(invokes[0].program_id, system_program::id());
+    assert_eq!(common::take_data(), envelopes.iter().flat_map(|e| common::relayed(e)).collect::<Vec<_>>());
+}
+
+#[test]
+fn one_bad_envelope_fails_the_whole_batch() {
+    let mut envelopes: Vec<Vec<u8>> = (1..=3).map(|i| common::envelope(i, 20)).collect();
+    envelopes[2][0] = b'X';
+    assert_eq!(batch(envelopes), Err(ProgramError::Custom(7)));
+    assert!(common::take_invokes().is_empty());
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn oversized_member_fails_the_whole_batch() {
+    let envelopes = vec![common::envelope(1, 20), common::envelope(2, MAX_ENVELOPE_BYTES)];
+    assert!(batch(envelopes).is_err());
+    assert!(common::take_invokes().is_empty());
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn batch_limits_are_enforced() {
+    let too_many: Vec<Vec<u8>> = (0..=MAX_BATCH_ENVELOPES as u8).map(|i| common::envelope(i, 1)).collect();
+    assert_eq!(batch(too_many), Err(ProgramError::Custom(8)));
+
+    // Each envelope is within MAX_ENVELOPE_BYTES, but together they exceed the batch ceiling.
+    let too_big = vec![common::envelope(1, 600), common::envelope(2, 600)];
+    assert_eq!(batch(too_big), Err(ProgramError::Custom(8)));
+
+    assert_eq!(batch(Vec::new()), Err(ProgramError::Custom(8)));
+    assert!(common::take_invokes().is_empty());
+}
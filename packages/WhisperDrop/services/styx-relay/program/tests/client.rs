@@ -0,0 +1,81 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+use borsh::BorshDeserialize;
+use common::TestAccount;
+use solana_program::{instruction::AccountMeta, pubkey::Pubkey, system_program};
+use solana_rpc_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+use styx_envelope::{encode, Algo, Env, Kind};
+use styx_relay_program::{
+    client::{relay_instruction, submit_envelope, SubmitError},
+    RelayIx, MAX_ENVELOPE_BYTES, SEED_RATE_CONFIG, SEED_RATE_LIMIT,
+};
+
+fn env(body_len: usize) -> Env {
+    Env::with_derived_id(Kind::Message, Algo::Pmf1, vec![0xab; body_len], None)
+}
+
+#[test]
+fn relay_instruction_is_accepted_by_the_program() {
+    let (program_id, payer, treasury) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let env = env(64);
+    let ix = relay_instruction(&program_id, &payer, &treasury, 5_000, &env).unwrap();
+
+    assert_eq!(ix.program_id, program_id);
+    assert_eq!(
+        ix.accounts,
+        vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(treasury, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(Pubkey::find_program_address(&[SEED_RATE_CONFIG], &program_id).0, false),
+            AccountMeta::new(Pubkey::find_program_address(&[SEED_RATE_LIMIT, payer.as_ref()], &program_id).0, false),
+        ]
+    );
+    let decoded = RelayIx::try_from_slice(&ix.data).unwrap();
+    let RelayIx::Relay { fee_lamports, envelope } = decoded.clone() else { panic!("not Relay") };
+    assert_eq!((fee_lamports, envelope.clone()), (5_000, encode(&env).unwrap()));
+
+    let mut accounts = vec![TestAccount::signer(payer), TestAccount::new(treasury), TestAccount::new(system_program::id())];
+    accounts.extend(common::rate_limit_accounts(&program_id, &payer));
+    common::run(&program_id, &mut accounts, &decoded).unwrap();
+    assert_eq!(common::take_data(), common::relayed(&envelope));
+}
+
+#[test]
+fn oversized_envelope_is_rejected_before_sending() {
+    let err = relay_instruction(
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        &Pubkey::new_unique(),
+        0,
+        &env(MAX_ENVELOPE_BYTES),
+    )
+    .unwrap_err();
+    assert!(matches!(err, SubmitError::TooLarge(len) if len > MAX_ENVELOPE_BYTES));
+}
+
+#[tokio::test]
+async fn submit_returns_the_payer_signature() {
+    let rpc = RpcClient::new_mock("succeeds".to_string());
+    let (program_id, treasury, payer) = (Pubkey::new_unique(), Pubkey::new_unique(), Keypair::new());
+    let signature = submit_envelope(&rpc, &program_id, &payer, &treasury, 5_000, &env(64)).await.unwrap();
+
+    // The mock hands out a fixed blockhash and ed25519 signing is deterministic.
+    let ix = relay_instruction(&program_id, &payer.pubkey(), &treasury, 5_000, &env(64)).unwrap();
+    let blockhash = rpc.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    assert_eq!(signature, tx.signatures[0]);
+}
+
+#[tokio::test]
+async fn program_errors_are_returned_without_retrying() {
+    let rpc = RpcClient::new_mock("instruction_error".to_string());
+    let err = submit_envelope(&rpc, &Pubkey::new_unique(), &Keypair::new(), &Pubkey::new_unique(), 5_000, &env(64))
+        .await
+        .unwrap_err();
+    let SubmitError::Rpc(e) = err else { panic!("expected rpc error") };
+    assert!(e.get_transaction_error().is_some());
+}
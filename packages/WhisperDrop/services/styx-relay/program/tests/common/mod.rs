@@ -0,0 +1,178 @@
+//! Host-side harness: captures `sol_log_data` frames and CPIs per test thread.
+//!
+//! System `create_account`, `allocate` and `assign` CPIs are applied to the
+//! target `AccountInfo` (lamports, zeroed data, owner) so the program can write
+//! the new account.
+
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::sync::Once;
+
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+};
+use sha2::{Digest, Sha256};
+use styx_relay_program::{process_instruction, RelayIx, HASH_FRAME_TAG, SEED_RATE_CONFIG, SEED_RATE_LIMIT};
+
+thread_local! {
+    static INVOKES: RefCell<Vec<Instruction>> = const { RefCell::new(Vec::new()) };
+    static DATA: RefCell<Vec<Vec<Vec<u8>>>> = const { RefCell::new(Vec::new()) };
+    static CLOCK: RefCell<Clock> = RefCell::new(Clock::default());
+}
+
+struct HostStubs;
+
+impl SyscallStubs for HostStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        DATA.with(|d| d.borrow_mut().push(fields.iter().map(|f| f.to_vec()).collect()));
+    }
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id == system_program::id() && instruction.data[..4] == [0, 0, 0, 0] {
+            // SystemInstruction::CreateAccount { lamports, space, owner }
+            let lamports = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap());
+            let space = u64::from_le_bytes(instruction.data[12..20].try_into().unwrap()) as usize;
+            let owner = Pubkey::new_from_array(instruction.data[20..52].try_into().unwrap());
+            let target = &instruction.accounts[1].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("created account passed");
+            **ai.lamports.borrow_mut() = lamports;
+            *ai.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+            ai.assign(&owner);
+        } else if instruction.program_id == system_program::id() && instruction.data[..4] == [8, 0, 0, 0] {
+            // SystemInstruction::Allocate { space }
+            let space = u64::from_le_bytes(instruction.data[4..12].try_into().unwrap()) as usize;
+            let target = &instruction.accounts[0].pubkey;
+            let ai = account_infos.iter().find(|a| a.key == target).expect("allocated account passed");
+            *ai.data.borrow_mut() = Box::leak(vec![0u8; space].into_boxed_slice());
+        } else if instruction.program_id == system_program::id() && instruction.data[..4] == [1, 0, 0, 0] {
+            // SystemInstruction::Assign { owner }
+            let owner = Pubkey::new_from_array(instruction.data[4..36].try_into().unwrap());
+            let target = &instruction.accounts[0].pubkey;
+            account_infos.iter().find(|a| a.key == target).expect("assigned account passed").assign(&owner);
+        }
+        INVOKES.with(|i| i.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Clock) = CLOCK.with(|c| c.borrow().clone()) };
+        SUCCESS
+    }
+}
+
+/// Install the stubs (once per process) and clear recorded CPIs and logs.
+pub fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        set_syscall_stubs(Box::new(HostStubs));
+    });
+    INVOKES.with(|i| i.borrow_mut().clear());
+    DATA.with(|d| d.borrow_mut().clear());
+}
+
+/// Set the slot `Clock::get` reports on this thread.
+pub fn set_slot(slot: u64) {
+    CLOCK.with(|c| c.borrow_mut().slot = slot);
+}
+
+/// Set the unix timestamp `Clock::get` reports on this thread.
+pub fn set_unix_timestamp(unix_timestamp: i64) {
+    CLOCK.with(|c| c.borrow_mut().unix_timestamp = unix_timestamp);
+}
+
+/// Drain the CPIs recorded on this thread.
+pub fn take_invokes() -> Vec<Instruction> {
+    INVOKES.with(|i| std::mem::take(&mut *i.borrow_mut()))
+}
+
+/// Drain the `sol_log_data` frames recorded on this thread.
+pub fn take_data() -> Vec<Vec<Vec<u8>>> {
+    DATA.with(|d| std::mem::take(&mut *d.borrow_mut()))
+}
+
+/// Account storage for one `AccountInfo`; `info` borrows it for a test's duration.
+#[derive(Clone)]
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+}
+
+impl TestAccount {
+    pub fn new(key: Pubkey) -> Self {
+        Self { key, owner: system_program::id(), lamports: 0, data: Vec::new(), is_signer: false }
+    }
+
+    pub fn signer(key: Pubkey) -> Self {
+        Self { is_signer: true, lamports: 1_000_000_000, ..Self::new(key) }
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(&self.key, self.is_signer, true, &mut self.lamports, &mut self.data, &self.owner, false, 0)
+    }
+}
+
+/// `[rate-limit config, payer's counter]`, the trailing accounts of every relay.
+pub fn rate_limit_accounts(program_id: &Pubkey, payer: &Pubkey) -> [TestAccount; 2] {
+    let (config, _) = Pubkey::find_program_address(&[SEED_RATE_CONFIG], program_id);
+    let (counter, _) = Pubkey::find_program_address(&[SEED_RATE_LIMIT, payer.as_ref()], program_id);
+    [TestAccount::new(config), TestAccount::new(counter)]
+}
+
+/// `[payer, treasury, system, rate-limit config, counter]` for the `Relay` layout.
+pub fn relay_accounts(program_id: &Pubkey) -> Vec<TestAccount> {
+    let payer = TestAccount::signer(Pubkey::new_unique());
+    let mut accounts = vec![TestAccount::new(Pubkey::new_unique()), TestAccount::new(system_program::id())];
+    accounts.extend(rate_limit_accounts(program_id, &payer.key));
+    accounts.insert(0, payer);
+    accounts
+}
+
+/// Run `ix`; data and owner changes the program makes land back in `accounts`.
+pub fn run(program_id: &Pubkey, accounts: &mut [TestAccount], ix: &RelayIx) -> Result<(), ProgramError> {
+    let infos: Vec<_> = accounts.iter_mut().map(TestAccount::info).collect();
+    setup();
+    let result = process_instruction(program_id, &infos, &borsh::to_vec(ix).unwrap());
+    let owners: Vec<Pubkey> = infos.iter().map(|i| *i.owner).collect();
+    let data: Vec<Vec<u8>> = infos.iter().map(|i| i.data.borrow().to_vec()).collect();
+    drop(infos);
+    for ((account, owner), data) in accounts.iter_mut().zip(owners).zip(data) {
+        account.owner = owner;
+        account.data = data;
+    }
+    result
+}
+
+/// An encoded v1 message envelope whose body is `body_len` bytes of `fill`.
+pub fn envelope(fill: u8, body_len: usize) -> Vec<u8> {
+    let env = styx_envelope::EnvBuilder::message([fill; 32]).body(vec![fill; body_len]).build().unwrap();
+    styx_envelope::encode(&env).unwrap()
+}
+
+/// The frames one relayed `envelope` logs: the envelope, then its hash frame.
+pub fn relayed(envelope: &[u8]) -> Vec<Vec<Vec<u8>>> {
+    vec![vec![envelope.to_vec()], vec![HASH_FRAME_TAG.to_vec(), Sha256::digest(envelope).to_vec()]]
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_relay_program::RelayIx;
+
+fn relay(envelope: Vec<u8>) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    common::run(&program_id, &mut common::relay_accounts(&program_id), &RelayIx::Relay { fee_lamports: 5_000, envelope })
+}
+
+#[test]
+fn valid_envelope_is_relayed() {
+    let envelope = common::envelope(1, 32);
+    relay(envelope.clone()).unwrap();
+    assert_eq!(common::take_invokes().len(), 1);
+    assert_eq!(common::take_data(), common::relayed(&envelope));
+}
+
+#[test]
+fn wrong_magic_is_rejected_without_charging() {
+    let mut envelope = common::envelope(1, 32);
+    envelope[..4].copy_from_slice(b"JUNK");
+    assert_eq!(relay(envelope), Err(ProgramError::Custom(7)));
+    assert!(common::take_invokes().is_empty());
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn too_short_blob_is_rejected() {
+    let envelope = common::envelope(1, 32);
+    assert_eq!(relay(envelope[..styx_envelope::STYX_HEADER_LEN - 1].to_vec()), Err(ProgramError::Custom(7)));
+    assert_eq!(relay(Vec::new()), Err(ProgramError::Custom(7)));
+}
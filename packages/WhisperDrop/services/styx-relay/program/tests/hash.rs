@@ -0,0 +1,39 @@
+mod common;
+
+use sha2::{Digest, Sha256};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_relay_program::{RelayIx, HASH_FRAME_TAG};
+
+fn run(ix: RelayIx) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    common::run(&program_id, &mut common::relay_accounts(&program_id), &ix)
+}
+
+#[test]
+fn hash_frame_follows_envelope_and_matches_sha256() {
+    let envelope = common::envelope(3, 40);
+    run(RelayIx::Relay { fee_lamports: 1, envelope: envelope.clone() }).unwrap();
+
+    let frames = common::take_data();
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0], vec![envelope.clone()]);
+    assert_eq!(frames[1][0], HASH_FRAME_TAG);
+    assert_eq!(frames[1][1], Sha256::digest(&envelope).to_vec());
+}
+
+#[test]
+fn each_batched_envelope_gets_its_own_hash() {
+    let envelopes = vec![common::envelope(1, 8), common::envelope(2, 8)];
+    run(RelayIx::RelayBatch { fee_lamports: 1, envelopes: envelopes.clone() }).unwrap();
+
+    let hashes: Vec<_> = common::take_data().into_iter().filter(|f| f[0] == HASH_FRAME_TAG).map(|f| f[1].clone()).collect();
+    let expected: Vec<_> = envelopes.iter().map(|e| Sha256::digest(e).to_vec()).collect();
+    assert_eq!(hashes, expected);
+    assert_ne!(hashes[0], hashes[1]);
+}
+
+#[test]
+fn rejected_envelope_logs_no_hash() {
+    assert_eq!(run(RelayIx::Relay { fee_lamports: 1, envelope: b"junk".to_vec() }), Err(ProgramError::Custom(7)));
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::TestAccount;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use styx_relay_program::{RelayConfig, RelayIx, SEED_CONFIG};
+
+const MIN_FEE_PER_BYTE: u64 = 10;
+
+/// `[payer, treasury, config, system, rate-limit config, counter]` with the
+/// treasury's config set to `MIN_FEE_PER_BYTE`.
+fn configured(program_id: &Pubkey) -> Vec<TestAccount> {
+    let treasury = TestAccount::signer(Pubkey::new_unique());
+    let (config, _) = Pubkey::find_program_address(&[SEED_CONFIG, treasury.key.as_ref()], program_id);
+    let mut accounts = vec![treasury, TestAccount::new(config), TestAccount::new(system_program::id())];
+    common::run(program_id, &mut accounts, &RelayIx::SetMinFee { min_fee_per_byte: MIN_FEE_PER_BYTE }).unwrap();
+    common::take_invokes();
+
+    let payer = TestAccount::signer(Pubkey::new_unique());
+    accounts.extend(common::rate_limit_accounts(program_id, &payer.key));
+    accounts.insert(0, payer);
+    accounts
+}
+
+fn relay(program_id: &Pubkey, accounts: &mut [TestAccount], fee_lamports: u64, envelope: Vec<u8>) -> Result<(), ProgramError> {
+    common::run(program_id, accounts, &RelayIx::RelayWithMinFee { fee_lamports, envelope })
+}
+
+#[test]
+fn set_min_fee_creates_config() {
+    let program_id = Pubkey::new_unique();
+    let accounts = configured(&program_id);
+    assert_eq!(accounts[2].owner, program_id);
+    assert_eq!(
+        RelayConfig::try_from_slice(&accounts[2].data).unwrap(),
+        RelayConfig { treasury: accounts[1].key, min_fee_per_byte: MIN_FEE_PER_BYTE }
+    );
+}
+
+#[test]
+fn fee_at_exact_minimum_is_accepted() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    let envelope = common::envelope(1, 100);
+    let fee = envelope.len() as u64 * MIN_FEE_PER_BYTE;
+    relay(&program_id, &mut accounts, fee, envelope.clone()).unwrap();
+    assert_eq!(common::take_invokes().len(), 1);
+    assert_eq!(common::take_data(), common::relayed(&envelope));
+}
+
+#[test]
+fn fee_one_below_minimum_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    let envelope = common::envelope(1, 100);
+    let fee = envelope.len() as u64 * MIN_FEE_PER_BYTE;
+    assert_eq!(relay(&program_id, &mut accounts, fee - 1, envelope), Err(ProgramError::Custom(5)));
+    assert!(common::take_invokes().is_empty());
+    assert!(common::take_data().is_empty());
+}
+
+#[test]
+fn config_of_another_treasury_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    accounts[1] = TestAccount::new(Pubkey::new_unique());
+    assert_eq!(relay(&program_id, &mut accounts, 1_000_000, common::envelope(1, 100)), Err(ProgramError::Custom(6)));
+}
+
+#[test]
+fn only_treasury_signer_sets_config() {
+    let program_id = Pubkey::new_unique();
+    let treasury = TestAccount::new(Pubkey::new_unique());
+    let (config, _) = Pubkey::find_program_address(&[SEED_CONFIG, treasury.key.as_ref()], &program_id);
+    let mut accounts = vec![treasury, TestAccount::new(config), TestAccount::new(system_program::id())];
+    assert_eq!(
+        common::run(&program_id, &mut accounts, &RelayIx::SetMinFee { min_fee_per_byte: 0 }),
+        Err(ProgramError::Custom(2))
+    );
+}
+
+#[test]
+fn pre_funded_config_is_still_created() {
+    let program_id = Pubkey::new_unique();
+    let treasury = TestAccount::signer(Pubkey::new_unique());
+    let (config, _) = Pubkey::find_program_address(&[SEED_CONFIG, treasury.key.as_ref()], &program_id);
+    // Someone sent lamports to the predictable PDA ahead of the operator.
+    let mut griefed = TestAccount::new(config);
+    griefed.lamports = 1_000;
+    let mut accounts = vec![treasury, griefed, TestAccount::new(system_program::id())];
+    common::run(&program_id, &mut accounts, &RelayIx::SetMinFee { min_fee_per_byte: MIN_FEE_PER_BYTE }).unwrap();
+
+    let kinds: Vec<u8> = common::take_invokes().iter().map(|ix| ix.data[0]).collect();
+    // Rent top-up, allocate, assign.
+    assert_eq!(kinds, [2, 8, 1]);
+    assert_eq!(accounts[1].owner, program_id);
+    assert_eq!(
+        RelayConfig::try_from_slice(&accounts[1].data).unwrap(),
+        RelayConfig { treasury: accounts[0].key, min_fee_per_byte: MIN_FEE_PER_BYTE }
+    );
+}
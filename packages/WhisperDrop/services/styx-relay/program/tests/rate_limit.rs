@@ -0,0 +1,223 @@
+mod common;
+
+use borsh::BorshDeserialize;
+use common::TestAccount;
+use solana_program::{bpf_loader_upgradeable, program_error::ProgramError, pubkey::Pubkey, system_program};
+use styx_relay_program::{RateLimit, RateLimitConfig, RelayIx, SEED_RATE_CONFIG};
+
+const WINDOW: u64 = 10;
+const CAP: u32 = 2;
+
+/// This program's ProgramData account, naming `authority` as upgrade authority.
+fn program_data(program_id: &Pubkey, authority: &Pubkey) -> TestAccount {
+    let (key, _) = Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id());
+    // UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }
+    let mut data = [&3u32.to_le_bytes()[..], &0u64.to_le_bytes(), &[1]].concat();
+    data.extend_from_slice(authority.as_ref());
+    TestAccount { owner: bpf_loader_upgradeable::id(), data, ..TestAccount::new(key) }
+}
+
+/// `[operator, config, system, program data]`, with `operator` as upgrade authority.
+fn set_rate_limit_accounts(program_id: &Pubkey) -> Vec<TestAccount> {
+    let operator = TestAccount::signer(Pubkey::new_unique());
+    let (config, _) = Pubkey::find_program_address(&[SEED_RATE_CONFIG], program_id);
+    let program_data = program_data(program_id, &operator.key);
+    vec![operator, TestAccount::new(config), TestAccount::new(system_program::id()), program_data]
+}
+
+fn set_rate_limit(program_id: &Pubkey, accounts: &mut [TestAccount], window_slots: u64, max_per_window: u32) -> Result<(), ProgramError> {
+    common::run(program_id, accounts, &RelayIx::SetRateLimit { window_slots, max_per_window })
+}
+
+/// The `Relay` accounts, with the operator's cap set to `CAP` per `WINDOW`.
+fn configured(program_id: &Pubkey) -> Vec<TestAccount> {
+    let mut operator = set_rate_limit_accounts(program_id);
+    set_rate_limit(program_id, &mut operator, WINDOW, CAP).unwrap();
+    common::take_invokes();
+
+    let mut accounts = common::relay_accounts(program_id);
+    accounts[3] = operator.swap_remove(1);
+    accounts
+}
+
+fn relay_at(program_id: &Pubkey, accounts: &mut [TestAccount], slot: u64) -> Result<(), ProgramError> {
+    common::set_slot(slot);
+    let ix = RelayIx::Relay { fee_lamports: 1_000, envelope: common::envelope(1, 10) };
+    common::run(program_id, accounts, &ix)
+}
+
+fn counter(accounts: &[TestAccount]) -> RateLimit {
+    RateLimit::try_from_slice(&accounts[4].data).unwrap()
+}
+
+#[test]
+fn upgrade_authority_creates_config() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = set_rate_limit_accounts(&program_id);
+    set_rate_limit(&program_id, &mut accounts, WINDOW, CAP).unwrap();
+    assert_eq!(accounts[1].owner, program_id);
+    assert_eq!(
+        RateLimitConfig::try_from_slice(&accounts[1].data).unwrap(),
+        RateLimitConfig { treasury: accounts[0].key, window_slots: WINDOW, max_per_window: CAP }
+    );
+}
+
+#[test]
+fn first_config_needs_the_upgrade_authority() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = set_rate_limit_accounts(&program_id);
+    accounts[3] = program_data(&program_id, &Pubkey::new_unique());
+    assert_eq!(set_rate_limit(&program_id, &mut accounts, WINDOW, u32::MAX), Err(ProgramError::Custom(12)));
+
+    // A look-alike ProgramData account does not count either.
+    let mut accounts = set_rate_limit_accounts(&program_id);
+    accounts[3].key = Pubkey::new_unique();
+    assert_eq!(set_rate_limit(&program_id, &mut accounts, WINDOW, u32::MAX), Err(ProgramError::Custom(12)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn only_the_operator_updates_config() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = set_rate_limit_accounts(&program_id);
+    set_rate_limit(&program_id, &mut accounts, WINDOW, CAP).unwrap();
+
+    let operator = accounts[0].clone();
+    accounts[0] = TestAccount::signer(Pubkey::new_unique());
+    assert_eq!(set_rate_limit(&program_id, &mut accounts, WINDOW, u32::MAX), Err(ProgramError::Custom(12)));
+
+    accounts[0] = operator;
+    set_rate_limit(&program_id, &mut accounts[..3], WINDOW * 2, CAP + 1).unwrap();
+    assert_eq!(
+        RateLimitConfig::try_from_slice(&accounts[1].data).unwrap(),
+        RateLimitConfig { treasury: accounts[0].key, window_slots: WINDOW * 2, max_per_window: CAP + 1 }
+    );
+}
+
+#[test]
+fn relays_are_not_counted_without_a_config() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = common::relay_accounts(&program_id);
+    for _ in 0..CAP + 1 {
+        relay_at(&program_id, &mut accounts, 100).unwrap();
+    }
+    assert_eq!(common::take_invokes().len(), 1);
+    assert!(accounts[4].data.is_empty());
+}
+
+#[test]
+fn counter_is_created_on_first_use_and_charged_to_payer() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    relay_at(&program_id, &mut accounts, 100).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 2);
+    assert_eq!(invokes[0].accounts[0].pubkey, accounts[0].key);
+    assert_eq!(invokes[0].accounts[1].pubkey, accounts[4].key);
+    assert_eq!(accounts[4].owner, program_id);
+    assert_eq!(counter(&accounts), RateLimit { window_start: 100, count: 1 });
+}
+
+#[test]
+fn pre_funded_counter_is_still_created() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    // Someone sent lamports to the payer's predictable counter PDA.
+    accounts[4].lamports = 1_000;
+    relay_at(&program_id, &mut accounts, 100).unwrap();
+
+    let kinds: Vec<u8> = common::take_invokes().iter().map(|ix| ix.data[0]).collect();
+    // Rent top-up, allocate, assign, then the fee.
+    assert_eq!(kinds, [2, 8, 1, 2]);
+    assert_eq!(accounts[4].owner, program_id);
+    assert_eq!(counter(&accounts), RateLimit { window_start: 100, count: 1 });
+}
+
+#[test]
+fn cap_is_enforced_within_a_window() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    relay_at(&program_id, &mut accounts, 100).unwrap();
+    relay_at(&program_id, &mut accounts, 105).unwrap();
+    common::take_data();
+    assert_eq!(relay_at(&program_id, &mut accounts, 109), Err(ProgramError::Custom(10)));
+    assert!(common::take_data().is_empty());
+    assert_eq!(counter(&accounts), RateLimit { window_start: 100, count: 2 });
+}
+
+#[test]
+fn other_relay_variants_share_the_cap() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    common::set_slot(100);
+    let topic = RelayIx::RelayWithTopic { fee_lamports: 0, topic: [1; 32], envelope: common::envelope(1, 10) };
+    common::run(&program_id, &mut accounts, &topic).unwrap();
+    let timestamp = RelayIx::RelayWithTimestamp { fee_lamports: 0, envelope: common::envelope(1, 10) };
+    common::run(&program_id, &mut accounts, &timestamp).unwrap();
+    assert_eq!(common::run(&program_id, &mut accounts, &topic), Err(ProgramError::Custom(10)));
+}
+
+#[test]
+fn batch_counts_every_envelope() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    common::set_slot(100);
+    let envelopes = vec![common::envelope(1, 10); CAP as usize + 1];
+    let ix = RelayIx::RelayBatch { fee_lamports: 0, envelopes };
+    assert_eq!(common::run(&program_id, &mut accounts, &ix), Err(ProgramError::Custom(10)));
+
+    let ix = RelayIx::RelayBatch { fee_lamports: 0, envelopes: vec![common::envelope(1, 10); CAP as usize] };
+    common::run(&program_id, &mut accounts, &ix).unwrap();
+    assert_eq!(counter(&accounts), RateLimit { window_start: 100, count: CAP });
+}
+
+#[test]
+fn rate_limited_variant_takes_its_own_account_order() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    // [payer, treasury, config, counter, system]
+    let system = accounts.remove(2);
+    accounts.push(system);
+    common::set_slot(100);
+    let ix = RelayIx::RelayRateLimited { fee_lamports: 0, envelope: common::envelope(1, 10) };
+    common::run(&program_id, &mut accounts, &ix).unwrap();
+    assert_eq!(RateLimit::try_from_slice(&accounts[3].data).unwrap(), RateLimit { window_start: 100, count: 1 });
+}
+
+#[test]
+fn window_resets_after_window_slots() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    relay_at(&program_id, &mut accounts, 100).unwrap();
+    relay_at(&program_id, &mut accounts, 101).unwrap();
+    assert_eq!(relay_at(&program_id, &mut accounts, 109), Err(ProgramError::Custom(10)));
+
+    relay_at(&program_id, &mut accounts, 110).unwrap();
+    assert_eq!(counter(&accounts), RateLimit { window_start: 110, count: 1 });
+    relay_at(&program_id, &mut accounts, 119).unwrap();
+    assert_eq!(relay_at(&program_id, &mut accounts, 119), Err(ProgramError::Custom(10)));
+}
+
+#[test]
+fn counter_of_another_payer_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = configured(&program_id);
+    accounts[4] = TestAccount::new(Pubkey::new_unique());
+    assert_eq!(relay_at(&program_id, &mut accounts, 100), Err(ProgramError::Custom(11)));
+}
+
+#[test]
+fn config_must_be_the_operator_pda() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = common::relay_accounts(&program_id);
+    accounts[3] = TestAccount::new(Pubkey::new_unique());
+    assert_eq!(relay_at(&program_id, &mut accounts, 100), Err(ProgramError::Custom(11)));
+}
+
+#[test]
+fn zero_window_is_rejected() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = set_rate_limit_accounts(&program_id);
+    assert_eq!(set_rate_limit(&program_id, &mut accounts, 0, 1), Err(ProgramError::Custom(11)));
+}
@@ -0,0 +1,105 @@
+mod common;
+
+use common::TestAccount;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use styx_relay_program::{
+    RelayIx, RING_ACCOUNT_LEN, RING_HEADER_LEN, RING_SLOTS, RING_SLOT_LEN, SEED_RING,
+};
+
+const TOPIC: [u8; 32] = [7; 32];
+
+fn accounts(program_id: &Pubkey, topic: &[u8; 32]) -> Vec<TestAccount> {
+    let (ring, _) = Pubkey::find_program_address(&[SEED_RING, topic], program_id);
+    let payer = TestAccount::signer(Pubkey::new_unique());
+    let rate_limit = common::rate_limit_accounts(program_id, &payer.key);
+    let mut accounts =
+        vec![payer, TestAccount::new(Pubkey::new_unique()), TestAccount::new(ring), TestAccount::new(system_program::id())];
+    accounts.extend(rate_limit);
+    accounts
+}
+
+fn store(program_id: &Pubkey, accounts: &mut [TestAccount], envelope: Vec<u8>) -> Result<(), ProgramError> {
+    common::run(program_id, accounts, &RelayIx::RelayStore { topic: TOPIC, fee_lamports: 0, envelope })
+}
+
+fn header(ring: &[u8]) -> (u32, u32, u64) {
+    (
+        u32::from_le_bytes(ring[32..36].try_into().unwrap()),
+        u32::from_le_bytes(ring[36..40].try_into().unwrap()),
+        u64::from_le_bytes(ring[40..48].try_into().unwrap()),
+    )
+}
+
+fn slot(ring: &[u8], index: usize) -> &[u8] {
+    let slot = &ring[RING_HEADER_LEN + index * RING_SLOT_LEN..][..RING_SLOT_LEN];
+    let len = u16::from_le_bytes([slot[0], slot[1]]) as usize;
+    &slot[2..2 + len]
+}
+
+#[test]
+fn first_store_creates_ring_and_logs_envelope() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = accounts(&program_id, &TOPIC);
+
+    let hello = common::envelope(1, 5);
+    store(&program_id, &mut accounts, hello.clone()).unwrap();
+
+    let invokes = common::take_invokes();
+    assert_eq!(invokes.len(), 1);
+    assert_eq!(invokes[0].accounts[1].pubkey, accounts[2].key);
+    assert_eq!(common::take_data(), common::relayed(&hello));
+
+    let ring = &accounts[2];
+    assert_eq!(ring.owner, program_id);
+    assert_eq!(ring.data.len(), RING_ACCOUNT_LEN);
+    assert_eq!(&ring.data[..32], &TOPIC);
+    assert_eq!(header(&ring.data), (1, 1, 1));
+    assert_eq!(slot(&ring.data, 0), hello);
+
+    // Second store reuses the ring without another create_account.
+    store(&program_id, &mut accounts, common::envelope(2, 5)).unwrap();
+    assert!(common::take_invokes().is_empty());
+    assert_eq!(header(&accounts[2].data), (2, 2, 2));
+}
+
+#[test]
+fn ring_wraps_and_overwrites_oldest() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = accounts(&program_id, &TOPIC);
+
+    for i in 0..RING_SLOTS + 2 {
+        store(&program_id, &mut accounts, common::envelope(i as u8, 3 + i)).unwrap();
+    }
+
+    let ring = &accounts[2].data;
+    assert_eq!(header(ring), (2, RING_SLOTS as u32, RING_SLOTS as u64 + 2));
+    assert_eq!(slot(ring, 0), common::envelope(RING_SLOTS as u8, 3 + RING_SLOTS));
+    assert_eq!(slot(ring, 1), common::envelope(RING_SLOTS as u8 + 1, 4 + RING_SLOTS));
+    assert_eq!(slot(ring, 2), common::envelope(2, 5));
+}
+
+#[test]
+fn ring_must_be_topic_pda() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = accounts(&program_id, &[8; 32]);
+    assert_eq!(store(&program_id, &mut accounts, common::envelope(1, 1)), Err(ProgramError::Custom(4)));
+    assert!(common::take_invokes().is_empty());
+}
+
+#[test]
+fn pre_funded_ring_is_still_created() {
+    let program_id = Pubkey::new_unique();
+    let mut accounts = accounts(&program_id, &TOPIC);
+    // Someone sent lamports to the topic's predictable PDA ahead of the first store.
+    accounts[2].lamports = 1_000;
+
+    let hello = common::envelope(1, 5);
+    store(&program_id, &mut accounts, hello.clone()).unwrap();
+
+    let kinds: Vec<u8> = common::take_invokes().iter().map(|ix| ix.data[0]).collect();
+    // Rent top-up, allocate, assign.
+    assert_eq!(kinds, [2, 8, 1]);
+    assert_eq!(accounts[2].owner, program_id);
+    assert_eq!(&accounts[2].data[..32], &TOPIC);
+    assert_eq!(slot(&accounts[2].data, 0), hello);
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::TestAccount;
+use solana_program::{program_error::ProgramError, pubkey::Pubkey, system_program};
+use styx_relay_program::RelayIx;
+
+#[derive(Debug, PartialEq, Eq)]
+enum To {
+    Treasury(u64),
+    Burn(u64),
+}
+
+/// The fee transfers made by a `RelayWithSplit`, in order.
+fn split(fee_lamports: u64, burn_bps: u16) -> Result<Vec<To>, ProgramError> {
+    let program_id = Pubkey::new_unique();
+    let payer = TestAccount::signer(Pubkey::new_unique());
+    let rate_limit = common::rate_limit_accounts(&program_id, &payer.key);
+    let mut accounts = vec![
+        payer,
+        TestAccount::new(Pubkey::new_unique()),
+        TestAccount::new(Pubkey::new_unique()),
+        TestAccount::new(system_program::id()),
+    ];
+    accounts.extend(rate_limit);
+    let ix = RelayIx::RelayWithSplit { fee_lamports, burn_bps, envelope: common::envelope(1, 8) };
+    common::run(&program_id, &mut accounts, &ix)?;
+
+    Ok(common::take_invokes()
+        .into_iter()
+        .map(|ix| {
+            // SystemInstruction::Transfer { lamports }
+            assert_eq!(ix.data[..4], 2u32.to_le_bytes());
+            let lamports = u64::from_le_bytes(ix.data[4..12].try_into().unwrap());
+            if ix.accounts[1].pubkey == accounts[1].key {
+                To::Treasury(lamports)
+            } else {
+                assert_eq!(ix.accounts[1].pubkey, accounts[2].key);
+                To::Burn(lamports)
+            }
+        })
+        .collect())
+}
+
+#[test]
+fn zero_bps_pays_treasury_only() {
+    assert_eq!(split(10_000, 0).unwrap(), vec![To::Treasury(10_000)]);
+}
+
+#[test]
+fn full_bps_pays_burn_only() {
+    assert_eq!(split(10_000, 10_000).unwrap(), vec![To::Burn(10_000)]);
+}
+
+#[test]
+fn burn_share_rounds_down() {
+    // 999 * 3333 / 10_000 = 332.97
+    assert_eq!(split(999, 3_333).unwrap(), vec![To::Burn(332), To::Treasury(667)]);
+}
+
+#[test]
+fn bps_above_10000_is_rejected() {
+    assert_eq!(split(10_000, 10_001), Err(ProgramError::Custom(9)));
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_relay_program::{RelayIx, TIME_FRAME_TAG};
+
+fn run(ix: RelayIx) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    common::run(&program_id, &mut common::relay_accounts(&program_id), &ix)
+}
+
+#[test]
+fn time_frame_precedes_envelope() {
+    common::set_slot(123_456);
+    common::set_unix_timestamp(1_700_000_000);
+    let envelope = common::envelope(1, 16);
+    run(RelayIx::RelayWithTimestamp { fee_lamports: 1, envelope: envelope.clone() }).unwrap();
+
+    assert_eq!(common::take_invokes().len(), 1);
+    let mut expected =
+        vec![vec![TIME_FRAME_TAG.to_vec(), 123_456u64.to_le_bytes().to_vec(), 1_700_000_000i64.to_le_bytes().to_vec()]];
+    expected.extend(common::relayed(&envelope));
+    assert_eq!(common::take_data(), expected);
+}
+
+#[test]
+fn timestamp_relay_still_checks_envelope() {
+    let result = run(RelayIx::RelayWithTimestamp { fee_lamports: 1, envelope: b"junk".to_vec() });
+    assert_eq!(result, Err(ProgramError::Custom(7)));
+    assert!(common::take_invokes().is_empty());
+    assert!(common::take_data().is_empty());
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use styx_relay_program::{RelayIx, TOPIC_FRAME_TAG};
+
+fn run(ix: RelayIx) -> Result<(), ProgramError> {
+    let program_id = Pubkey::new_unique();
+    common::run(&program_id, &mut common::relay_accounts(&program_id), &ix)
+}
+
+#[test]
+fn topic_frame_precedes_envelope() {
+    let envelope = common::envelope(1, 16);
+    run(RelayIx::RelayWithTopic { fee_lamports: 1, topic: [9; 32], envelope: envelope.clone() }).unwrap();
+
+    assert_eq!(common::take_invokes().len(), 1);
+    let mut expected = vec![vec![TOPIC_FRAME_TAG.to_vec(), vec![9; 32]]];
+    expected.extend(common::relayed(&envelope));
+    assert_eq!(common::take_data(), expected);
+}
+
+#[test]
+fn plain_relay_has_no_topic_frame() {
+    let envelope = common::envelope(1, 16);
+    run(RelayIx::Relay { fee_lamports: 0, envelope: envelope.clone() }).unwrap();
+    assert_eq!(common::take_data(), common::relayed(&envelope));
+}
+
+#[test]
+fn relay_encoding_is_unchanged() {
+    // Existing clients encode `Relay` as discriminant 0, fee u64, then the envelope.
+    let data = borsh::to_vec(&RelayIx::Relay { fee_lamports: 7, envelope: vec![1, 2] }).unwrap();
+    assert_eq!(data, [&[0u8][..], &7u64.to_le_bytes(), &2u32.to_le_bytes(), &[1, 2]].concat());
+}
+
+#[test]
+fn topic_relay_still_checks_envelope() {
+    let result = run(RelayIx::RelayWithTopic { fee_lamports: 1, topic: [9; 32], envelope: b"junk".to_vec() });
+    assert_eq!(result, Err(ProgramError::Custom(7)));
+    assert!(common::take_data().is_empty());
+}
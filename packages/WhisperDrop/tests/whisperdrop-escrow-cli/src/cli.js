@@ -72,6 +72,7 @@ const IDL = {
       { name: "manifestHash", type: { array: ["u8", 32] } },
       { name: "merkleRoot", type: { array: ["u8", 32] } },
       { name: "expiryUnix", type: "i64" },
+      { name: "claimDeadlineUnix", type: "i64" },
     ]},
     { name: "deposit", accounts: [
       { name: "authority", isMut: true, isSigner: true },
@@ -108,8 +109,9 @@ function deriveEscrowPda(campaignPda) {
   // matches program: seeds = ["escrow", campaign.key()]
   return PublicKey.findProgramAddressSync([Buffer.from("escrow"), campaignPda.toBuffer()], programId)[0];
 }
-function deriveNullifierPda(campaignPda, recipient) {
-  return PublicKey.findProgramAddressSync([Buffer.from("nullifier"), campaignPda.toBuffer(), recipient.toBuffer()], programId)[0];
+function deriveNullifierPda(campaignPda, recipient, nonce16) {
+  // one per leaf: seeds = ["nullifier", campaign, recipient, nonce_hex_16]
+  return PublicKey.findProgramAddressSync([Buffer.from("nullifier"), campaignPda.toBuffer(), recipient.toBuffer(), nonce16], programId)[0];
 }
 
 const argv = yargs(hideBin(process.argv))
@@ -119,7 +121,8 @@ const argv = yargs(hideBin(process.argv))
     .option("manifestHashB64Url", { type: "string", demandOption: true })
     .option("merkleRootB64Url", { type: "string", demandOption: true })
     .option("mint", { type: "string", demandOption: true })
-    .option("expiry", { type: "number", demandOption: true }))
+    .option("expiry", { type: "number", demandOption: true })
+    .option("claimDeadline", { type: "number", describe: "defaults to expiry (no claim grace)" }))
   .command("deposit", "Deposit to escrow", (y) => y
     .option("campaignPda", { type: "string", demandOption: true })
     .option("fromAta", { type: "string", demandOption: true })
@@ -138,7 +141,6 @@ async function main() {
     const campaignPda = deriveCampaignPda(campaignId32);
     const escrow = deriveEscrowPda(campaignPda);
     const recipient = new PublicKey(plan.recipient);
-    const nullifier = deriveNullifierPda(campaignPda, recipient);
     const mint = new PublicKey(plan.mint || plan.mintPubkey || plan.mintAddress || plan.mint || plan.programMint || plan.mint); // tolerant
     // plan in Step5 didn't include mint; user can provide via env or extend plan; for now require env MINT optional?
     if (!mint) throw new Error("Plan must include mint field (base58). Add it when exporting, or extend plan.json.");
@@ -147,6 +149,7 @@ async function main() {
     const nonce = Buffer.from(plan.nonceHex, "hex");
     if (nonce.length !== 16) throw new Error("nonceHex must be 16 bytes hex");
     const nonceArr = u16FromHex16(nonce);
+    const nullifier = deriveNullifierPda(campaignPda, recipient, nonce);
 
     const proof = (plan.proof || []).map((p) => {
       const b = b64urlToBytes(p);
@@ -190,7 +193,8 @@ async function main() {
       [...campaignId32],
       [...manifestHash32],
       [...merkleRoot32],
-      new anchor.BN(argv.expiry)
+      new anchor.BN(argv.expiry),
+      new anchor.BN(argv.claimDeadline ?? argv.expiry)
     )
       .accounts({
         authority: provider.wallet.publicKey,